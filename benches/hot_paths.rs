@@ -0,0 +1,224 @@
+//! Benchmarks of the paths wallets hit on every sync and send, run with `cargo bench`
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use serde_json::json;
+use sia_rust::encoding::{Encodable, Encoder};
+use sia_rust::spend_policy::{SpendPolicy, UnlockCondition};
+use sia_rust::transaction::{SatisfiedPolicy, SiacoinElement, SiacoinInputV2, SiacoinOutput, StateElement,
+                            V2Transaction};
+use sia_rust::types::{Address, Event, H256};
+use sia_rust::utxo::{select_utxos, SelectionStrategy, SpendableFilter};
+use sia_rust::Keypair;
+
+const INPUT_COUNTS: [usize; 3] = [1, 16, 128];
+const EVENT_COUNTS: [usize; 3] = [10, 100, 1000];
+
+fn keypair(seed: u8) -> Keypair { Keypair::from_private_bytes(&[seed; 32]).unwrap() }
+
+fn utxo(i: usize, address: &Address) -> SiacoinElement {
+    let mut id = [0u8; 32];
+    id[..8].copy_from_slice(&(i as u64).to_le_bytes());
+    SiacoinElement {
+        state_element: StateElement {
+            id: H256(id),
+            leaf_index: i as u64,
+            merkle_proof: Some(vec![H256(id); 16]),
+        },
+        siacoin_output: SiacoinOutput {
+            value: (1_000_000 * (i as u128 + 1)).into(),
+            address: address.clone(),
+        },
+        maturity_height: (i % 300) as u64,
+    }
+}
+
+/// A transaction spending `inputs` standard v1 outputs into two outputs
+fn transaction(inputs: usize) -> V2Transaction {
+    let public_key = keypair(1).public();
+    let policy = SpendPolicy::UnlockConditions(UnlockCondition::standard_unlock(public_key));
+    let address = policy.address();
+    V2Transaction {
+        siacoin_inputs: (0..inputs)
+            .map(|i| SiacoinInputV2 {
+                parent: utxo(i, &address),
+                satisfied_policy: SatisfiedPolicy {
+                    policy: policy.clone(),
+                    signatures: vec![],
+                    preimages: vec![],
+                },
+            })
+            .collect(),
+        siacoin_outputs: vec![
+            SiacoinOutput {
+                value: 1_000_000.into(),
+                address: address.clone(),
+            },
+            SiacoinOutput {
+                value: 2_000_000.into(),
+                address,
+            },
+        ],
+        miner_fee: 10_000.into(),
+        ..Default::default()
+    }
+}
+
+fn event_json(i: usize) -> serde_json::Value {
+    json!({
+        "id": format!("h:{:064x}", i),
+        "index": {
+          "height": 203,
+          "id": "bid:bd04c08bb96203c7f24adf2d405cb1069c7da8573573011379a986be62fc2a29"
+        },
+        "timestamp": "2024-07-18T19:04:16Z",
+        "maturityHeight": 203,
+        "type": "v2Transaction",
+        "data": {
+          "siacoinInputs": [
+            {
+              "parent": {
+                "id": "h:78d58090bcdeaccf22abf99b6e0de25273e9eb82210359a16cefbd743a85fd50",
+                "leafIndex": 421,
+                "merkleProof": [
+                  "h:f26accb7c256e867a9ed62671ebe6c3eb34d085e5266f67073af2daa549f980d",
+                  "h:d39e139147168c70da11c3f6db4fa54d35914ef67ba5654a75107da9c099ddda",
+                  "h:f447a5360e1a7c4cab3062dd1699f56ea642b4f6cc6464fdfca0d1aa15fa436c"
+                ],
+                "siacoinOutput": {
+                  "value": "256394172736732570239334030000",
+                  "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+                },
+                "maturityHeight": 0
+              },
+              "satisfiedPolicy": {
+                "policy": {
+                  "type": "uc",
+                  "policy": {
+                    "timelock": 0,
+                    "publicKeys": [
+                      "ed25519:cecc1507dc1ddd7295951c290888f095adb9044d1b73d696e6df065d683bd4fc"
+                    ],
+                    "signaturesRequired": 1
+                  }
+                },
+                "signatures": [
+                  "sig:c432fea5f147205e49235ddbd75c232fd8e9c7526b2b1575f70653ae2b3c0d0338c7fe710be338482060cf6ef2dea5e2319252fc28deaf70c77a2be60a533400"
+                ]
+              }
+            }
+          ],
+          "siacoinOutputs": [
+            {
+              "value": "10400000000000000000000000000",
+              "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+            },
+            {
+              "value": "245994172736732570239334030000",
+              "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+            }
+          ],
+          "minerFee": "0"
+        }
+    })
+}
+
+fn bench_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_v2_transaction");
+    for inputs in INPUT_COUNTS {
+        let tx = transaction(inputs);
+        let mut encoder = Encoder::default();
+        tx.encode(&mut encoder);
+        group.throughput(Throughput::Bytes(encoder.buffer.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(inputs), &tx, |b, tx| {
+            b.iter(|| {
+                encoder.reset();
+                black_box(tx).encode(&mut encoder);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_sig_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("input_sig_hash");
+    for inputs in INPUT_COUNTS {
+        let tx = transaction(inputs);
+        group.bench_with_input(BenchmarkId::from_parameter(inputs), &tx, |b, tx| {
+            b.iter(|| black_box(tx).input_sig_hash())
+        });
+    }
+    group.finish();
+}
+
+fn bench_address_derivation(c: &mut Criterion) {
+    let public_key = keypair(2).public();
+    c.bench_function("address_from_public_key", |b| {
+        b.iter(|| SpendPolicy::PublicKey(black_box(public_key)).address())
+    });
+    c.bench_function("address_from_unlock_conditions", |b| {
+        b.iter(|| UnlockCondition::standard_unlock(black_box(public_key)).address())
+    });
+    c.bench_function("keypair_from_private_bytes", |b| {
+        b.iter(|| Keypair::from_private_bytes(black_box(&[3; 32])).unwrap().public())
+    });
+}
+
+fn bench_spendable_filter(c: &mut Criterion) {
+    let address = SpendPolicy::PublicKey(keypair(4).public()).address();
+    let mut group = c.benchmark_group("spendable_filter");
+    for count in [100usize, 10_000] {
+        let utxos: Vec<_> = (0..count).map(|i| utxo(i, &address)).collect();
+        let filter = SpendableFilter::new(150);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &utxos, |b, utxos| {
+            b.iter_batched(|| utxos.clone(), |utxos| filter.filter(utxos), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn bench_coin_selection(c: &mut Criterion) {
+    let address = SpendPolicy::PublicKey(keypair(5).public()).address();
+    let utxos: Vec<_> = (0..1000).map(|i| utxo(i, &address)).collect();
+    // not a sum of outputs, so branch and bound explores until it hits its limit of tries
+    let amount = 1_234_567_890u128.into();
+    let mut group = c.benchmark_group("select_utxos");
+    let strategies = [
+        ("largest_first", SelectionStrategy::LargestFirst),
+        ("branch_and_bound", SelectionStrategy::BranchAndBound {
+            tolerance: 100_000.into(),
+        }),
+    ];
+    for (name, strategy) in strategies {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &utxos, |b, utxos| {
+            b.iter_batched(
+                || utxos.clone(),
+                |utxos| select_utxos(utxos, amount, strategy).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_event_deserialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deserialize_events");
+    for count in EVENT_COUNTS {
+        let body = serde_json::to_string(&(0..count).map(event_json).collect::<Vec<_>>()).unwrap();
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &body, |b, body| {
+            b.iter(|| serde_json::from_str::<Vec<Event>>(black_box(body)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_encoding,
+    bench_sig_hash,
+    bench_address_derivation,
+    bench_spendable_filter,
+    bench_coin_selection,
+    bench_event_deserialization
+);
+criterion_main!(benches);
@@ -0,0 +1,245 @@
+use crate::keypair::Seed;
+use crate::types::Address;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Identifies a serialized `EncryptedBackup`
+pub const BACKUP_MAGIC: &str = "sia-rust-backup";
+
+/// Version of the envelope format, ie. the KDF, cipher and layout of `EncryptedBackup`.
+///
+/// Only bumped for changes older readers can not handle. Additions to `WalletBackup` do not bump it.
+pub const BACKUP_VERSION: u16 = 1;
+
+const SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+/// Largest `KdfParams` accepted, so a crafted backup can not make decryption exhaust memory or run for hours
+const MAX_MEMORY_COST: u32 = 4 * 1024 * 1024;
+const MAX_TIME_COST: u32 = 64;
+const MAX_PARALLELISM: u32 = 64;
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("BackupError error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("BackupError error: not a wallet backup")]
+    InvalidMagic,
+    #[error("BackupError error: unsupported backup version {0}, expected at most {BACKUP_VERSION}")]
+    UnsupportedVersion(u16),
+    #[error("BackupError error: invalid backup encoding: {0}")]
+    InvalidEncoding(String),
+    #[error("BackupError error: key derivation failed: {0}")]
+    Kdf(String),
+    #[error("BackupError error: key derivation parameters {0:?} exceed the supported maximum")]
+    KdfParamsTooHigh(KdfParams),
+    #[error("BackupError error: failed to gather randomness: {0}")]
+    Random(#[from] getrandom::Error),
+    #[error("BackupError error: encryption failed")]
+    Encryption,
+    /// The password is wrong or the backup was modified
+    #[error("BackupError error: decryption failed, wrong password or corrupted backup")]
+    Decryption,
+}
+
+/// An address of the wallet and what is known about it
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BackupAddress {
+    pub address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Index the address' key was derived at, `None` for imported keys
+    #[serde(default, rename = "derivationIndex", skip_serializing_if = "Option::is_none")]
+    pub derivation_index: Option<u64>,
+}
+
+/// How far the history of an address has been synced, so a restored wallet can resume instead of rescanning
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct HistoryCursor {
+    /// Height of the last fully processed block
+    pub height: u64,
+    /// Number of events already fetched, ie. the next `offset` for `AddressEventsRequest`
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// The plaintext contents of a wallet backup.
+///
+/// Fields may be added in later crate versions but are never removed or repurposed. New fields must have a
+/// default so older backups still restore. Fields unknown to this version are kept in `extra` and written
+/// back by `encrypt`, so a backup restored and re-exported by an older version loses nothing.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct WalletBackup {
+    /// Seed the wallet's keys are derived from, hex encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<Seed>,
+    /// Hex encoded ed25519 private keys imported independently of `seed`
+    #[serde(default, rename = "importedKeys", skip_serializing_if = "Vec::is_empty")]
+    pub imported_keys: Vec<String>,
+    #[serde(default)]
+    pub addresses: Vec<BackupAddress>,
+    /// Next unused derivation index of `seed`
+    #[serde(default, rename = "nextDerivationIndex")]
+    pub next_derivation_index: u64,
+    /// History cursors by address
+    #[serde(default, rename = "historyCursors")]
+    pub history_cursors: HashMap<Address, HistoryCursor>,
+    #[serde(default, rename = "createdAt", skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// Argon2id parameters used to derive the encryption key from the password.
+///
+/// Memory cost is capped at 4 GiB, time cost and parallelism at 64.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    #[serde(rename = "memoryCost")]
+    pub memory_cost: u32,
+    #[serde(rename = "timeCost")]
+    pub time_cost: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_cost: 64 * 1024,
+            time_cost: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; KEY_LENGTH]>, BackupError> {
+        if self.memory_cost > MAX_MEMORY_COST || self.time_cost > MAX_TIME_COST || self.parallelism > MAX_PARALLELISM {
+            return Err(BackupError::KdfParamsTooHigh(*self));
+        }
+        let params = Params::new(self.memory_cost, self.time_cost, self.parallelism, Some(KEY_LENGTH))
+            .map_err(|e| BackupError::Kdf(e.to_string()))?;
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut *key)
+            .map_err(|e| BackupError::Kdf(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// The serialized backup artifact.
+///
+/// A JSON object holding everything needed to decrypt it besides the password. The header fields are
+/// authenticated along with the ciphertext, so they can not be altered without failing decryption.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EncryptedBackup {
+    pub magic: String,
+    pub version: u16,
+    pub kdf: KdfParams,
+    /// Base64 encoded
+    pub salt: String,
+    /// Base64 encoded XChaCha20-Poly1305 nonce
+    pub nonce: String,
+    /// Base64 encoded XChaCha20-Poly1305 ciphertext of the JSON encoded `WalletBackup`
+    pub ciphertext: String,
+}
+
+impl EncryptedBackup {
+    /// Associated data binding the header to the ciphertext
+    fn header_aad(&self) -> Result<Vec<u8>, BackupError> {
+        let header = (&self.magic, self.version, &self.kdf, &self.salt, &self.nonce);
+        Ok(serde_json::to_vec(&header)?)
+    }
+
+    pub fn decrypt(&self, password: &[u8]) -> Result<WalletBackup, BackupError> {
+        if self.magic != BACKUP_MAGIC {
+            return Err(BackupError::InvalidMagic);
+        }
+        if self.version > BACKUP_VERSION {
+            return Err(BackupError::UnsupportedVersion(self.version));
+        }
+
+        let decode = |s: &str| {
+            BASE64
+                .decode(s)
+                .map_err(|e| BackupError::InvalidEncoding(e.to_string()))
+        };
+        let salt = decode(&self.salt)?;
+        let nonce = decode(&self.nonce)?;
+        let ciphertext = decode(&self.ciphertext)?;
+        if nonce.len() != 24 {
+            return Err(BackupError::InvalidEncoding(format!(
+                "invalid nonce length {}",
+                nonce.len()
+            )));
+        }
+
+        let key = self.kdf.derive_key(password, &salt)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&*key));
+        let aad = self.header_aad()?;
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(&nonce), Payload {
+                msg: &ciphertext,
+                aad: &aad,
+            })
+            .map(Zeroizing::new)
+            .map_err(|_| BackupError::Decryption)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+impl WalletBackup {
+    /// Encrypt the backup with a key derived from `password` using the default `KdfParams`
+    pub fn encrypt(&self, password: &[u8]) -> Result<EncryptedBackup, BackupError> {
+        self.encrypt_with_params(password, KdfParams::default())
+    }
+
+    pub fn encrypt_with_params(&self, password: &[u8], kdf: KdfParams) -> Result<EncryptedBackup, BackupError> {
+        let mut salt = [0u8; SALT_LENGTH];
+        getrandom::getrandom(&mut salt)?;
+        let mut nonce = [0u8; 24];
+        getrandom::getrandom(&mut nonce)?;
+
+        let mut backup = EncryptedBackup {
+            magic: BACKUP_MAGIC.to_owned(),
+            version: BACKUP_VERSION,
+            kdf,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: String::new(),
+        };
+
+        let key = kdf.derive_key(password, &salt)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&*key));
+        let plaintext = Zeroizing::new(serde_json::to_vec(self)?);
+        let aad = backup.header_aad()?;
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), Payload {
+                msg: &plaintext,
+                aad: &aad,
+            })
+            .map_err(|_| BackupError::Encryption)?;
+        backup.ciphertext = BASE64.encode(ciphertext);
+        Ok(backup)
+    }
+
+    /// Serialize and encrypt the backup into its JSON artifact
+    pub fn backup(&self, password: &[u8]) -> Result<String, BackupError> {
+        Ok(serde_json::to_string(&self.encrypt(password)?)?)
+    }
+
+    /// Decrypt a JSON artifact produced by `backup`
+    pub fn restore(artifact: &str, password: &[u8]) -> Result<Self, BackupError> {
+        let backup: EncryptedBackup = serde_json::from_str(artifact)?;
+        backup.decrypt(password)
+    }
+}
@@ -6,6 +6,7 @@ use std::convert::From;
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::str::FromStr;
+use thiserror::Error;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "String")]
@@ -41,7 +42,7 @@ impl Encoder {
 
     /// writes a length-prefixed []byte to the underlying stream.
     pub fn write_len_prefixed_bytes(&mut self, data: &[u8]) {
-        self.buffer.extend_from_slice(&data.len().to_le_bytes());
+        self.write_u64(data.len() as u64);
         self.buffer.extend_from_slice(data);
     }
 
@@ -81,6 +82,95 @@ pub trait Encodable {
     fn encode(&self, encoder: &mut Encoder);
 }
 
+#[derive(Debug, Error, PartialEq)]
+pub enum DecodingError {
+    #[error("DecodingError error: expected {needed} more bytes, {remaining} remaining")]
+    UnexpectedEnd { needed: u64, remaining: usize },
+    #[error("DecodingError error: invalid bool {0}")]
+    InvalidBool(u8),
+    #[error("DecodingError error: {0}")]
+    InvalidValue(String),
+    #[error("DecodingError error: {0} trailing bytes")]
+    TrailingBytes(usize),
+}
+
+/// Reads the canonical encoding written by `Encoder`, the equivalent of Sia Core's `types.Decoder`
+pub struct Decoder<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self { Decoder { buffer } }
+
+    /// Decode a single `T` that must span all of `buffer`
+    pub fn decode_all<T: Decodable>(buffer: &[u8]) -> Result<T, DecodingError> {
+        let mut decoder = Decoder::new(buffer);
+        let item = T::decode(&mut decoder)?;
+        match decoder.remaining() {
+            0 => Ok(item),
+            remaining => Err(DecodingError::TrailingBytes(remaining)),
+        }
+    }
+
+    pub fn remaining(&self) -> usize { self.buffer.len() }
+
+    pub fn read_slice(&mut self, len: u64) -> Result<&'a [u8], DecodingError> {
+        let end = DecodingError::UnexpectedEnd {
+            needed: len,
+            remaining: self.buffer.len(),
+        };
+        if len > self.buffer.len() as u64 {
+            return Err(end);
+        }
+        let (slice, rest) = self.buffer.split_at(len as usize);
+        self.buffer = rest;
+        Ok(slice)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], DecodingError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_slice(N as u64)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, DecodingError> { Ok(self.read_array::<1>()?[0]) }
+
+    pub fn read_u64(&mut self) -> Result<u64, DecodingError> { Ok(u64::from_le_bytes(self.read_array()?)) }
+
+    pub fn read_u128(&mut self) -> Result<u128, DecodingError> { Ok(u128::from_le_bytes(self.read_array()?)) }
+
+    pub fn read_bool(&mut self) -> Result<bool, DecodingError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(DecodingError::InvalidBool(b)),
+        }
+    }
+
+    /// reads a length-prefixed []byte written by `Encoder::write_len_prefixed_bytes`
+    pub fn read_len_prefixed_bytes(&mut self) -> Result<Vec<u8>, DecodingError> {
+        let len = self.read_u64()?;
+        Ok(self.read_slice(len)?.to_vec())
+    }
+
+    // equivalent of Sia Core's DecodeSlice()
+    pub fn read_len_prefixed_vec<T: Decodable>(&mut self) -> Result<Vec<T>, DecodingError> {
+        let len = self.read_u64()?;
+        // every item takes at least one byte, so a length beyond the input is rejected before allocating for it
+        if len > self.buffer.len() as u64 {
+            return Err(DecodingError::UnexpectedEnd {
+                needed: len,
+                remaining: self.buffer.len(),
+            });
+        }
+        (0..len).map(|_| T::decode(self)).collect()
+    }
+}
+
+pub trait Decodable: Sized {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError>;
+}
+
 /// This wrapper allows us to use Signature internally but still serde as "sig:" prefixed string
 #[derive(Debug)]
 pub struct PrefixedSignature(pub Signature);
@@ -313,6 +403,10 @@ impl Encodable for H256 {
     fn encode(&self, encoder: &mut Encoder) { encoder.write_slice(&self.0); }
 }
 
+impl Decodable for H256 {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> { Ok(H256(decoder.read_array()?)) }
+}
+
 #[test]
 fn test_encoder_default_hash() {
     assert_eq!(
@@ -0,0 +1,105 @@
+use crate::http::client::{ApiClient, ApiClientError};
+use crate::http::endpoints::{RouteProbeRequest, StateRequest};
+use http::StatusCode;
+
+/// Body returned by walletd's router for paths it does not serve. Handlers return 404 for missing
+/// resources as well, so the body is what distinguishes an unknown route.
+const ROUTE_NOT_FOUND_BODY: &str = "404 page not found";
+
+const PROBE_V2_CONSTRUCT: &str = "api/wallets/0/construct/v2/transaction";
+const PROBE_BATCH_EVENTS: &str = "api/batch/addresses/events";
+
+/// Optional features of the connected server, see `ApiClientHelpers::capabilities`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    /// The daemon version reported by `api/state`, if the server exposes it
+    pub version: Option<String>,
+    /// Wallet-side construction of v2 transactions via `POST /wallets/:id/construct/v2/transaction`
+    pub v2_construct: bool,
+    /// Fetching events of many addresses at once via `POST /batch/addresses/events`
+    pub batch_events: bool,
+    /// Push notifications over a websocket. No walletd release exposes one yet, so this is always `false`
+    /// and exists for callers to branch on rather than assuming polling.
+    pub websocket: bool,
+}
+
+pub(crate) fn is_route_not_found(error: &ApiClientError) -> bool {
+    match error.http_status() {
+        Some((status, body)) => status == StatusCode::NOT_FOUND && body.trim() == ROUTE_NOT_FOUND_BODY,
+        None => false,
+    }
+}
+
+/// Interpret the result of a `RouteProbeRequest`
+///
+/// Only the router's own 404 means the route is unknown. Any other response, including errors from the
+/// handler or `405 Method Not Allowed`, means the server routes the path. Errors indicating the node
+/// itself failed are returned as is, since they say nothing about the route.
+pub fn route_supported<T>(result: Result<T, ApiClientError>) -> Result<bool, ApiClientError> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) if is_route_not_found(&e) => Ok(false),
+        Err(e) if e.is_node_failure() => Err(e),
+        Err(_) => Ok(true),
+    }
+}
+
+async fn probe<C: ApiClient + Sync>(client: &C, path: &str) -> Result<bool, ApiClientError> {
+    let request = RouteProbeRequest { path: path.to_owned() };
+    route_supported(client.dispatcher(request).await)
+}
+
+pub(crate) async fn probe_capabilities<C: ApiClient + Sync>(client: &C) -> Result<Capabilities, ApiClientError> {
+    // older daemons do not serve api/state; treat its absence as an unknown version
+    let version = match client.dispatcher(StateRequest).await {
+        Ok(state) => Some(state.version),
+        Err(e) if e.is_node_failure() => return Err(e),
+        Err(_) => None,
+    };
+    Ok(Capabilities {
+        version,
+        v2_construct: probe(client, PROBE_V2_CONSTRUCT).await?,
+        batch_events: probe(client, PROBE_BATCH_EVENTS).await?,
+        websocket: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_err(status: StatusCode, body: &str) -> Result<(), ApiClientError> {
+        Err(ApiClientError::UnexpectedHttpStatus {
+            status,
+            body: body.to_owned(),
+        })
+    }
+
+    #[test]
+    fn test_route_supported() {
+        assert!(route_supported(Ok(())).unwrap());
+        assert!(!route_supported(status_err(StatusCode::NOT_FOUND, "404 page not found\n")).unwrap());
+        // handler level 404, eg. unknown wallet ID
+        assert!(route_supported(status_err(StatusCode::NOT_FOUND, "wallet not found")).unwrap());
+        assert!(route_supported(status_err(StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed\n")).unwrap());
+        assert!(route_supported(status_err(StatusCode::BAD_REQUEST, "invalid wallet ID")).unwrap());
+    }
+
+    #[test]
+    fn test_route_supported_with_endpoint() {
+        let result = status_err(StatusCode::NOT_FOUND, "404 page not found\n").map_err(|e| e.with_endpoint("/api/foo"));
+        assert!(!route_supported(result).unwrap());
+    }
+
+    #[test]
+    fn test_route_supported_node_failure() {
+        let result = route_supported(status_err(StatusCode::BAD_GATEWAY, ""));
+        assert!(matches!(
+            result,
+            Err(ApiClientError::UnexpectedHttpStatus {
+                status: StatusCode::BAD_GATEWAY,
+                ..
+            })
+        ));
+    }
+}
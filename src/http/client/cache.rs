@@ -0,0 +1,253 @@
+use crate::http::client::{EndpointSchema, SchemaMethod};
+use crate::http::endpoints::SiaApiRequest;
+use core::time::Duration;
+use instant::Instant;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_MAX_ENTRIES: usize = 512;
+const DEFAULT_TIP_TTL_MS: u64 = 1000;
+const DEFAULT_CONFIRMED_TTL_SECS: u64 = 600;
+
+/// How long the response to a request may be served from a `ResponseCache`, see `SiaApiRequest::cache_policy`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CachePolicy {
+    /// Always sent to the node
+    NoCache,
+    /// Never changes once returned, eg. a block by its ID or the network parameters
+    Immutable,
+    /// Only changes if a reorg reverts it, eg. a confirmed event. Kept for `ResponseCacheConf::confirmed_ttl`.
+    Confirmed,
+    /// Follows the tip of the chain, kept for `ResponseCacheConf::tip_ttl`
+    Tip,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponseCacheConf {
+    /// Number of responses kept before the oldest entry is evicted
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// Milliseconds responses following the tip are served from the cache
+    #[serde(default = "default_tip_ttl")]
+    pub tip_ttl: u64,
+    /// Seconds responses only a reorg can change are served from the cache, so a reorg reverting them, eg. one
+    /// watched for by `wait_for_confirmations`, is noticed up to this much later
+    #[serde(default = "default_confirmed_ttl")]
+    pub confirmed_ttl: u64,
+}
+
+fn default_max_entries() -> usize { DEFAULT_MAX_ENTRIES }
+
+fn default_tip_ttl() -> u64 { DEFAULT_TIP_TTL_MS }
+
+fn default_confirmed_ttl() -> u64 { DEFAULT_CONFIRMED_TTL_SECS }
+
+impl Default for ResponseCacheConf {
+    fn default() -> Self {
+        ResponseCacheConf {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            tip_ttl: DEFAULT_TIP_TTL_MS,
+            confirmed_ttl: DEFAULT_CONFIRMED_TTL_SECS,
+        }
+    }
+}
+
+/// Where the response to a cacheable request is kept, see `ResponseCache::slot`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheSlot {
+    key: String,
+    policy: CachePolicy,
+}
+
+#[derive(Debug)]
+struct CachedBody {
+    body: String,
+    /// `None` for immutable responses
+    expires: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    bodies: HashMap<String, CachedBody>,
+    /// Insertion order of `bodies`, oldest first
+    order: VecDeque<String>,
+}
+
+/// In-memory cache of response bodies of GET requests, keyed by their endpoint schema.
+///
+/// Only requests whose `SiaApiRequest::cache_policy` allows it are cached, each for as long as its policy
+/// permits. Clients answer cached requests without contacting the node, so retries, failover and `Metrics` do not
+/// apply to them. Unlike `EtagCache` the key does not include the node, as every node of a client is expected to
+/// follow the same chain. Clones share the same entries.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    max_entries: usize,
+    tip_ttl: Duration,
+    confirmed_ttl: Duration,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self { ResponseCache::from(ResponseCacheConf::default()) }
+}
+
+impl From<ResponseCacheConf> for ResponseCache {
+    fn from(conf: ResponseCacheConf) -> Self {
+        ResponseCache {
+            max_entries: conf.max_entries.max(1),
+            tip_ttl: Duration::from_millis(conf.tip_ttl),
+            confirmed_ttl: Duration::from_secs(conf.confirmed_ttl),
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+}
+
+/// Path template, path params and query params of `schema`, the params sorted by name
+fn cache_key(schema: &EndpointSchema) -> String {
+    let mut key = schema.path_schema.clone();
+    for params in [&schema.path_params, &schema.query_params].iter() {
+        let mut params: Vec<_> = params.iter().flatten().collect();
+        params.sort();
+        key.push('|');
+        for (name, value) in params {
+            key.push_str(&format!("{}={};", name, value));
+        }
+    }
+    key
+}
+
+impl ResponseCache {
+    /// The slot the response to `request` is cached in, `None` if it must not be cached
+    pub fn slot<R: SiaApiRequest>(request: &R) -> Option<CacheSlot> {
+        let policy = request.cache_policy();
+        if policy == CachePolicy::NoCache {
+            return None;
+        }
+        let schema = request.to_endpoint_schema().ok()?;
+        if !matches!(schema.method, SchemaMethod::Get) {
+            return None;
+        }
+        Some(CacheSlot {
+            key: cache_key(&schema),
+            policy,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> { self.entries.lock().expect("response cache mutex poisoned") }
+
+    /// The cached body of `slot`, unless it expired
+    pub fn get(&self, slot: &CacheSlot) -> Option<String> {
+        let mut entries = self.lock();
+        let expired = match entries.bodies.get(&slot.key)? {
+            CachedBody {
+                expires: Some(expires), ..
+            } => Instant::now() >= *expires,
+            _ => false,
+        };
+        if expired {
+            entries.bodies.remove(&slot.key);
+            entries.order.retain(|key| key != &slot.key);
+            return None;
+        }
+        entries.bodies.get(&slot.key).map(|cached| cached.body.clone())
+    }
+
+    pub fn store(&self, slot: CacheSlot, body: String) {
+        let ttl = match slot.policy {
+            CachePolicy::NoCache => return,
+            CachePolicy::Immutable => None,
+            CachePolicy::Confirmed => Some(self.confirmed_ttl),
+            CachePolicy::Tip => Some(self.tip_ttl),
+        };
+        let cached = CachedBody {
+            body,
+            expires: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        let mut entries = self.lock();
+        if entries.bodies.insert(slot.key.clone(), cached).is_none() {
+            entries.order.push_back(slot.key);
+        }
+        while entries.order.len() > self.max_entries {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.bodies.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drop every entry, eg. after switching the client to another network
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.bodies.clear();
+        entries.order.clear();
+    }
+
+    pub fn len(&self) -> usize { self.lock().bodies.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::endpoints::{AddressBalanceRequest, ConsensusBlockRequest, ConsensusNetworkRequest,
+                                 ConsensusTipRequest, TxpoolBroadcastRequest};
+    use crate::types::{Address, BlockID, H256};
+
+    fn block(id: u8) -> ConsensusBlockRequest {
+        ConsensusBlockRequest {
+            id: BlockID(H256::from(format!("{:064x}", id).as_str())),
+        }
+    }
+
+    #[test]
+    fn test_response_cache_slot() {
+        assert!(ResponseCache::slot(&ConsensusNetworkRequest).is_some());
+        assert_eq!(ResponseCache::slot(&block(1)), ResponseCache::slot(&block(1)));
+        assert_ne!(ResponseCache::slot(&block(1)), ResponseCache::slot(&block(2)));
+
+        let balance = AddressBalanceRequest {
+            address: Address(H256::default()),
+        };
+        assert!(ResponseCache::slot(&balance).is_none());
+        let broadcast = TxpoolBroadcastRequest {
+            transactions: vec![],
+            v2transactions: vec![],
+        };
+        assert!(ResponseCache::slot(&broadcast).is_none());
+    }
+
+    #[test]
+    fn test_response_cache_expiry() {
+        let cache = ResponseCache::from(ResponseCacheConf {
+            tip_ttl: 0,
+            ..Default::default()
+        });
+        let block = ResponseCache::slot(&block(1)).unwrap();
+        let tip = ResponseCache::slot(&ConsensusTipRequest).unwrap();
+        cache.store(block.clone(), "{}".into());
+        cache.store(tip.clone(), "{}".into());
+
+        assert_eq!(cache.get(&block).as_deref(), Some("{}"));
+        assert!(cache.get(&tip).is_none());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_response_cache_evicts_oldest() {
+        let cache = ResponseCache::from(ResponseCacheConf {
+            max_entries: 2,
+            ..Default::default()
+        });
+        for id in 1..=3 {
+            cache.store(ResponseCache::slot(&block(id)).unwrap(), "{}".into());
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&ResponseCache::slot(&block(1)).unwrap()).is_none());
+        assert!(cache.get(&ResponseCache::slot(&block(3)).unwrap()).is_some());
+    }
+}
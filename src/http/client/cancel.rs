@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// Wakers of the pending `Cancelled` futures, keyed per future so each removes its own when dropped
+#[derive(Debug, Default)]
+struct Wakers {
+    next_key: u64,
+    by_key: HashMap<u64, Waker>,
+}
+
+/// Cooperative cancellation of requests, see `RequestOpts::cancel`.
+///
+/// Clones share the same state, so a token can be handed to every request of a long operation, eg. a scan, and
+/// cancelled from elsewhere to abort all of them. Once cancelled a token stays cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self { CancellationToken::default() }
+
+    /// Cancel every request using this token. Requests in flight fail with `ApiClientError::Cancelled` at their
+    /// next poll and later ones are not sent.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().expect("cancellation mutex poisoned").by_key);
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool { self.inner.cancelled.load(Ordering::SeqCst) }
+
+    /// Resolves once the token is cancelled
+    pub fn cancelled(&self) -> Cancelled<'_> { Cancelled { token: self, key: None } }
+}
+
+/// Future returned by `CancellationToken::cancelled`
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    /// Key of this future's waker in `Inner::wakers`, set once it was polled
+    key: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if this.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = this.token.inner.wakers.lock().expect("cancellation mutex poisoned");
+        // `cancel` may have run before the lock was taken, in which case it already drained the wakers
+        if this.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let key = match this.key {
+            Some(key) => key,
+            None => {
+                let key = wakers.next_key;
+                wakers.next_key += 1;
+                this.key = Some(key);
+                key
+            },
+        };
+        match wakers.by_key.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => (),
+            Some(waker) => *waker = cx.waker().clone(),
+            None => {
+                wakers.by_key.insert(key, cx.waker().clone());
+            },
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            if let Ok(mut wakers) = self.token.inner.wakers.lock() {
+                wakers.by_key.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        assert!(!token.is_cancelled());
+
+        let clone = token.clone();
+        let waiter = tokio::spawn(async move { clone.cancelled().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter woken")
+            .unwrap();
+
+        assert!(token.is_cancelled());
+        token.cancelled().await;
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_dropped_removes_waker() {
+        let token = CancellationToken::new();
+        for _ in 0..3 {
+            let pending = tokio::time::timeout(Duration::from_millis(1), token.cancelled()).await;
+            assert!(pending.is_err());
+        }
+        assert!(token.inner.wakers.lock().unwrap().by_key.is_empty());
+    }
+}
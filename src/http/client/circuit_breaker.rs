@@ -0,0 +1,207 @@
+use crate::http::client::ApiClientError;
+use core::time::Duration;
+use instant::Instant;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 30;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CircuitBreakerConf {
+    /// Number of consecutive failures before requests to a node are short-circuited
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds to wait before letting a single probe request through to a failing node
+    #[serde(default = "default_probe_interval")]
+    pub probe_interval: u64,
+}
+
+fn default_failure_threshold() -> u32 { DEFAULT_FAILURE_THRESHOLD }
+
+fn default_probe_interval() -> u64 { DEFAULT_PROBE_INTERVAL_SECS }
+
+impl Default for CircuitBreakerConf {
+    fn default() -> Self {
+        CircuitBreakerConf {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            probe_interval: DEFAULT_PROBE_INTERVAL_SECS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircuitState {
+    /// Requests flow normally; `failures` counts consecutive failures
+    Closed { failures: u32 },
+    /// Requests are rejected until `until`
+    Open { until: Instant },
+    /// A single probe request was let through at `since`; its outcome decides the next state
+    HalfOpen { since: Instant },
+}
+
+impl Default for CircuitState {
+    fn default() -> Self { CircuitState::Closed { failures: 0 } }
+}
+
+/// Tracks the health of each node by base URL and short-circuits requests to nodes that keep failing.
+///
+/// After `failure_threshold` consecutive failures the circuit opens and requests fail immediately with
+/// `ApiClientError::CircuitOpen`. Once `probe_interval` has elapsed, one request is let through to probe the node;
+/// success closes the circuit while failure opens it again. Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    probe_interval: Duration,
+    states: Arc<Mutex<HashMap<Url, CircuitState>>>,
+}
+
+impl From<CircuitBreakerConf> for CircuitBreaker {
+    fn from(conf: CircuitBreakerConf) -> Self {
+        CircuitBreaker::new(conf.failure_threshold, Duration::from_secs(conf.probe_interval))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, probe_interval: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            probe_interval,
+            states: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn state(&self, url: &Url) -> CircuitState {
+        let states = self.states.lock().expect("circuit breaker mutex poisoned");
+        states.get(url).copied().unwrap_or_default()
+    }
+
+    /// Check whether a request may be sent to `url`, moving an expired open circuit to half-open
+    pub fn allow(&self, url: &Url) -> Result<(), ApiClientError> {
+        let mut states = self.states.lock().expect("circuit breaker mutex poisoned");
+        let state = states.entry(url.clone()).or_default();
+        let now = Instant::now();
+        let retry_after = match *state {
+            CircuitState::Closed { .. } => return Ok(()),
+            CircuitState::Open { until } if now >= until => {
+                *state = CircuitState::HalfOpen { since: now };
+                return Ok(());
+            },
+            CircuitState::Open { until } => until - now,
+            // the probe may have been dropped without an outcome; allow another after a full interval
+            CircuitState::HalfOpen { since } if now >= since + self.probe_interval => {
+                *state = CircuitState::HalfOpen { since: now };
+                return Ok(());
+            },
+            CircuitState::HalfOpen { since } => since + self.probe_interval - now,
+        };
+        Err(ApiClientError::CircuitOpen {
+            url: url.clone(),
+            retry_after,
+        })
+    }
+
+    pub fn record_success(&self, url: &Url) {
+        let mut states = self.states.lock().expect("circuit breaker mutex poisoned");
+        states.insert(url.clone(), CircuitState::Closed { failures: 0 });
+    }
+
+    pub fn record_failure(&self, url: &Url) {
+        let mut states = self.states.lock().expect("circuit breaker mutex poisoned");
+        let state = states.entry(url.clone()).or_default();
+        let open = CircuitState::Open {
+            until: Instant::now() + self.probe_interval,
+        };
+        *state = match *state {
+            CircuitState::Closed { failures } if failures + 1 < self.failure_threshold => {
+                CircuitState::Closed { failures: failures + 1 }
+            },
+            CircuitState::Closed { .. } | CircuitState::HalfOpen { .. } => open,
+            // a request sent before the circuit opened failed late; keep the current window
+            CircuitState::Open { until } => CircuitState::Open { until },
+        };
+    }
+
+    /// Record the outcome of a request to `url`. Only errors indicating an unhealthy node are counted as failures.
+    pub fn record<T>(&self, url: &Url, result: &Result<T, ApiClientError>) {
+        match result {
+            Ok(_) => self.record_success(url),
+            Err(e) if e.is_node_failure() => self.record_failure(url),
+            // the node responded, so it is reachable even though the request was rejected
+            Err(_) => self.record_success(url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    fn url() -> Url { Url::parse("http://127.0.0.1:9980/").unwrap() }
+
+    #[test]
+    fn test_circuit_breaker_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        let url = url();
+
+        breaker.record_failure(&url);
+        breaker.record_failure(&url);
+        assert_eq!(breaker.state(&url), CircuitState::Closed { failures: 2 });
+        breaker.allow(&url).unwrap();
+
+        breaker.record_failure(&url);
+        assert!(matches!(breaker.state(&url), CircuitState::Open { .. }));
+        match breaker.allow(&url) {
+            Err(ApiClientError::CircuitOpen { url: err_url, .. }) => assert_eq!(err_url, url),
+            other => panic!("expected CircuitOpen, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker_success_resets_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        let url = url();
+
+        breaker.record_failure(&url);
+        breaker.record_success(&url);
+        breaker.record_failure(&url);
+        assert_eq!(breaker.state(&url), CircuitState::Closed { failures: 1 });
+    }
+
+    #[test]
+    fn test_circuit_breaker_half_open_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        let url = url();
+
+        breaker.record_failure(&url);
+        assert!(breaker.allow(&url).is_err());
+
+        sleep(Duration::from_millis(30));
+        // a single probe is let through
+        breaker.allow(&url).unwrap();
+        assert!(matches!(breaker.state(&url), CircuitState::HalfOpen { .. }));
+        assert!(breaker.allow(&url).is_err());
+
+        // failed probe reopens the circuit
+        breaker.record_failure(&url);
+        assert!(matches!(breaker.state(&url), CircuitState::Open { .. }));
+
+        sleep(Duration::from_millis(30));
+        breaker.allow(&url).unwrap();
+        breaker.record_success(&url);
+        assert_eq!(breaker.state(&url), CircuitState::Closed { failures: 0 });
+    }
+
+    #[test]
+    fn test_circuit_breaker_tracks_urls_independently() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        let other = Url::parse("http://127.0.0.1:9981/").unwrap();
+
+        breaker.record_failure(&url());
+        assert!(breaker.allow(&url()).is_err());
+        breaker.allow(&other).unwrap();
+    }
+}
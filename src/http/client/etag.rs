@@ -0,0 +1,120 @@
+use crate::http::client::ApiClientError;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Number of URLs kept before the oldest entry is evicted
+const DEFAULT_MAX_ENTRIES: usize = 256;
+
+/// The body of a response along with the ETag the server sent for it
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedResponse {
+    pub etag: String,
+    pub body: String,
+}
+
+impl CachedResponse {
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, ApiClientError> {
+        serde_json::from_str(&self.body).map_err(ApiClientError::Serde)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    responses: HashMap<Url, CachedResponse>,
+    /// Insertion order of `responses`, oldest first
+    order: VecDeque<Url>,
+}
+
+/// Cache of GET response bodies by URL, used to send conditional requests.
+///
+/// Clients send the cached ETag as `If-None-Match` and reuse the cached body when the server, or a caching proxy
+/// in front of it, answers `304 Not Modified`. Clones share the same entries.
+#[derive(Clone, Debug)]
+pub struct EtagCache {
+    max_entries: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl Default for EtagCache {
+    fn default() -> Self { EtagCache::new(DEFAULT_MAX_ENTRIES) }
+}
+
+impl EtagCache {
+    pub fn new(max_entries: usize) -> Self {
+        EtagCache {
+            max_entries: max_entries.max(1),
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> { self.entries.lock().expect("etag cache mutex poisoned") }
+
+    pub fn get(&self, url: &Url) -> Option<CachedResponse> { self.lock().responses.get(url).cloned() }
+
+    pub fn store(&self, url: Url, etag: String, body: String) {
+        let mut entries = self.lock();
+        if entries
+            .responses
+            .insert(url.clone(), CachedResponse { etag, body })
+            .is_none()
+        {
+            entries.order.push_back(url);
+        }
+        while entries.order.len() > self.max_entries {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.responses.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn remove(&self, url: &Url) {
+        let mut entries = self.lock();
+        if entries.responses.remove(url).is_some() {
+            entries.order.retain(|u| u != url);
+        }
+    }
+
+    pub fn len(&self) -> usize { self.lock().responses.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> Url { Url::parse("https://host/").unwrap().join(path).unwrap() }
+
+    #[test]
+    fn test_etag_cache_store() {
+        let cache = EtagCache::default();
+        cache.store(url("api/consensus/tip"), "\"1\"".into(), r#"{"height":1}"#.into());
+        cache.store(url("api/consensus/tip"), "\"2\"".into(), r#"{"height":2}"#.into());
+
+        let cached = cache.get(&url("api/consensus/tip")).unwrap();
+        assert_eq!(cached.etag, "\"2\"");
+        assert_eq!(cached.parse::<serde_json::Value>().unwrap()["height"], 2);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&url("api/state")).is_none());
+
+        cache.remove(&url("api/consensus/tip"));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_etag_cache_evicts_oldest() {
+        let cache = EtagCache::new(2);
+        cache.store(url("a"), "a".into(), "{}".into());
+        cache.store(url("b"), "b".into(), "{}".into());
+        // updating an entry does not refresh its position
+        cache.store(url("a"), "a2".into(), "{}".into());
+        cache.store(url("c"), "c".into(), "{}".into());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&url("a")).is_none());
+        assert!(cache.get(&url("b")).is_some());
+        assert!(cache.get(&url("c")).is_some());
+    }
+}
@@ -4,6 +4,7 @@
 use crate::http::endpoints::{SiaApiRequest};
 use async_trait::async_trait;
 use serde::Deserialize;
+use url::Url;
 use crate::http::client::{ApiClient, ApiClientError, ApiClientHelpers, EndpointSchema};
 
 
@@ -38,11 +39,16 @@ impl ApiClient for ExampleClient {
         /// Add logic for converting the schema into a request
         /// Schema is a standard format for providing a client with the information needed
         /// to create their request type. 
-        todo!(); 
+        todo!();
+    }
+
+    // The base URL of the node the client sends requests to
+    fn base_url(&self) -> &Url {
+        todo!();
     }
 
     // Convert an `SiaApiRequest` into a `Request`
-    fn to_data_request<R: SiaApiRequest>(&self, request: R) -> Result<Self::Request, ApiClientError> {
+    fn to_data_request<R: SiaApiRequest>(&self, request: &R) -> Result<Self::Request, ApiClientError> {
         /// Convert an `SiaApiRequest` into a `Request` that can be executed
         /// SiaApiRequest represents a request-response pair defining data types for input and output
         /// This function converts the request into a format that ExampleClient can execute
@@ -56,8 +62,9 @@ impl ApiClient for ExampleClient {
         todo!();
     }
 
-    // Dispatcher function that converts the request and handles execution
-    async fn dispatcher<R: SiaApiRequest>(&self, request: R) -> Result<R::Response, ApiClientError> {
+    // Converts the request and handles a single execution
+    // `dispatcher` and `dispatcher_with_opts` have default implementations built on top of this
+    async fn dispatch_once<R: SiaApiRequest>(&self, request: &R) -> Result<R::Response, ApiClientError> {
         let request = self.to_data_request(request)?;  // Convert request to data request
 
         // Execute the request
@@ -0,0 +1,267 @@
+//! A read-only `ApiClient` backed by a public explorer running `explored`, eg. SiaScan, so applications can query
+//! balances, the tip and fees without running their own walletd.
+//!
+//! Requests are written against walletd and translated to the explorer's API. Only endpoints the explorer serves
+//! with a compatible response are supported, every other request fails with `ApiClientError::BuildError` without
+//! being sent. Nothing can be broadcast through an explorer.
+use crate::http::client::cache::{CachePolicy, ResponseCache};
+use crate::http::client::circuit_breaker::CircuitBreaker;
+use crate::http::client::failover::NodePool;
+use crate::http::client::instrument::Metrics;
+use crate::http::client::native::{Conf, NativeClient};
+use crate::http::client::retry::RetryPolicy;
+use crate::http::client::throttle::AdaptiveThrottle;
+use crate::http::client::{startup_ping, ApiClient, ApiClientError, ApiClientHelpers, EndpointSchema,
+                          EndpointSchemaBuilder, RequestOpts, SchemaMethod};
+use crate::http::endpoints::SiaApiRequest;
+use crate::types::{ChainIndex, TransactionID};
+use async_trait::async_trait;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// The mainnet explorer API of SiaScan
+pub const SIASCAN_URL: &str = "https://api.siascan.com/";
+
+const ENDPOINT_TRANSACTION_INDICES: &str = "transactions/{txid}/indices";
+
+/// Walletd endpoints the explorer serves with a compatible response, and the explorer's path of each
+const ROUTES: &[(&str, &str)] = &[
+    ("api/addresses/{address}/balance", "addresses/{address}/balance"),
+    ("api/consensus/index/{height}", "consensus/tip/{height}"),
+    ("api/consensus/network", "consensus/network"),
+    ("api/consensus/tip", "consensus/tip"),
+    ("api/consensus/tipstate", "consensus/state"),
+    ("api/txpool/fee", "txpool/fee"),
+    (ENDPOINT_TRANSACTION_INDICES, ENDPOINT_TRANSACTION_INDICES),
+];
+
+/// Translate `schema` to the explorer's API, failing for requests it does not serve
+fn explorer_schema(mut schema: EndpointSchema) -> Result<EndpointSchema, ApiClientError> {
+    if !matches!(schema.method, SchemaMethod::Get) {
+        return Err(ApiClientError::BuildError(format!(
+            "{} is not served by the explorer, which is read-only",
+            schema.path_schema
+        )));
+    }
+    match ROUTES.iter().find(|(walletd, _)| *walletd == schema.path_schema) {
+        Some((_, explorer)) => {
+            schema.path_schema = (*explorer).to_owned();
+            Ok(schema)
+        },
+        None => Err(ApiClientError::BuildError(format!(
+            "{} is not served by the explorer",
+            schema.path_schema
+        ))),
+    }
+}
+
+/// Represents the request-response pair for fetching the blocks a transaction was confirmed in.
+///
+/// # Explorer Endpoint
+/// `GET /transactions/:id/indices`
+///
+/// # Description
+/// Returns the index of every block of the best chain the transaction was included in, empty if it is unconfirmed
+/// or unknown to the explorer. Only served by explorers, see `ExplorerClient::transaction_indices`.
+///
+/// # Response
+/// - The response is a `Vec<ChainIndex>`.
+///   This corresponds to the `[]types.ChainIndex` type in Go.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/explored/blob/master/api/server.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TransactionIndicesRequest {
+    pub txid: TransactionID,
+}
+
+impl SiaApiRequest for TransactionIndicesRequest {
+    type Response = Vec<ChainIndex>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut path_params = HashMap::new();
+        path_params.insert("txid".to_owned(), self.txid.0.to_string());
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_TRANSACTION_INDICES.to_owned(), SchemaMethod::Get)
+                .path_params(path_params)
+                .build(),
+        )
+    }
+
+    // empty until the transaction is confirmed, so follows the tip like the txpool
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Tip }
+
+    fn context_txid(&self) -> Option<TransactionID> { Some(self.txid) }
+}
+
+/// An `ApiClient` sending requests to an explorer instead of walletd. Configured like `NativeClient`, with
+/// `server_url` pointing at the explorer's API, eg. `SIASCAN_URL`.
+#[derive(Clone)]
+pub struct ExplorerClient {
+    inner: NativeClient,
+}
+
+impl ExplorerClient {
+    /// Report a `RequestSample` of every request to `metrics`
+    pub fn with_metrics(self, metrics: std::sync::Arc<dyn Metrics>) -> Self {
+        ExplorerClient {
+            inner: self.inner.with_metrics(metrics),
+        }
+    }
+
+    /// Indices of the blocks `txid` was confirmed in, see `TransactionIndicesRequest`
+    pub async fn transaction_indices(&self, txid: TransactionID) -> Result<Vec<ChainIndex>, ApiClientError> {
+        self.dispatcher(TransactionIndicesRequest { txid }).await
+    }
+}
+
+#[async_trait]
+impl ApiClient for ExplorerClient {
+    type Request = reqwest::Request;
+    type Response = reqwest::Response;
+    type Conf = Conf;
+
+    async fn new(conf: Self::Conf) -> Result<Self, ApiClientError> {
+        let skip_health_check = conf.skip_health_check;
+        // the inner client would ping walletd's path of the tip
+        let inner = NativeClient::new(Conf {
+            skip_health_check: true,
+            ..conf
+        })
+        .await?;
+        let client = ExplorerClient { inner };
+        if !skip_health_check {
+            startup_ping(&client).await?;
+        }
+        Ok(client)
+    }
+
+    fn process_schema(&self, schema: EndpointSchema) -> Result<Self::Request, ApiClientError> {
+        self.inner.process_schema(explorer_schema(schema)?)
+    }
+
+    async fn execute_request(&self, request: Self::Request) -> Result<Self::Response, ApiClientError> {
+        self.inner.execute_request(request).await
+    }
+
+    async fn dispatch_once<R: SiaApiRequest>(&self, request: &R) -> Result<R::Response, ApiClientError> {
+        let cache_slot = self
+            .inner
+            .response_cache
+            .as_ref()
+            .and_then(|_| ResponseCache::slot(request));
+        let data_request = self.to_data_request(request)?;
+        let endpoint = data_request.url().path().to_owned();
+        self.inner
+            .execute_and_parse::<R>(data_request, cache_slot)
+            .await
+            .map_err(|e| e.with_request_context(endpoint, request))
+    }
+
+    fn base_url(&self) -> &Url { self.inner.base_url() }
+
+    fn node_pool(&self) -> Option<&NodePool> { self.inner.node_pool() }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> { self.inner.circuit_breaker() }
+
+    fn throttle(&self) -> Option<&AdaptiveThrottle> { self.inner.throttle() }
+
+    fn slow_request_threshold(&self) -> Option<Duration> { self.inner.slow_request_threshold() }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> { self.inner.retry_policy() }
+
+    fn metrics(&self) -> Option<&dyn Metrics> { self.inner.metrics() }
+
+    fn response_cache(&self) -> Option<&ResponseCache> { self.inner.response_cache() }
+
+    fn default_opts(&self) -> RequestOpts { self.inner.default_opts() }
+}
+
+#[async_trait]
+impl ApiClientHelpers for ExplorerClient {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::endpoints::{TxpoolBroadcastRequest, WalletsRequest};
+    use crate::http::mock::{fixtures, MockWalletd};
+    use crate::types::{Address, Currency, H256};
+    use std::str::FromStr;
+    use wiremock::ResponseTemplate;
+
+    const ADDRESS: &str = "addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f";
+
+    fn json(body: &str) -> ResponseTemplate { ResponseTemplate::new(200).set_body_raw(body, "application/json") }
+
+    /// An explorer serving the tip, fee, a balance and the indices of the zero txid
+    async fn mock_explorer() -> MockWalletd {
+        let mock = MockWalletd::start_empty().await;
+        mock.respond("GET", "/consensus/tip", json(fixtures::CONSENSUS_TIP))
+            .await;
+        mock.respond("GET", "/txpool/fee", json(fixtures::TXPOOL_FEE)).await;
+        let address = Address::from_str(ADDRESS).unwrap();
+        let balance = r#"{"unspentSiacoins":"1000","immatureSiacoins":"10","unspentSiafunds":5}"#;
+        mock.respond(
+            "GET",
+            &format!("/addresses/addr%3A{}/balance", address.str_without_prefix()),
+            json(balance),
+        )
+        .await;
+        let indices = r#"[{"height":200,"id":"bid:bd04c08bb96203c7f24adf2d405cb1069c7da8573573011379a986be62fc2a29"}]"#;
+        mock.respond(
+            "GET",
+            &format!("/transactions/{}/indices", H256::default()),
+            json(indices),
+        )
+        .await;
+        mock
+    }
+
+    #[tokio::test]
+    async fn test_explorer_client() {
+        let mock = mock_explorer().await;
+        let client = ExplorerClient::new(mock.conf()).await.unwrap();
+
+        assert_eq!(client.current_height().await.unwrap(), 203);
+        let balance = client
+            .address_balance(Address::from_str(ADDRESS).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(balance.siacoins, Currency(1000));
+        assert_eq!(balance.immature_siacoins, Currency(10));
+        assert_eq!(balance.siafunds, 5);
+        let indices = client.transaction_indices(TransactionID::default()).await.unwrap();
+        assert_eq!(indices[0].height, 200);
+    }
+
+    #[tokio::test]
+    async fn test_explorer_client_unsupported() {
+        let mock = mock_explorer().await;
+        let client = ExplorerClient::new(mock.conf()).await.unwrap();
+        let sent = mock.server().received_requests().await.unwrap().len();
+
+        let broadcast = TxpoolBroadcastRequest {
+            transactions: vec![],
+            v2transactions: vec![],
+        };
+        assert!(matches!(
+            client.dispatcher(broadcast).await.unwrap_err().inner(),
+            ApiClientError::BuildError(_)
+        ));
+        assert!(matches!(
+            client.dispatcher(WalletsRequest).await.unwrap_err().inner(),
+            ApiClientError::BuildError(_)
+        ));
+        assert_eq!(mock.server().received_requests().await.unwrap().len(), sent);
+    }
+
+    #[tokio::test]
+    async fn test_explorer_client_not_an_explorer() {
+        // walletd serves the tip under `api/`, so the explorer's path is unknown to it
+        let walletd = MockWalletd::start().await;
+        assert!(ExplorerClient::new(walletd.conf()).await.is_err());
+    }
+}
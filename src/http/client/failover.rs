@@ -0,0 +1,236 @@
+use crate::http::client::ApiClientError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Consecutive `5xx` responses after which a node is failed over
+pub const DEFAULT_FAILOVER_THRESHOLD: u32 = 3;
+
+/// Health of a single node as tracked by `NodePool`
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeHealth {
+    pub url: Url,
+    /// Consecutive failed requests, reset by any response that is not a server error
+    pub consecutive_failures: u32,
+    /// Whether requests are currently sent to this node
+    pub active: bool,
+}
+
+/// The walletd nodes a client can send requests to, in order of preference.
+///
+/// Requests go to the active node, initially the first one. The pool moves on to the next node, wrapping around
+/// after the last, as soon as the active node is unreachable or its circuit breaker is open, or after it returned
+/// `failover_threshold` server errors in a row. The request that failed is then sent to the new node by
+/// `ApiClient::dispatcher`, so callers only see an error once every node failed it. Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct NodePool {
+    nodes: Arc<Vec<Url>>,
+    failover_threshold: u32,
+    active: Arc<AtomicUsize>,
+    failures: Arc<Mutex<Vec<u32>>>,
+}
+
+impl NodePool {
+    pub fn new(primary: Url, fallbacks: Vec<Url>, failover_threshold: u32) -> Self {
+        let mut nodes = Vec::with_capacity(fallbacks.len() + 1);
+        nodes.push(primary);
+        for url in fallbacks {
+            if !nodes.contains(&url) {
+                nodes.push(url);
+            }
+        }
+        let failures = vec![0; nodes.len()];
+        NodePool {
+            nodes: Arc::new(nodes),
+            failover_threshold: failover_threshold.max(1),
+            active: Arc::new(AtomicUsize::new(0)),
+            failures: Arc::new(Mutex::new(failures)),
+        }
+    }
+
+    /// A pool of a single node, which is never failed over
+    pub fn single(url: Url) -> Self { NodePool::new(url, Vec::new(), DEFAULT_FAILOVER_THRESHOLD) }
+
+    /// The node requests are currently sent to
+    pub fn active(&self) -> &Url { &self.nodes[self.active.load(Ordering::Acquire)] }
+
+    pub fn nodes(&self) -> &[Url] { &self.nodes }
+
+    pub fn health(&self) -> Vec<NodeHealth> {
+        let failures = self.failures.lock().expect("node pool mutex poisoned");
+        let active = self.active.load(Ordering::Acquire);
+        self.nodes
+            .iter()
+            .zip(failures.iter())
+            .enumerate()
+            .map(|(i, (url, failures))| NodeHealth {
+                url: url.clone(),
+                consecutive_failures: *failures,
+                active: i == active,
+            })
+            .collect()
+    }
+
+    /// Record the outcome of a request to `url`, failing over to the next node if `url` is deemed down
+    pub fn record<T>(&self, url: &Url, result: &Result<T, ApiClientError>) {
+        let index = match self.nodes.iter().position(|node| node == url) {
+            Some(index) => index,
+            None => return,
+        };
+        let mut failures = self.failures.lock().expect("node pool mutex poisoned");
+        let failover = match result.as_ref().map_err(ApiClientError::inner) {
+            Err(ApiClientError::CircuitOpen { .. }) => true,
+            Err(ApiClientError::UnexpectedHttpStatus { status, .. }) | Err(ApiClientError::ApiError { status, .. })
+                if status.is_server_error() =>
+            {
+                failures[index] += 1;
+                failures[index] >= self.failover_threshold
+            },
+            Err(e) if e.is_node_failure() => {
+                failures[index] += 1;
+                true
+            },
+            _ => {
+                failures[index] = 0;
+                false
+            },
+        };
+        if failover && self.nodes.len() > 1 {
+            // requests that were in flight before the pool moved on must not move it again
+            let next = (index + 1) % self.nodes.len();
+            if self
+                .active
+                .compare_exchange(index, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                failures[next] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::http::client::native::NativeClient;
+    use crate::http::client::retry::RetryPolicyConf;
+    use crate::http::client::ApiClient;
+    use crate::http::endpoints::ConsensusTipRequest;
+    use crate::http::mock::MockWalletd;
+    use http::StatusCode;
+    use wiremock::ResponseTemplate;
+
+    fn pool() -> NodePool {
+        NodePool::new(
+            Url::parse("http://primary:9980/").unwrap(),
+            vec![
+                Url::parse("http://fallback1:9980/").unwrap(),
+                Url::parse("http://fallback2:9980/").unwrap(),
+            ],
+            2,
+        )
+    }
+
+    fn server_error() -> Result<(), ApiClientError> {
+        Err(ApiClientError::UnexpectedHttpStatus {
+            status: StatusCode::BAD_GATEWAY,
+            body: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_node_pool_fails_over_after_threshold() {
+        let pool = pool();
+        let primary = pool.active().clone();
+
+        pool.record(&primary, &server_error());
+        assert_eq!(pool.active(), &primary);
+        assert_eq!(pool.health()[0].consecutive_failures, 1);
+
+        pool.record(&primary, &server_error());
+        assert_eq!(pool.active(), &pool.nodes()[1]);
+        let health = pool.health();
+        assert!(!health[0].active);
+        assert!(health[1].active);
+    }
+
+    #[test]
+    fn test_node_pool_rejection_resets_failures() {
+        let pool = pool();
+        let primary = pool.active().clone();
+
+        pool.record(&primary, &server_error());
+        let rejected: Result<(), _> = Err(ApiClientError::UnexpectedHttpStatus {
+            status: StatusCode::BAD_REQUEST,
+            body: String::new(),
+        });
+        pool.record(&primary, &rejected);
+        pool.record(&primary, &server_error());
+        assert_eq!(pool.active(), &primary);
+    }
+
+    #[test]
+    fn test_node_pool_circuit_open_fails_over_and_wraps_around() {
+        let pool = pool();
+        for i in 0..3 {
+            let url = pool.active().clone();
+            assert_eq!(url, pool.nodes()[i]);
+            let open: Result<(), _> = Err(ApiClientError::CircuitOpen {
+                url: url.clone(),
+                retry_after: Default::default(),
+            });
+            pool.record(&url, &open);
+        }
+        assert_eq!(pool.active(), &pool.nodes()[0]);
+    }
+
+    #[test]
+    fn test_node_pool_ignores_stale_failures() {
+        let pool = pool();
+        let primary = pool.active().clone();
+        pool.record(&primary, &server_error());
+        pool.record(&primary, &server_error());
+        // a request sent to the primary before the failover completes late
+        pool.record(&primary, &server_error());
+        assert_eq!(pool.active(), &pool.nodes()[1]);
+    }
+
+    #[test]
+    fn test_node_pool_single_node() {
+        let url = Url::parse("http://primary:9980/").unwrap();
+        let pool = NodePool::single(url.clone());
+        pool.record(&url, &server_error());
+        pool.record(&url, &server_error());
+        pool.record(&url, &server_error());
+        assert_eq!(pool.active(), &url);
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_fails_over() {
+        let route = "/api/consensus/tip";
+        let primary = MockWalletd::start().await;
+        primary.respond("GET", route, ResponseTemplate::new(502)).await;
+        let fallback = MockWalletd::start().await;
+        let mut conf = primary.conf();
+        conf.skip_health_check = true;
+        conf.fallback_urls = vec![fallback.url()];
+        conf.retry = Some(RetryPolicyConf {
+            max_attempts: DEFAULT_FAILOVER_THRESHOLD,
+            base_delay: 1,
+            jitter: false,
+            ..Default::default()
+        });
+        let client = NativeClient::new(conf).await.unwrap();
+
+        // the primary is failed over once its retries reach the threshold, the same request then goes to the fallback
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        assert_eq!(primary.received(route).await, DEFAULT_FAILOVER_THRESHOLD as usize);
+        assert_eq!(fallback.received(route).await, 1);
+        assert_eq!(client.base_url(), &client.node_pool().unwrap().nodes()[1]);
+
+        // the failed over client keeps using the fallback
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        assert_eq!(primary.received(route).await, DEFAULT_FAILOVER_THRESHOLD as usize);
+        assert_eq!(fallback.received(route).await, 2);
+    }
+}
@@ -0,0 +1,148 @@
+//! Observability of the requests a client sends: a `tracing` span per request and the `Metrics` hook
+use crate::http::client::ApiClientError;
+use crate::http::endpoints::SiaApiRequest;
+use core::time::Duration;
+use instant::Instant;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Measurements of one request, taken once it finished, after any retries
+#[derive(Clone, Debug)]
+pub struct RequestSample<'a> {
+    /// HTTP method, eg. `GET`
+    pub method: &'a str,
+    /// Path template of the endpoint, eg. `api/addresses/{address}/events`, so samples can be grouped by it
+    pub endpoint: &'a str,
+    /// Status of the error response, `None` for successful requests and failures without a response
+    pub status: Option<http::StatusCode>,
+    pub ok: bool,
+    /// Time from the start of the first attempt until the result, including retries and their delays
+    pub latency: Duration,
+    /// Attempts made, more than one if the request was retried or failed over
+    pub attempts: u32,
+}
+
+/// Collects measurements of the requests a client sends, eg. into counters and latency histograms.
+///
+/// Set with `NativeClient::with_metrics` or `WasmClient::with_metrics`. Called on the task that dispatched the
+/// request, so implementations should record and return rather than block.
+pub trait Metrics: Send + Sync {
+    fn record_request(&self, sample: &RequestSample<'_>);
+}
+
+/// Run `dispatch`, the attempts of `request` counted in `attempts`, within a span carrying the method and
+/// endpoint, then record the outcome on the span and with `metrics`.
+///
+/// The span is named `walletd_request` and is only emitted with the `tracing` feature.
+pub(crate) async fn observe<R, T, F>(
+    metrics: Option<&dyn Metrics>,
+    request: &R,
+    attempts: &AtomicU32,
+    dispatch: F,
+) -> Result<T, ApiClientError>
+where
+    R: SiaApiRequest,
+    F: Future<Output = Result<T, ApiClientError>>,
+{
+    let (method, endpoint) = match request.to_endpoint_schema() {
+        Ok(schema) => (http::Method::from(schema.method), schema.path_schema),
+        // the request fails to build in `dispatch` as well, so there is nothing to attribute it to
+        Err(_) => (http::Method::GET, String::new()),
+    };
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "walletd_request",
+        method = %method,
+        endpoint = %endpoint,
+        status = tracing::field::Empty,
+        ok = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        attempts = tracing::field::Empty,
+    );
+
+    let started = Instant::now();
+    #[cfg(feature = "tracing")]
+    let result = tracing::Instrument::instrument(dispatch, span.clone()).await;
+    #[cfg(not(feature = "tracing"))]
+    let result = dispatch.await;
+
+    let sample = RequestSample {
+        method: method.as_str(),
+        endpoint: &endpoint,
+        status: result
+            .as_ref()
+            .err()
+            .and_then(|e| e.http_status())
+            .map(|(status, _)| status),
+        ok: result.is_ok(),
+        latency: started.elapsed(),
+        attempts: attempts.load(Ordering::Relaxed),
+    };
+    #[cfg(feature = "tracing")]
+    {
+        if let Some(status) = sample.status {
+            span.record("status", status.as_u16());
+        }
+        span.record("ok", sample.ok);
+        span.record("latency_ms", sample.latency.as_millis() as u64);
+        span.record("attempts", sample.attempts);
+    }
+    if let Some(metrics) = metrics {
+        metrics.record_request(&sample);
+    }
+    result
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::http::client::ApiClient;
+    use crate::http::endpoints::{ConsensusTipRequest, GetEventRequest};
+    use crate::http::mock::MockWalletd;
+    use crate::types::TransactionID;
+    use std::sync::{Arc, Mutex};
+    use wiremock::ResponseTemplate;
+
+    /// Keeps `(method, endpoint, status, ok, attempts)` of every sample
+    #[derive(Default)]
+    struct RecordedSamples(Mutex<Vec<(String, String, Option<u16>, bool, u32)>>);
+
+    impl Metrics for RecordedSamples {
+        fn record_request(&self, sample: &RequestSample<'_>) {
+            self.0.lock().unwrap().push((
+                sample.method.to_owned(),
+                sample.endpoint.to_owned(),
+                sample.status.map(|status| status.as_u16()),
+                sample.ok,
+                sample.attempts,
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let mock = MockWalletd::start().await;
+        mock.respond(
+            "GET",
+            "/api/events/0000000000000000000000000000000000000000000000000000000000000000",
+            ResponseTemplate::new(404).set_body_string("not found"),
+        )
+        .await;
+        let metrics = Arc::new(RecordedSamples::default());
+        let client = mock.client().await.unwrap().with_metrics(metrics.clone());
+
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        client
+            .dispatcher(GetEventRequest {
+                txid: TransactionID::default(),
+            })
+            .await
+            .unwrap_err();
+
+        let samples = metrics.0.lock().unwrap();
+        assert_eq!(*samples, vec![
+            ("GET".to_owned(), "api/consensus/tip".to_owned(), None, true, 1),
+            ("GET".to_owned(), "api/events/{txid}".to_owned(), Some(404), false, 1),
+        ]);
+    }
+}
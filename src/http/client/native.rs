@@ -1,28 +1,386 @@
-use crate::http::endpoints::{ConsensusTipRequest, SiaApiRequest};
+use crate::http::endpoints::SiaApiRequest;
 use async_trait::async_trait;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
-use http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
-use reqwest::Client as ReqwestClient;
+use http::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, ETAG, IF_NONE_MATCH, RETRY_AFTER, USER_AGENT};
+use reqwest::{Certificate, Client as ReqwestClient, Proxy};
 use serde::Deserialize;
 use url::Url;
 
-use crate::http::client::{ApiClient, ApiClientError, ApiClientHelpers, Body as ClientBody, EndpointSchema};
+use crate::http::client::cache::{CacheSlot, ResponseCache, ResponseCacheConf};
+use crate::http::client::circuit_breaker::{CircuitBreaker, CircuitBreakerConf};
+use crate::http::client::etag::EtagCache;
+use crate::http::client::failover::NodePool;
+use crate::http::client::instrument::Metrics;
+use crate::http::client::retry::{RetryPolicy, RetryPolicyConf};
+use crate::http::client::throttle::{AdaptiveThrottle, AdaptiveThrottleConf};
+use crate::http::client::{resolve_nodes, startup_ping, ApiClient, ApiClientError, ApiClientHelpers,
+                          Body as ClientBody, EndpointSchema, RequestOpts};
 use core::time::Duration;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Seconds each request may take if `Conf::timeout` is not set
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
 
 #[derive(Clone)]
 pub struct NativeClient {
     pub client: ReqwestClient,
-    pub base_url: Url,
+    pub nodes: NodePool,
+    pub default_opts: RequestOpts,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub throttle: Option<AdaptiveThrottle>,
+    pub etag_cache: Option<EtagCache>,
+    pub response_cache: Option<ResponseCache>,
+    pub slow_request_threshold: Option<Duration>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub metrics: Option<Arc<dyn Metrics>>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Conf {
     pub server_url: Url,
+    /// Path walletd is served under relative to `server_url`, eg. `sia/walletd` behind a reverse proxy
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// Nodes requests fail over to, in order, when `server_url` is unreachable or keeps failing, see `NodePool`.
+    /// `base_path` applies to each of them.
+    #[serde(default)]
+    pub fallback_urls: Vec<Url>,
     #[serde(default)]
     pub password: Option<String>,
+    /// Timeout in seconds of each individual HTTP request, 10 if not set, see `RequestOpts::timeout`.
+    /// Can be overridden per call via `dispatcher_with_opts`.
     #[serde(default)]
     pub timeout: Option<u64>,
+    /// Total time budget in seconds of each logical operation, see `RequestOpts::deadline`.
+    /// Can be overridden per call via `dispatcher_with_opts`.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// Short-circuit requests to a node after repeated failures, disabled if not set
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConf>,
+    /// Adapt the number of concurrent requests to the node's latency and failures, unlimited if not set
+    #[serde(default)]
+    pub throttle: Option<AdaptiveThrottleConf>,
+    /// Restrict connections to one address family, eg. `ipv4` on hosts with broken IPv6 routes
+    #[serde(default)]
+    pub ip_family: IpFamily,
+    /// Send conditional GET requests and reuse cached bodies on `304 Not Modified`, see `EtagCache`
+    #[serde(default)]
+    pub etag_cache: bool,
+    /// Answer requests for immutable data, eg. blocks by ID, and the recent tip from memory, disabled if not set.
+    /// See `ResponseCache`.
+    #[serde(default)]
+    pub response_cache: Option<ResponseCacheConf>,
+    /// Log requests taking longer than this many milliseconds as warnings through `tracing`, disabled if not set or
+    /// without the `tracing` feature
+    #[serde(default)]
+    pub slow_request_threshold: Option<u64>,
+    /// Retry requests failing with transient errors, each request is attempted once if not set
+    #[serde(default)]
+    pub retry: Option<RetryPolicyConf>,
+    /// Reuse of connections across requests, reqwest's defaults apply to anything not set
+    #[serde(default)]
+    pub pool: PoolConf,
+    /// `User-Agent` header of every request, none is sent if not set
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Additional headers sent with every request, eg. credentials of a reverse proxy in front of walletd
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// HTTP, HTTPS or SOCKS proxy all requests are sent through, eg. `socks5://127.0.0.1:9050`.
+    /// Without it the proxy of the `HTTP_PROXY`/`HTTPS_PROXY` environment variables is used, if any.
+    #[serde(default)]
+    pub proxy: Option<Url>,
+    /// Verification of the node's TLS certificate, eg. for nodes with a self-signed certificate
+    #[serde(default)]
+    pub tls: TlsConf,
+    /// Build the client without pinging the node, so it can be created while the node is down. Connectivity is then
+    /// only verified by the first request or an explicit `ApiClientHelpers::health_check`.
+    #[serde(default)]
+    pub skip_health_check: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct TlsConf {
+    /// Path of a PEM encoded CA certificate trusted in addition to the system's roots
+    #[serde(default)]
+    pub ca_certificate: Option<PathBuf>,
+    /// Accept any certificate, including expired ones and those for other hosts.
+    /// Leaves the connection open to interception, prefer `ca_certificate` wherever possible.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct PoolConf {
+    /// Idle connections kept open per host, unlimited if not set
+    #[serde(default)]
+    pub max_idle_per_host: Option<usize>,
+    /// Seconds an idle connection is kept open before it is closed
+    #[serde(default)]
+    pub idle_timeout: Option<u64>,
+    /// Interval in seconds of TCP keep-alive probes on open connections, disabled if not set
+    #[serde(default)]
+    pub tcp_keepalive: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// Connect to any resolved address, racing IPv6 and IPv4 attempts
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    /// The unspecified local address to bind to, which limits connections to addresses of the same family
+    pub fn local_address(self) -> Option<IpAddr> {
+        match self {
+            IpFamily::Any => None,
+            IpFamily::Ipv4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpFamily::Ipv6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// Builds a `Conf` from its server URL, leaving every option not set at its default.
+/// ```ignore
+/// let client = ClientConfBuilder::new(server_url)
+///     .password("password")
+///     .retry(RetryPolicyConf::default())
+///     .skip_health_check(true)
+///     .connect()
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientConfBuilder {
+    conf: Conf,
+}
+
+impl ClientConfBuilder {
+    pub fn new(server_url: Url) -> Self {
+        ClientConfBuilder {
+            conf: Conf {
+                server_url,
+                base_path: None,
+                fallback_urls: Vec::new(),
+                password: None,
+                timeout: None,
+                deadline: None,
+                circuit_breaker: None,
+                throttle: None,
+                ip_family: IpFamily::default(),
+                etag_cache: false,
+                response_cache: None,
+                slow_request_threshold: None,
+                retry: None,
+                pool: PoolConf::default(),
+                user_agent: None,
+                headers: HashMap::new(),
+                proxy: None,
+                tls: TlsConf::default(),
+                skip_health_check: false,
+            },
+        }
+    }
+
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.conf.base_path = Some(base_path.into());
+        self
+    }
+
+    /// Add a node to fail over to, after those added before
+    pub fn fallback_url(mut self, url: Url) -> Self {
+        self.conf.fallback_urls.push(url);
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.conf.password = Some(password.into());
+        self
+    }
+
+    /// See `Conf::timeout`
+    pub fn timeout(mut self, secs: u64) -> Self {
+        self.conf.timeout = Some(secs);
+        self
+    }
+
+    /// See `Conf::deadline`
+    pub fn deadline(mut self, secs: u64) -> Self {
+        self.conf.deadline = Some(secs);
+        self
+    }
+
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConf) -> Self {
+        self.conf.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    pub fn throttle(mut self, throttle: AdaptiveThrottleConf) -> Self {
+        self.conf.throttle = Some(throttle);
+        self
+    }
+
+    pub fn ip_family(mut self, ip_family: IpFamily) -> Self {
+        self.conf.ip_family = ip_family;
+        self
+    }
+
+    pub fn etag_cache(mut self, enabled: bool) -> Self {
+        self.conf.etag_cache = enabled;
+        self
+    }
+
+    pub fn response_cache(mut self, response_cache: ResponseCacheConf) -> Self {
+        self.conf.response_cache = Some(response_cache);
+        self
+    }
+
+    /// See `Conf::slow_request_threshold`
+    pub fn slow_request_threshold(mut self, millis: u64) -> Self {
+        self.conf.slow_request_threshold = Some(millis);
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicyConf) -> Self {
+        self.conf.retry = Some(retry);
+        self
+    }
+
+    pub fn pool(mut self, pool: PoolConf) -> Self {
+        self.conf.pool = pool;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.conf.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send the header with every request, replacing any value added before
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.conf.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Url) -> Self {
+        self.conf.proxy = Some(proxy);
+        self
+    }
+
+    pub fn tls(mut self, tls: TlsConf) -> Self {
+        self.conf.tls = tls;
+        self
+    }
+
+    /// See `Conf::skip_health_check`
+    pub fn skip_health_check(mut self, skip: bool) -> Self {
+        self.conf.skip_health_check = skip;
+        self
+    }
+
+    pub fn build(self) -> Conf { self.conf }
+
+    /// Build the `Conf` and create a client from it
+    pub async fn connect(self) -> Result<NativeClient, ApiClientError> { NativeClient::new(self.conf).await }
+}
+
+pub(crate) async fn sleep(duration: Duration) { tokio::time::sleep(duration).await }
+
+impl NativeClient {
+    /// Report a `RequestSample` of every request to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub(crate) async fn execute_and_parse<R: SiaApiRequest>(
+        &self,
+        mut request: reqwest::Request,
+        cache_slot: Option<CacheSlot>,
+    ) -> Result<R::Response, ApiClientError> {
+        let etag_cache = match &self.etag_cache {
+            Some(cache) if request.method() == reqwest::Method::GET => Some(cache),
+            _ => None,
+        };
+        let url = request.url().clone();
+        let cached = etag_cache.and_then(|cache| cache.get(&url));
+        if let Some(etag) = cached.as_ref().and_then(|c| HeaderValue::from_str(&c.etag).ok()) {
+            request.headers_mut().insert(IF_NONE_MATCH, etag);
+        }
+
+        // Execute the request using reqwest client
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ApiClientError::ReqwestError)?;
+
+        if let (reqwest::StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), &cached) {
+            return cached.parse();
+        }
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_owned);
+
+        // Check the response status and return the appropriate result
+        match response.status() {
+            reqwest::StatusCode::OK => {
+                let etag = etag.filter(|_| etag_cache.is_some());
+                let response_cache = self.response_cache.as_ref().zip(cache_slot);
+                if etag.is_none() && response_cache.is_none() {
+                    return response
+                        .json::<R::Response>()
+                        .await
+                        .map_err(ApiClientError::ReqwestError);
+                }
+                let body = response.text().await.map_err(ApiClientError::ReqwestError)?;
+                let parsed = serde_json::from_str(&body).map_err(ApiClientError::Serde)?;
+                if let (Some(cache), Some(etag)) = (etag_cache, etag) {
+                    cache.store(url, etag, body.clone());
+                }
+                if let Some((cache, slot)) = response_cache {
+                    cache.store(slot, body);
+                }
+                Ok(parsed)
+            },
+
+            reqwest::StatusCode::NO_CONTENT => {
+                if let Some(resp_type) = R::is_empty_response() {
+                    Ok(resp_type)
+                } else {
+                    Err(ApiClientError::UnexpectedEmptyResponse {
+                        expected_type: std::any::type_name::<R::Response>().to_string(),
+                    })
+                }
+            },
+            // Handle unexpected statuses eg, 400, 404, 500
+            status => {
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned);
+                // Extract the body, using map_err to format the error in case of failure
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|e| format!("Failed to retrieve body: {}", e))
+                    .unwrap_or_else(|e| e);
+
+                Err(ApiClientError::UnexpectedHttpStatus { status, body }.with_retry_after(retry_after.as_deref()))
+            },
+        }
+    }
 }
 
 #[async_trait]
@@ -33,6 +391,18 @@ impl ApiClient for NativeClient {
 
     async fn new(conf: Self::Conf) -> Result<Self, ApiClientError> {
         let mut headers = HeaderMap::new();
+        for (name, value) in &conf.headers {
+            let name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| ApiClientError::BuildError(e.to_string()))?;
+            let value = HeaderValue::from_str(value).map_err(|e| ApiClientError::BuildError(e.to_string()))?;
+            headers.insert(name, value);
+        }
+        if let Some(user_agent) = &conf.user_agent {
+            headers.insert(
+                USER_AGENT,
+                HeaderValue::from_str(user_agent).map_err(|e| ApiClientError::BuildError(e.to_string()))?,
+            );
+        }
         if let Some(password) = &conf.password {
             let auth_value = format!("Basic {}", BASE64.encode(format!(":{}", password)));
             headers.insert(
@@ -40,24 +410,53 @@ impl ApiClient for NativeClient {
                 HeaderValue::from_str(&auth_value).map_err(|e| ApiClientError::BuildError(e.to_string()))?,
             );
         }
-        let timeout = conf.timeout.unwrap_or(10);
-        let client = ReqwestClient::builder()
+        // the timeout of each request is enforced by `dispatcher_with_opts`, so calls can override it
+        let mut builder = ReqwestClient::builder()
             .default_headers(headers)
-            .timeout(Duration::from_secs(timeout))
-            .build()
-            .map_err(ApiClientError::ReqwestError)?;
+            .local_address(conf.ip_family.local_address())
+            .tcp_keepalive(conf.pool.tcp_keepalive.map(Duration::from_secs));
+        if let Some(max_idle) = conf.pool.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = conf.pool.idle_timeout {
+            builder = builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+        }
+        if let Some(proxy) = &conf.proxy {
+            builder = builder.proxy(Proxy::all(proxy.clone()).map_err(ApiClientError::ReqwestError)?);
+        }
+        if let Some(path) = &conf.tls.ca_certificate {
+            let pem = std::fs::read(path)
+                .map_err(|e| ApiClientError::BuildError(format!("reading {}: {}", path.display(), e)))?;
+            let certificate = Certificate::from_pem(&pem).map_err(ApiClientError::ReqwestError)?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder = builder.danger_accept_invalid_certs(conf.tls.danger_accept_invalid_certs);
+        let client = builder.build().map_err(ApiClientError::ReqwestError)?;
 
         let ret = NativeClient {
             client,
-            base_url: conf.server_url,
+            nodes: resolve_nodes(&conf.server_url, &conf.fallback_urls, conf.base_path.as_deref())?,
+            default_opts: RequestOpts {
+                deadline: conf.deadline.map(Duration::from_secs),
+                timeout: Some(Duration::from_secs(conf.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS))),
+                cancel: None,
+            },
+            circuit_breaker: conf.circuit_breaker.map(CircuitBreaker::from),
+            throttle: conf.throttle.map(AdaptiveThrottle::from),
+            etag_cache: conf.etag_cache.then(EtagCache::default),
+            response_cache: conf.response_cache.map(ResponseCache::from),
+            slow_request_threshold: conf.slow_request_threshold.map(Duration::from_millis),
+            retry_policy: conf.retry.map(RetryPolicy::from),
+            metrics: None,
         };
-        // Ping the server with ConsensusTipRequest to check if the client is working
-        ret.dispatcher(ConsensusTipRequest).await?;
+        if !conf.skip_health_check {
+            startup_ping(&ret).await?;
+        }
         Ok(ret)
     }
 
     fn process_schema(&self, schema: EndpointSchema) -> Result<Self::Request, ApiClientError> {
-        let url = schema.build_url(&self.base_url)?;
+        let url = schema.build_url(self.nodes.active())?;
         let req = match schema.body {
             ClientBody::None => self.client.request(schema.method.into(), url).build(),
             ClientBody::Utf8(body) => self.client.request(schema.method.into(), url).body(body).build(),
@@ -71,103 +470,564 @@ impl ApiClient for NativeClient {
         self.client.execute(request).await.map_err(ApiClientError::ReqwestError)
     }
 
-    async fn dispatcher<R: SiaApiRequest>(&self, request: R) -> Result<R::Response, ApiClientError> {
-        let request = self.to_data_request(request)?;
-
-        // Execute the request using reqwest client
-        let response = self
-            .client
-            .execute(request)
+    async fn dispatch_once<R: SiaApiRequest>(&self, request: &R) -> Result<R::Response, ApiClientError> {
+        let cache_slot = self.response_cache.as_ref().and_then(|_| ResponseCache::slot(request));
+        let data_request = self.to_data_request(request)?;
+        let endpoint = data_request.url().path().to_owned();
+        self.execute_and_parse::<R>(data_request, cache_slot)
             .await
-            .map_err(ApiClientError::ReqwestError)?;
+            .map_err(|e| e.with_request_context(endpoint, request))
+    }
 
-        // Check the response status and return the appropriate result
-        match response.status() {
-            reqwest::StatusCode::OK => Ok(response
-                .json::<R::Response>()
-                .await
-                .map_err(ApiClientError::ReqwestError)?),
-            reqwest::StatusCode::NO_CONTENT => {
-                if let Some(resp_type) = R::is_empty_response() {
-                    Ok(resp_type)
-                } else {
-                    Err(ApiClientError::UnexpectedEmptyResponse {
-                        expected_type: std::any::type_name::<R::Response>().to_string(),
-                    })
-                }
-            },
-            // Handle unexpected statuses eg, 400, 404, 500
-            status => {
-                // Extract the body, using map_err to format the error in case of failure
-                let body = response
-                    .text()
-                    .await
-                    .map_err(|e| format!("Failed to retrieve body: {}", e))
-                    .unwrap_or_else(|e| e);
+    fn base_url(&self) -> &Url { self.nodes.active() }
 
-                Err(ApiClientError::UnexpectedHttpStatus {
-                    status,
-                    body,
-                })
-            }
-        }
-    }
+    fn node_pool(&self) -> Option<&NodePool> { Some(&self.nodes) }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> { self.circuit_breaker.as_ref() }
+
+    fn throttle(&self) -> Option<&AdaptiveThrottle> { self.throttle.as_ref() }
+
+    fn slow_request_threshold(&self) -> Option<Duration> { self.slow_request_threshold }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> { self.retry_policy.as_ref() }
+
+    fn metrics(&self) -> Option<&dyn Metrics> { self.metrics.as_deref() }
+
+    fn response_cache(&self) -> Option<&ResponseCache> { self.response_cache.as_ref() }
+
+    fn default_opts(&self) -> RequestOpts { self.default_opts.clone() }
 }
 
 #[async_trait]
 impl ApiClientHelpers for NativeClient {}
 
-// TODO these tests should not rely on the actual server - mock the server or use docker
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http::endpoints::{AddressBalanceRequest, GetEventRequest};
-    use crate::types::Address;
+    use crate::http::client::cancel::CancellationToken;
+    use crate::http::client::TYPICAL_TX_SIZE;
+    use crate::http::endpoints::{AddWalletAddressRequest, AddWalletRequest, AddressBalanceRequest,
+                                 AddressEventsRequest, AddressUnconfirmedEventsRequest, ConsensusBlockRequest,
+                                 ConsensusIndexRequest, ConsensusNetworkRequest, ConsensusTipRequest,
+                                 ConsensusTipStateRequest, DeleteWalletRequest, GetAddressSiafundUtxosRequest,
+                                 GetAddressUtxosRequest, GetEventRequest, StateRequest, TxpoolBroadcastRequest,
+                                 TxpoolFeeRequest, TxpoolTransactionsRequest, WalletAddress, WalletAddressesRequest,
+                                 WalletBalanceRequest, WalletEventsRequest, WalletUnconfirmedEventsRequest,
+                                 WalletsRequest};
+    #[cfg(feature = "watcher")]
+    use crate::http::events::EventStreamConf;
+    use crate::http::mock::{fixtures, MockWalletd};
+    use crate::transaction::{Currency, V2Transaction};
+    use crate::types::{Address, EventID, TransactionID};
+    #[cfg(feature = "watcher")] use futures::TryStreamExt;
+    #[cfg(feature = "watcher")]
+    use wiremock::matchers::{method, path, query_param};
+    #[cfg(feature = "watcher")] use wiremock::Mock;
+    use wiremock::ResponseTemplate;
 
     use std::str::FromStr;
     use tokio;
 
-    async fn init_client() -> NativeClient {
-        let conf = Conf {
-            server_url: Url::parse("https://sia-walletd.komodo.earth/").unwrap(),
-            password: None,
-            timeout: Some(10),
-        };
-        NativeClient::new(conf).await.unwrap()
+    fn address() -> Address {
+        Address::from_str("addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f").unwrap()
     }
 
     /// Helper function to setup the client and send a request
     async fn test_dispatch<R: SiaApiRequest>(request: R) -> R::Response {
-        let api_client = init_client().await;
+        let mock = MockWalletd::start().await;
+        let api_client = mock.client().await.unwrap();
         api_client.dispatcher(request).await.unwrap()
     }
 
     #[tokio::test]
-    async fn test_new_client() { let _api_client = init_client().await; }
+    async fn test_new_client() {
+        let mock = MockWalletd::start().await;
+        let _api_client = mock.client().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_client_no_walletd() {
+        let mock = MockWalletd::start_empty().await;
+        let err = mock.client().await.unwrap_err();
+        assert!(
+            matches!(err.inner(), ApiClientError::UnexpectedHttpStatus { status, .. } if *status == http::StatusCode::NOT_FOUND),
+            "{:?}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_skip_health_check() {
+        let mock = MockWalletd::start_empty().await;
+        let api_client = ClientConfBuilder::new(mock.url())
+            .skip_health_check(true)
+            .connect()
+            .await
+            .unwrap();
+        assert!(mock.server().received_requests().await.unwrap().is_empty());
+        api_client.health_check().await.unwrap_err();
+
+        let mock = MockWalletd::start().await;
+        let api_client = NativeClient::new(Conf {
+            skip_health_check: true,
+            ..mock.conf()
+        })
+        .await
+        .unwrap();
+        let status = api_client.health_check().await.unwrap();
+        assert_eq!(status.version, "v0.8.0");
+        assert_eq!(status.tip.height, 203);
+    }
+
+    #[test]
+    fn test_conf_builder() {
+        let conf = ClientConfBuilder::new(Url::parse("https://host/").unwrap())
+            .base_path("sia/walletd")
+            .fallback_url(Url::parse("https://fallback/").unwrap())
+            .password("password")
+            .header("X-Proxy-Token", "secret")
+            .ip_family(IpFamily::Ipv4)
+            .build();
+        assert_eq!(conf.base_path.as_deref(), Some("sia/walletd"));
+        assert_eq!(conf.fallback_urls.len(), 1);
+        assert_eq!(conf.password.as_deref(), Some("password"));
+        assert_eq!(conf.headers["X-Proxy-Token"], "secret");
+        assert_eq!(conf.ip_family, IpFamily::Ipv4);
+        assert!(!conf.skip_health_check);
+        assert!(conf.retry.is_none());
+
+        // the same defaults as a `Conf` deserialized from just its server URL
+        let built = ClientConfBuilder::new(Url::parse("https://host/").unwrap()).build();
+        let conf: Conf = serde_json::from_value(serde_json::json!({"server_url": "https://host/"})).unwrap();
+        assert_eq!(format!("{:?}", built), format!("{:?}", conf));
+    }
+
+    #[test]
+    fn test_conf_ip_family() {
+        let conf: Conf = serde_json::from_value(serde_json::json!({"server_url": "https://host/"})).unwrap();
+        assert_eq!(conf.ip_family, IpFamily::Any);
+        assert_eq!(conf.ip_family.local_address(), None);
+
+        let conf: Conf = serde_json::from_value(json!({"server_url": "https://host/", "ip_family": "ipv4"})).unwrap();
+        assert_eq!(conf.ip_family.local_address(), Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)));
+        assert!(serde_json::from_value::<Conf>(json!({"server_url": "https://host/", "ip_family": "ipx"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conf_pool() {
+        let conf: Conf = serde_json::from_value(serde_json::json!({"server_url": "https://host/"})).unwrap();
+        assert_eq!(conf.pool, PoolConf::default());
+
+        let mock = MockWalletd::start().await;
+        let conf = Conf {
+            pool: PoolConf {
+                max_idle_per_host: Some(4),
+                idle_timeout: Some(30),
+                tcp_keepalive: Some(60),
+            },
+            ..mock.conf()
+        };
+        NativeClient::new(conf).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conf_headers_and_tls() {
+        let conf: Conf = serde_json::from_value(serde_json::json!({"server_url": "https://host/"})).unwrap();
+        assert!(conf.headers.is_empty());
+        assert_eq!(conf.tls, TlsConf::default());
+
+        let mock = MockWalletd::start().await;
+        let conf = Conf {
+            user_agent: Some("sia-rust-test/1.0".to_owned()),
+            headers: HashMap::from([("X-Proxy-Token".to_owned(), "secret".to_owned())]),
+            ..mock.conf()
+        };
+        NativeClient::new(conf).await.unwrap();
+        let requests = mock.server().received_requests().await.unwrap();
+        let headers = &requests[0].headers;
+        assert_eq!(headers.get("user-agent").unwrap(), "sia-rust-test/1.0");
+        assert_eq!(headers.get("x-proxy-token").unwrap(), "secret");
+
+        let conf = Conf {
+            headers: HashMap::from([("bad header".to_owned(), "value".to_owned())]),
+            ..mock.conf()
+        };
+        let err = NativeClient::new(conf).await.unwrap_err();
+        assert!(matches!(err, ApiClientError::BuildError(_)), "{:?}", err);
+
+        let conf = Conf {
+            tls: TlsConf {
+                ca_certificate: Some(PathBuf::from("/nonexistent/ca.pem")),
+                danger_accept_invalid_certs: false,
+            },
+            ..mock.conf()
+        };
+        let err = NativeClient::new(conf).await.unwrap_err();
+        assert!(matches!(err, ApiClientError::BuildError(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_and_cancellation() {
+        let mock = MockWalletd::start().await;
+        let slow = ResponseTemplate::new(200)
+            .set_body_raw(fixtures::TXPOOL_FEE, "application/json")
+            .set_delay(Duration::from_millis(300));
+        mock.respond("GET", "/api/txpool/fee", slow).await;
+        let api_client = mock.client().await.unwrap();
+
+        let err = api_client
+            .dispatcher_with_opts(TxpoolFeeRequest, RequestOpts::with_timeout(Duration::from_millis(50)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.inner(), ApiClientError::Timeout(_)));
+        // a longer timeout than the client's lets slow endpoints through
+        api_client
+            .dispatcher_with_opts(TxpoolFeeRequest, RequestOpts::with_timeout(Duration::from_secs(5)))
+            .await
+            .unwrap();
+
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let err = api_client
+            .dispatcher_with_opts(TxpoolFeeRequest, RequestOpts::with_cancellation(token))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiClientError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_batch() {
+        let mock = MockWalletd::start().await;
+        let api_client = mock.client().await.unwrap();
+        mock.respond(
+            "GET",
+            "/api/events/0000000000000000000000000000000000000000000000000000000000000000",
+            ResponseTemplate::new(404),
+        )
+        .await;
+        let requests = vec![
+            GetEventRequest {
+                txid: TransactionID::from_str("txid:5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd")
+                    .unwrap(),
+            },
+            GetEventRequest {
+                txid: TransactionID::default(),
+            },
+            GetEventRequest {
+                txid: TransactionID::from_str("txid:5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd")
+                    .unwrap(),
+            },
+        ];
+        let results = api_client.dispatch_batch(requests, 2).await;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        let mock = MockWalletd::start().await;
+        let conf = Conf {
+            response_cache: Some(Default::default()),
+            ..mock.conf()
+        };
+        let api_client = NativeClient::new(conf).await.unwrap();
+        let id = api_client
+            .dispatcher(ConsensusIndexRequest { height: 200 })
+            .await
+            .unwrap()
+            .id;
+        let block = api_client.dispatcher(ConsensusBlockRequest { id }).await.unwrap();
+        let cached = api_client.dispatcher(ConsensusBlockRequest { id }).await.unwrap();
+        assert_eq!(block, cached);
+        // the index follows reorgs, so it is requested every time
+        api_client
+            .dispatcher(ConsensusIndexRequest { height: 200 })
+            .await
+            .unwrap();
+
+        let requests = mock.server().received_requests().await.unwrap();
+        let count = |prefix: &str| {
+            requests
+                .iter()
+                .filter(|request| request.url.path().starts_with(prefix))
+                .count()
+        };
+        assert_eq!(count("/api/consensus/blocks/"), 1);
+        assert_eq!(count("/api/consensus/index/"), 2);
+        assert_eq!(api_client.response_cache.as_ref().map(ResponseCache::len), Some(2));
+
+        // cancellation is checked before the cache
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = api_client
+            .dispatcher_with_opts(ConsensusBlockRequest { id }, RequestOpts::with_cancellation(token))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.inner(), ApiClientError::Cancelled));
+    }
 
     #[tokio::test]
     async fn test_api_consensus_tip() {
-        // paranoid unit test - NativeClient::new already pings the server with ConsensusTipRequest
-        let _response = test_dispatch(ConsensusTipRequest).await;
+        let response = test_dispatch(ConsensusTipRequest).await;
+        assert_eq!(response.height, 203);
+    }
+
+    #[tokio::test]
+    async fn test_api_consensus_tipstate() {
+        let response = test_dispatch(ConsensusTipStateRequest).await;
+        assert_eq!(response.index.height, 203);
+    }
+
+    #[tokio::test]
+    async fn test_api_consensus_network() {
+        let network = test_dispatch(ConsensusNetworkRequest).await;
+        assert_eq!(network.name, "zen");
+        assert_eq!(network.maturity_height(203), 347);
+        assert_eq!(network.maturity_height(u64::MAX), u64::MAX);
+        assert!(network.v2_allowed(203));
+        assert!(network.v2_required(110));
+        assert!(!network.v2_required(109));
+    }
+
+    #[tokio::test]
+    async fn test_api_consensus_blocks() {
+        let index = test_dispatch(ConsensusIndexRequest { height: 200 }).await;
+        assert_eq!(index.height, 200);
+
+        let block = test_dispatch(ConsensusBlockRequest { id: index.id }).await;
+        assert_eq!(block.miner_payouts.len(), 1);
+        assert!(block.transactions.is_empty());
+        assert_eq!(block.v2.as_ref().map(|v2| v2.height), Some(200));
+        assert_eq!(block.v2_transactions().len(), 1);
+
+        let mock = MockWalletd::start().await;
+        let api_client = mock.client().await.unwrap();
+        assert_eq!(api_client.block_at_height(200).await.unwrap(), block);
     }
 
     #[tokio::test]
     async fn test_api_address_balance() {
-        let request = AddressBalanceRequest {
-            address: Address::from_str(
-                "addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f",
-            )
-            .unwrap(),
+        let response = test_dispatch(AddressBalanceRequest { address: address() }).await;
+        assert_eq!(response.siacoins, Currency(256394172736732570239334030000));
+    }
+
+    #[tokio::test]
+    async fn test_api_address_events() {
+        let request = AddressEventsRequest {
+            address: address(),
+            limit: Some(10),
+            offset: Some(0),
         };
-        let _response = test_dispatch(request).await;
+        let response = test_dispatch(request).await;
+        assert_eq!(response.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_api_address_utxos() {
+        let request = GetAddressUtxosRequest {
+            address: address(),
+            limit: None,
+            offset: None,
+        };
+        let response = test_dispatch(request).await;
+        assert_eq!(response[0].maturity_height, 154);
+    }
+
+    #[tokio::test]
+    async fn test_api_address_siafund_utxos() {
+        let request = GetAddressSiafundUtxosRequest {
+            address: address(),
+            limit: None,
+            offset: None,
+        };
+        let response = test_dispatch(request).await;
+        assert_eq!(response[0].siafund_output.value, 2000);
+    }
+
+    #[cfg(feature = "wallet")]
+    #[tokio::test]
+    async fn test_spendable_siafund_utxos() {
+        let mock = MockWalletd::start().await;
+        let api_client = mock.client().await.unwrap();
+        let utxos = api_client.spendable_siafund_utxos(&address()).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        let balance = api_client.address_balance(address()).await.unwrap();
+        assert_eq!(balance.siafunds, 0);
     }
 
     #[tokio::test]
     async fn test_api_events() {
-        use crate::types::H256;
-        let request = GetEventRequest {
-            txid: H256::from_str("77c5ae2220eac76dd841e365bb14fcba5499977e6483472b96f4a83bcdd6c892").unwrap(),
+        let txid =
+            TransactionID::from_str("txid:5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd").unwrap();
+        let response = test_dispatch(GetEventRequest { txid }).await;
+        assert_eq!(response.0.id, EventID::from(txid));
+    }
+
+    #[tokio::test]
+    async fn test_api_address_unconfirmed() {
+        let response = test_dispatch(AddressUnconfirmedEventsRequest { address: address() }).await;
+        assert!(response.is_empty());
+    }
+
+    #[cfg(feature = "watcher")]
+    #[tokio::test]
+    async fn test_address_unconfirmed_activity() {
+        let mock = MockWalletd::start().await;
+        let route = format!(
+            "/api/addresses/addr%3A{}/events/unconfirmed",
+            address().str_without_prefix()
+        );
+        let events = ResponseTemplate::new(200).set_body_raw(fixtures::ADDRESS_EVENTS, "application/json");
+        mock.respond("GET", &route, events).await;
+        let api_client = mock.client().await.unwrap();
+        let activity = api_client.address_unconfirmed(address()).await.unwrap();
+        assert_eq!(activity.events.len(), 1);
+        // the fixture event neither pays nor spends from this address
+        assert_eq!(activity.incoming, Currency::default());
+        assert_eq!(activity.outgoing, Currency::default());
+    }
+
+    #[cfg(feature = "watcher")]
+    #[tokio::test]
+    async fn test_event_stream() {
+        let mock = MockWalletd::start().await;
+        let route = format!("/api/addresses/addr%3A{}/events", address().str_without_prefix());
+        // every page holds the single fixture event up to offset 3, where the history ends
+        Mock::given(method("GET"))
+            .and(path(route.as_str()))
+            .and(query_param("offset", "3"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("[]", "application/json"))
+            .with_priority(1)
+            .mount(mock.server())
+            .await;
+        let api_client = mock.client().await.unwrap();
+        let conf = EventStreamConf {
+            page_size: 1,
+            max_in_flight: 2,
+            offset: 1,
+        };
+        let events: Vec<_> = api_client.event_stream(address(), conf).try_collect().await.unwrap();
+        assert_eq!(events.len(), 2);
+
+        let mut offsets: Vec<_> = mock
+            .server()
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == route)
+            .filter_map(|request| request.url.query_pairs().find(|(name, _)| name == "offset"))
+            .map(|(_, offset)| offset.parse::<i64>().unwrap())
+            .collect();
+        // concurrent pages may arrive out of order
+        offsets.sort_unstable();
+        // resumed at offset 1, at most one page requested past the end
+        assert_eq!(offsets[..3], [1, 2, 3]);
+        assert!(offsets.len() <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_api_state() {
+        let response = test_dispatch(StateRequest).await;
+        assert_eq!(response.version, "v0.8.0");
+    }
+
+    #[tokio::test]
+    async fn test_api_txpool() {
+        let fee = test_dispatch(TxpoolFeeRequest).await;
+        assert_eq!(fee.0, Currency(10000000000000000000));
+
+        let mock = MockWalletd::start().await;
+        let api_client = mock.client().await.unwrap();
+        let estimate = api_client.estimate_miner_fee(TYPICAL_TX_SIZE).await.unwrap();
+        assert_eq!(estimate, Currency(10000000000000000000000));
+
+        let txpool = test_dispatch(TxpoolTransactionsRequest).await;
+        assert!(txpool.transactions.is_empty());
+        assert!(txpool.v2transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_api_txpool_broadcast() {
+        let request = TxpoolBroadcastRequest {
+            transactions: vec![],
+            v2transactions: vec![V2Transaction::default()],
         };
         let _response = test_dispatch(request).await;
     }
+
+    #[tokio::test]
+    async fn test_api_wallets() {
+        let wallets = test_dispatch(WalletsRequest).await;
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(wallets[0].name, "hot wallet");
+
+        let request = AddWalletRequest {
+            name: "hot wallet".to_owned(),
+            description: String::new(),
+            metadata: json!(null),
+        };
+        let wallet = test_dispatch(request).await;
+        assert_eq!(wallet.id, 1);
+
+        let addresses = test_dispatch(WalletAddressesRequest { id: wallet.id }).await;
+        assert_eq!(addresses[0].address, address());
+        assert!(addresses[0].spend_policy.is_some());
+
+        let request = AddWalletAddressRequest {
+            id: wallet.id,
+            address: WalletAddress {
+                address: address(),
+                description: String::new(),
+                spend_policy: None,
+                metadata: json!(null),
+            },
+        };
+        test_dispatch(request).await;
+
+        let balance = test_dispatch(WalletBalanceRequest { id: wallet.id }).await;
+        assert_eq!(balance.siacoins, Currency(256394172736732570239334030000));
+        let request = WalletEventsRequest {
+            id: wallet.id,
+            limit: Some(10),
+            offset: None,
+        };
+        assert_eq!(test_dispatch(request).await.len(), 1);
+        assert!(test_dispatch(WalletUnconfirmedEventsRequest { id: wallet.id })
+            .await
+            .is_empty());
+
+        test_dispatch(DeleteWalletRequest { id: wallet.id }).await;
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_rejected() {
+        let mock = MockWalletd::start().await;
+        let reason = "failed to add transaction set: siacoin input 0 spends nonexistent output";
+        mock.respond(
+            "POST",
+            "/api/txpool/broadcast",
+            ResponseTemplate::new(400).set_body_string(reason),
+        )
+        .await;
+
+        let client = mock.client().await.unwrap();
+        let err = client
+            .broadcast_transaction(&V2Transaction::default())
+            .await
+            .unwrap_err();
+        match err.inner() {
+            ApiClientError::TransactionRejected { status, message } => {
+                assert_eq!(*status, http::StatusCode::BAD_REQUEST);
+                assert_eq!(message, reason);
+            },
+            other => panic!("expected TransactionRejected, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,290 @@
+use crate::http::client::ApiClientError;
+use core::time::Duration;
+use http::StatusCode;
+use serde::Deserialize;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY_MS: u64 = 200;
+const DEFAULT_MAX_DELAY_MS: u64 = 5_000;
+const DEFAULT_RETRYABLE_STATUS_CODES: [u16; 4] = [429, 502, 503, 504];
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetryPolicyConf {
+    /// Total number of attempts made for a request, including the first
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Milliseconds to wait before the first retry, doubled for every further retry
+    #[serde(default = "default_base_delay")]
+    pub base_delay: u64,
+    /// Upper bound in milliseconds of the wait between two attempts
+    #[serde(default = "default_max_delay")]
+    pub max_delay: u64,
+    /// Wait a random duration between half and all of the delay, so clients that failed together do not
+    /// retry in lockstep
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+    /// HTTP statuses worth retrying. Transport errors such as timeouts and refused connections always are.
+    #[serde(default = "default_retryable_status_codes")]
+    pub retryable_status_codes: Vec<u16>,
+}
+
+fn default_max_attempts() -> u32 { DEFAULT_MAX_ATTEMPTS }
+
+fn default_base_delay() -> u64 { DEFAULT_BASE_DELAY_MS }
+
+fn default_max_delay() -> u64 { DEFAULT_MAX_DELAY_MS }
+
+fn default_jitter() -> bool { true }
+
+fn default_retryable_status_codes() -> Vec<u16> { DEFAULT_RETRYABLE_STATUS_CODES.to_vec() }
+
+impl Default for RetryPolicyConf {
+    fn default() -> Self {
+        RetryPolicyConf {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY_MS,
+            max_delay: DEFAULT_MAX_DELAY_MS,
+            jitter: true,
+            retryable_status_codes: default_retryable_status_codes(),
+        }
+    }
+}
+
+/// Retries requests failing with transient errors after an exponentially growing delay.
+///
+/// An attempt is retried if the node could not be reached or timed out, or if it responded with one of
+/// `retryable_status_codes`. Rejections such as `400 Bad Request` and errors raised before the request is sent,
+/// eg. `ApiClientError::CircuitOpen`, are returned right away. A `Retry-After` header lengthens the delay before
+/// the next attempt, up to `max_delay`. A `RequestOpts::deadline` bounds all attempts together, including the
+/// delays between them.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable_status_codes: Vec<StatusCode>,
+}
+
+impl From<RetryPolicyConf> for RetryPolicy {
+    fn from(conf: RetryPolicyConf) -> Self {
+        let mut policy = RetryPolicy::new(
+            conf.max_attempts,
+            Duration::from_millis(conf.base_delay),
+            Duration::from_millis(conf.max_delay),
+        );
+        policy.jitter = conf.jitter;
+        policy.retryable_status_codes = conf
+            .retryable_status_codes
+            .into_iter()
+            .filter_map(|code| StatusCode::from_u16(code).ok())
+            .collect();
+        policy
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self { RetryPolicy::from(RetryPolicyConf::default()) }
+}
+
+impl RetryPolicy {
+    /// A policy retrying the default statuses with jitter
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay,
+            jitter: true,
+            retryable_status_codes: DEFAULT_RETRYABLE_STATUS_CODES
+                .iter()
+                .filter_map(|code| StatusCode::from_u16(*code).ok())
+                .collect(),
+        }
+    }
+
+    pub fn max_attempts(&self) -> u32 { self.max_attempts }
+
+    /// Whether `attempt`, counting from 1, failing with `error` should be followed by another attempt
+    pub fn should_retry(&self, error: &ApiClientError, attempt: u32) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        match error.http_status() {
+            Some((status, _)) => self.retryable_status_codes.contains(&status),
+            None => error.is_node_failure(),
+        }
+    }
+
+    /// The wait after `attempt`, counting from 1, has failed
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let mut random = [0u8; 4];
+        // jitter is best effort, fall back to the full delay if no randomness is available
+        match getrandom::getrandom(&mut random) {
+            Ok(()) => delay / 2 + (delay / 2).mul_f64(u32::from_le_bytes(random) as f64 / u32::MAX as f64),
+            Err(_) => delay,
+        }
+    }
+
+    /// The wait after `attempt` has failed with `error`, at least as long as its `Retry-After` up to `max_delay`
+    pub fn delay_for(&self, error: &ApiClientError, attempt: u32) -> Duration {
+        let delay = self.delay(attempt);
+        match error.retry_after() {
+            Some(retry_after) => delay.max(retry_after.min(self.max_delay)),
+            None => delay,
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::http::client::native::NativeClient;
+    use crate::http::client::ApiClient;
+    use crate::http::endpoints::{ConsensusTipRequest, TxpoolBroadcastRequest};
+    use crate::http::mock::MockWalletd;
+    use crate::transaction::V2Transaction;
+    use std::time::Instant;
+    use wiremock::ResponseTemplate;
+
+    const TIP: &str = "/api/consensus/tip";
+
+    async fn client(mock: &MockWalletd, max_attempts: u32) -> NativeClient {
+        let mut conf = mock.conf();
+        conf.skip_health_check = true;
+        conf.retry = Some(RetryPolicyConf {
+            max_attempts,
+            base_delay: 1,
+            max_delay: 5_000,
+            jitter: false,
+            ..Default::default()
+        });
+        NativeClient::new(conf).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let mock = MockWalletd::start().await;
+        mock.respond_times("GET", TIP, ResponseTemplate::new(503), 1).await;
+        mock.respond_times("GET", TIP, ResponseTemplate::new(502), 1).await;
+        let client = client(&mock, 3).await;
+
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        assert_eq!(mock.received(TIP).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let mock = MockWalletd::start().await;
+        mock.respond("GET", TIP, ResponseTemplate::new(503)).await;
+        let client = client(&mock, 2).await;
+
+        let err = client.dispatcher(ConsensusTipRequest).await.unwrap_err();
+        assert_eq!(
+            err.http_status().map(|(status, _)| status),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(mock.received(TIP).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_status_classification() {
+        // rejections and server errors not listed in `retryable_status_codes` fail on the first attempt
+        for status in [400u16, 404, 500] {
+            let mock = MockWalletd::start().await;
+            mock.respond("GET", TIP, ResponseTemplate::new(status).set_body_string("failed"))
+                .await;
+            let client = client(&mock, 3).await;
+
+            let err = client.dispatcher(ConsensusTipRequest).await.unwrap_err();
+            assert_eq!(err.http_status().map(|(status, _)| status.as_u16()), Some(status));
+            assert_eq!(mock.received(TIP).await, 1);
+        }
+
+        let mock = MockWalletd::start().await;
+        mock.respond_times("GET", TIP, ResponseTemplate::new(429), 1).await;
+        let client = client(&mock, 3).await;
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        assert_eq!(mock.received(TIP).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_honors_retry_after() {
+        let mock = MockWalletd::start().await;
+        let busy = ResponseTemplate::new(429).insert_header("Retry-After", "1");
+        mock.respond_times("GET", TIP, busy, 1).await;
+        let client = client(&mock, 2).await;
+
+        let started = Instant::now();
+        client.dispatcher(ConsensusTipRequest).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(mock.received(TIP).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_resends_body() {
+        let route = "/api/txpool/broadcast";
+        let mock = MockWalletd::start().await;
+        mock.respond_times("POST", route, ResponseTemplate::new(503), 1).await;
+        let client = client(&mock, 2).await;
+
+        client
+            .dispatcher(TxpoolBroadcastRequest {
+                transactions: vec![],
+                v2transactions: vec![V2Transaction::default()],
+            })
+            .await
+            .unwrap();
+        let bodies: Vec<Vec<u8>> = mock
+            .server()
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|request| request.url.path() == route)
+            .map(|request| request.body)
+            .collect();
+        assert_eq!(bodies.len(), 2);
+        assert!(!bodies[0].is_empty());
+        assert_eq!(bodies[0], bodies[1]);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let mut policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1));
+        policy.jitter = false;
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(4), Duration::from_millis(800));
+        assert_eq!(policy.delay(5), Duration::from_secs(1));
+        assert_eq!(policy.delay(64), Duration::from_secs(1));
+
+        policy.jitter = true;
+        for attempt in 1..5 {
+            let delay = policy.delay(attempt);
+            let max = Duration::from_millis(100 << (attempt - 1));
+            assert!(delay >= max / 2 && delay <= max, "{:?}", delay);
+        }
+
+        // a `Retry-After` lengthens the delay up to `max_delay`
+        policy.jitter = false;
+        let busy = |header| {
+            ApiClientError::UnexpectedHttpStatus {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                body: String::new(),
+            }
+            .with_retry_after(Some(header))
+        };
+        assert_eq!(policy.delay_for(&busy("0"), 1), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(&busy("1"), 1), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(&busy("60"), 1), Duration::from_secs(1));
+        assert_eq!(
+            policy.delay_for(&busy("Wed, 21 Oct 2015 07:28:00 GMT"), 1),
+            Duration::from_millis(100)
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use core::time::Duration;
+use futures::channel::oneshot;
+use instant::Instant;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_MIN_CONCURRENCY: usize = 1;
+const DEFAULT_MAX_CONCURRENCY: usize = 16;
+const DEFAULT_LATENCY_TARGET_MS: u64 = 1000;
+/// Factor the limit is multiplied with when the node appears overloaded
+const DECREASE_FACTOR: f64 = 0.7;
+/// Weight of the newest sample in the latency moving average
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AdaptiveThrottleConf {
+    #[serde(default = "default_min_concurrency")]
+    pub min_concurrency: usize,
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Average response latency in milliseconds above which the node is considered overloaded
+    #[serde(default = "default_latency_target")]
+    pub latency_target: u64,
+}
+
+fn default_min_concurrency() -> usize { DEFAULT_MIN_CONCURRENCY }
+
+fn default_max_concurrency() -> usize { DEFAULT_MAX_CONCURRENCY }
+
+fn default_latency_target() -> u64 { DEFAULT_LATENCY_TARGET_MS }
+
+impl Default for AdaptiveThrottleConf {
+    fn default() -> Self {
+        AdaptiveThrottleConf {
+            min_concurrency: DEFAULT_MIN_CONCURRENCY,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            latency_target: DEFAULT_LATENCY_TARGET_MS,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ThrottleState {
+    limit: f64,
+    in_flight: usize,
+    /// Moving average of response latencies in milliseconds
+    latency: Option<f64>,
+    last_decrease: Option<Instant>,
+    waiters: VecDeque<oneshot::Sender<()>>,
+}
+
+/// Limits the number of concurrent requests to a node, adapting the limit to how the node copes.
+///
+/// The limit grows additively while responses are fast and shrinks multiplicatively when the average latency
+/// exceeds `latency_target` or the node fails, at most once per average round trip so a burst of slow
+/// responses counts as a single signal. Requests over the limit wait for a permit. Scans and backfills can
+/// size their batches with `limit`. Clones share the same state.
+#[derive(Clone, Debug)]
+pub struct AdaptiveThrottle {
+    min: usize,
+    max: usize,
+    latency_target: Duration,
+    state: Arc<Mutex<ThrottleState>>,
+}
+
+impl From<AdaptiveThrottleConf> for AdaptiveThrottle {
+    fn from(conf: AdaptiveThrottleConf) -> Self {
+        AdaptiveThrottle::new(
+            conf.min_concurrency,
+            conf.max_concurrency,
+            Duration::from_millis(conf.latency_target),
+        )
+    }
+}
+
+impl AdaptiveThrottle {
+    pub fn new(min_concurrency: usize, max_concurrency: usize, latency_target: Duration) -> Self {
+        let min = min_concurrency.max(1);
+        let max = max_concurrency.max(min);
+        AdaptiveThrottle {
+            min,
+            max,
+            latency_target,
+            state: Arc::new(Mutex::new(ThrottleState {
+                limit: max as f64,
+                in_flight: 0,
+                latency: None,
+                last_decrease: None,
+                waiters: VecDeque::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ThrottleState> { self.state.lock().expect("throttle mutex poisoned") }
+
+    /// The current concurrency limit
+    pub fn limit(&self) -> usize { self.lock().limit as usize }
+
+    pub fn in_flight(&self) -> usize { self.lock().in_flight }
+
+    /// Wait until a request may be sent. The returned permit should be completed with `ThrottlePermit::record`.
+    pub async fn acquire(&self) -> ThrottlePermit {
+        let receiver = {
+            let mut state = self.lock();
+            if state.in_flight < state.limit as usize {
+                state.in_flight += 1;
+                None
+            } else {
+                let (sender, receiver) = oneshot::channel();
+                state.waiters.push_back(sender);
+                Some(receiver)
+            }
+        };
+        if let Some(receiver) = receiver {
+            let mut waiter = Waiter {
+                throttle: self.clone(),
+                receiver: Some(receiver),
+            };
+            if let Some(receiver) = waiter.receiver.as_mut() {
+                receiver.await.ok();
+            }
+            // the slot was handed over by `wake_waiters`, which already counted it as in flight
+            waiter.receiver = None;
+        }
+        ThrottlePermit {
+            throttle: self.clone(),
+            started: Instant::now(),
+            released: false,
+        }
+    }
+
+    fn release(&self) {
+        let mut state = self.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        Self::wake_waiters(&mut state);
+    }
+
+    fn wake_waiters(state: &mut ThrottleState) {
+        while state.in_flight < state.limit as usize {
+            match state.waiters.pop_front() {
+                // waiters that gave up dropped their receiver, skip them
+                Some(waiter) => {
+                    if waiter.send(()).is_ok() {
+                        state.in_flight += 1;
+                    }
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Adjust the limit to the outcome of a request that took `latency`
+    pub fn record(&self, latency: Duration, node_failure: bool, now: Instant) {
+        let mut state = self.lock();
+        let sample = latency.as_secs_f64() * 1000.0;
+        let average = match state.latency {
+            Some(average) => average * (1.0 - LATENCY_SMOOTHING) + sample * LATENCY_SMOOTHING,
+            None => sample,
+        };
+        state.latency = Some(average);
+
+        let overloaded = node_failure || average > self.latency_target.as_secs_f64() * 1000.0;
+        if overloaded {
+            let round_trip = Duration::from_secs_f64(average / 1000.0);
+            let recently_decreased = state
+                .last_decrease
+                .map(|at| now.saturating_duration_since(at) < round_trip)
+                .unwrap_or(false);
+            if !recently_decreased {
+                state.limit = (state.limit * DECREASE_FACTOR).max(self.min as f64);
+                state.last_decrease = Some(now);
+            }
+        } else {
+            state.limit = (state.limit + 1.0 / state.limit).min(self.max as f64);
+            Self::wake_waiters(&mut state);
+        }
+    }
+}
+
+/// Returns a slot handed over to a waiter that was dropped before it could take it, eg. on a deadline
+struct Waiter {
+    throttle: AdaptiveThrottle,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if let Ok(Some(())) = receiver.try_recv() {
+                self.throttle.release();
+            }
+        }
+    }
+}
+
+/// A slot for a single request, released when dropped
+#[derive(Debug)]
+pub struct ThrottlePermit {
+    throttle: AdaptiveThrottle,
+    started: Instant,
+    released: bool,
+}
+
+impl ThrottlePermit {
+    /// Release the permit and feed the request's latency and outcome back into the throttle
+    pub fn record(mut self, node_failure: bool) {
+        let now = Instant::now();
+        self.throttle
+            .record(now.saturating_duration_since(self.started), node_failure, now);
+        self.throttle.release();
+        self.released = true;
+    }
+}
+
+impl Drop for ThrottlePermit {
+    fn drop(&mut self) {
+        if !self.released {
+            self.throttle.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    fn throttle() -> AdaptiveThrottle { AdaptiveThrottle::new(1, 8, Duration::from_millis(100)) }
+
+    #[test]
+    fn test_throttle_decreases_when_slow() {
+        let throttle = throttle();
+        let start = Instant::now();
+        assert_eq!(throttle.limit(), 8);
+
+        throttle.record(Duration::from_millis(500), false, start);
+        assert_eq!(throttle.limit(), 5);
+        // a burst of slow responses within one round trip counts once
+        throttle.record(Duration::from_millis(500), false, start + Duration::from_millis(10));
+        assert_eq!(throttle.limit(), 5);
+
+        throttle.record(Duration::from_millis(500), false, start + Duration::from_secs(1));
+        assert_eq!(throttle.limit(), 3);
+        for i in 2..10 {
+            throttle.record(Duration::from_millis(500), false, start + Duration::from_secs(i));
+        }
+        assert_eq!(throttle.limit(), 1);
+    }
+
+    #[test]
+    fn test_throttle_decreases_on_failure() {
+        let throttle = throttle();
+        throttle.record(Duration::from_millis(1), true, Instant::now());
+        assert_eq!(throttle.limit(), 5);
+    }
+
+    #[test]
+    fn test_throttle_recovers() {
+        let throttle = AdaptiveThrottle::new(1, 4, Duration::from_millis(100));
+        let start = Instant::now();
+        throttle.record(Duration::from_millis(1), true, start);
+        throttle.record(Duration::from_millis(1), true, start + Duration::from_secs(1));
+        assert_eq!(throttle.limit(), 1);
+
+        for i in 0..20 {
+            throttle.record(Duration::from_millis(10), false, start + Duration::from_secs(2 + i));
+        }
+        assert_eq!(throttle.limit(), 4);
+    }
+
+    #[test]
+    fn test_throttle_waits_for_permit() {
+        let throttle = AdaptiveThrottle::new(1, 1, Duration::from_millis(100));
+        let first = throttle.acquire().now_or_never().unwrap();
+        assert_eq!(throttle.in_flight(), 1);
+
+        // waiters that give up before a slot frees are skipped
+        let mut abandoned = Box::pin(throttle.acquire());
+        assert!((&mut abandoned).now_or_never().is_none());
+        let mut second = Box::pin(throttle.acquire());
+        assert!((&mut second).now_or_never().is_none());
+        drop(abandoned);
+
+        first.record(false);
+        let second = second.now_or_never().unwrap();
+        assert_eq!(throttle.in_flight(), 1);
+        drop(second);
+        assert_eq!(throttle.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_throttle_cancelled_after_handover() {
+        let throttle = AdaptiveThrottle::new(1, 1, Duration::from_millis(100));
+        let first = throttle.acquire().now_or_never().unwrap();
+        let mut second = Box::pin(throttle.acquire());
+        assert!((&mut second).now_or_never().is_none());
+
+        // the slot is handed to `second`, which is dropped without ever being polled again
+        drop(first);
+        assert_eq!(throttle.in_flight(), 1);
+        drop(second);
+        assert_eq!(throttle.in_flight(), 0);
+    }
+}
@@ -1,50 +1,289 @@
-use crate::http::client::{ApiClient, ApiClientError, ApiClientHelpers, Body, EndpointSchema, SchemaMethod};
-use crate::http::endpoints::{ConsensusTipRequest, SiaApiRequest};
+use crate::http::client::cache::{ResponseCache, ResponseCacheConf};
+use crate::http::client::circuit_breaker::{CircuitBreaker, CircuitBreakerConf};
+use crate::http::client::etag::EtagCache;
+use crate::http::client::failover::NodePool;
+use crate::http::client::instrument::Metrics;
+use crate::http::client::retry::{RetryPolicy, RetryPolicyConf};
+use crate::http::client::throttle::{AdaptiveThrottle, AdaptiveThrottleConf};
+use crate::http::client::{resolve_nodes, startup_ping, ApiClient, ApiClientError, ApiClientHelpers, Body,
+                          EndpointSchema, RequestOpts, SchemaMethod};
+use crate::http::endpoints::SiaApiRequest;
 
 use async_trait::async_trait;
+use core::time::Duration;
+use futures::channel::oneshot;
+use gloo_timers::future::TimeoutFuture;
 use http::StatusCode;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
 use url::Url;
+use wasm_bindgen_futures::spawn_local;
 
 pub mod wasm_fetch;
 use wasm_fetch::{Body as FetchBody, FetchMethod, FetchRequest, FetchResponse};
 
+/// `ApiClient` for browsers and other wasm32 hosts, sending requests through the JS `fetch` API
 #[derive(Clone)]
-pub struct Client {
-    pub base_url: Url,
+pub struct WasmClient {
+    pub nodes: NodePool,
     pub headers: HashMap<String, String>,
+    pub default_opts: RequestOpts,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    pub throttle: Option<AdaptiveThrottle>,
+    pub etag_cache: Option<EtagCache>,
+    pub response_cache: Option<ResponseCache>,
+    pub slow_request_threshold: Option<Duration>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub metrics: Option<Arc<dyn Metrics>>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Conf {
     pub server_url: Url,
+    /// Path walletd is served under relative to `server_url`, eg. `sia/walletd` behind a reverse proxy
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// Nodes requests fail over to, in order, when `server_url` is unreachable or keeps failing, see `NodePool`.
+    /// `base_path` applies to each of them.
+    #[serde(default)]
+    pub fallback_urls: Vec<Url>,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Total time budget in seconds of each logical operation, see `RequestOpts::deadline`.
+    /// Can be overridden per call via `dispatcher_with_opts`.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// Short-circuit requests to a node after repeated failures, disabled if not set
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConf>,
+    /// Adapt the number of concurrent requests to the node's latency and failures, unlimited if not set
+    #[serde(default)]
+    pub throttle: Option<AdaptiveThrottleConf>,
+    /// Send conditional GET requests and reuse cached bodies on `304 Not Modified`, see `EtagCache`
+    #[serde(default)]
+    pub etag_cache: bool,
+    /// Answer requests for immutable data, eg. blocks by ID, and the recent tip from memory, disabled if not set.
+    /// See `ResponseCache`.
+    #[serde(default)]
+    pub response_cache: Option<ResponseCacheConf>,
+    /// Log requests taking longer than this many milliseconds as warnings through `tracing`, disabled if not set or
+    /// without the `tracing` feature
+    #[serde(default)]
+    pub slow_request_threshold: Option<u64>,
+    /// Retry requests failing with transient errors, each request is attempted once if not set
+    #[serde(default)]
+    pub retry: Option<RetryPolicyConf>,
+    /// Build the client without pinging the node, so it can be created while the node is down. Connectivity is then
+    /// only verified by the first request or an explicit `ApiClientHelpers::health_check`.
+    #[serde(default)]
+    pub skip_health_check: bool,
+}
+
+/// Builds a `Conf` from its server URL, leaving every option not set at its default
+#[derive(Clone, Debug)]
+pub struct ClientConfBuilder {
+    conf: Conf,
+}
+
+impl ClientConfBuilder {
+    pub fn new(server_url: Url) -> Self {
+        ClientConfBuilder {
+            conf: Conf {
+                server_url,
+                base_path: None,
+                fallback_urls: Vec::new(),
+                headers: HashMap::new(),
+                deadline: None,
+                circuit_breaker: None,
+                throttle: None,
+                etag_cache: false,
+                response_cache: None,
+                slow_request_threshold: None,
+                retry: None,
+                skip_health_check: false,
+            },
+        }
+    }
+
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.conf.base_path = Some(base_path.into());
+        self
+    }
+
+    /// Add a node to fail over to, after those added before
+    pub fn fallback_url(mut self, url: Url) -> Self {
+        self.conf.fallback_urls.push(url);
+        self
+    }
+
+    /// Send the header with every request, replacing any value added before
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.conf.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// See `Conf::deadline`
+    pub fn deadline(mut self, secs: u64) -> Self {
+        self.conf.deadline = Some(secs);
+        self
+    }
+
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConf) -> Self {
+        self.conf.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    pub fn throttle(mut self, throttle: AdaptiveThrottleConf) -> Self {
+        self.conf.throttle = Some(throttle);
+        self
+    }
+
+    pub fn etag_cache(mut self, enabled: bool) -> Self {
+        self.conf.etag_cache = enabled;
+        self
+    }
+
+    pub fn response_cache(mut self, response_cache: ResponseCacheConf) -> Self {
+        self.conf.response_cache = Some(response_cache);
+        self
+    }
+
+    /// See `Conf::slow_request_threshold`
+    pub fn slow_request_threshold(mut self, millis: u64) -> Self {
+        self.conf.slow_request_threshold = Some(millis);
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicyConf) -> Self {
+        self.conf.retry = Some(retry);
+        self
+    }
+
+    /// See `Conf::skip_health_check`
+    pub fn skip_health_check(mut self, skip: bool) -> Self {
+        self.conf.skip_health_check = skip;
+        self
+    }
+
+    pub fn build(self) -> Conf { self.conf }
+
+    /// Build the `Conf` and create a client from it
+    pub async fn connect(self) -> Result<WasmClient, ApiClientError> { WasmClient::new(self.conf).await }
+}
+
+/// JS timers are not `Send`, so the timer is spawned locally similar to `FetchRequest::execute`
+pub(crate) async fn sleep(duration: Duration) {
+    let (tx, rx) = oneshot::channel();
+    let millis = u32::try_from(duration.as_millis()).unwrap_or(u32::MAX);
+    spawn_local(async move {
+        TimeoutFuture::new(millis).await;
+        tx.send(()).ok();
+    });
+    rx.await.ok();
+}
+
+/// The ETag and body of a successful response that came with an ETag, to be cached once the body parsed
+fn etag_entry(response: &FetchResponse) -> Option<(String, String)> {
+    // JS lowercases header names
+    match (response.status, response.headers.get("etag"), &response.body) {
+        (StatusCode::OK, Some(etag), Some(FetchBody::Utf8(body))) => Some((etag.clone(), body.clone())),
+        _ => None,
+    }
+}
+
+/// The body of a successful response to a cacheable request, to be cached once it parsed
+fn cacheable_body(response: &FetchResponse) -> Option<String> {
+    match (response.status, &response.body) {
+        (StatusCode::OK, Some(FetchBody::Utf8(body))) => Some(body.clone()),
+        (StatusCode::OK, Some(FetchBody::Json(body))) => Some(body.to_string()),
+        _ => None,
+    }
+}
+
+fn parse_response<R: SiaApiRequest>(response: FetchResponse) -> Result<R::Response, ApiClientError> {
+    match response.status {
+        StatusCode::OK => {
+            let response_body = match response.body {
+                Some(FetchBody::Json(body)) => serde_json::from_value(body).map_err(ApiClientError::Serde)?,
+                Some(FetchBody::Utf8(body)) => serde_json::from_str(&body).map_err(ApiClientError::Serde)?,
+                _ => {
+                    return Err(ApiClientError::FixmePlaceholder(
+                        "Unsupported body type in response".to_string(),
+                    ))
+                },
+            };
+            Ok(response_body)
+        },
+        StatusCode::NO_CONTENT => {
+            if let Some(resp_type) = R::is_empty_response() {
+                Ok(resp_type)
+            } else {
+                Err(ApiClientError::UnexpectedEmptyResponse {
+                    expected_type: std::any::type_name::<R::Response>().to_string(),
+                })
+            }
+        },
+        status => {
+            // Extract the body as is if it's text, otherwise use the Display implementation of Body
+            let body = match response.body {
+                Some(FetchBody::Utf8(body)) => body,
+                Some(body) => format!("{}", body),
+                None => "".to_string(), // If body is None, use an empty string
+            };
+            // header names are lowercased by fetch
+            let retry_after = response.headers.get("retry-after").map(String::as_str);
+
+            Err(ApiClientError::UnexpectedHttpStatus { status, body }.with_retry_after(retry_after))
+        },
+    }
+}
+
+impl WasmClient {
+    /// Report a `RequestSample` of every request to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
 }
 
 #[async_trait]
-impl ApiClient for Client {
+impl ApiClient for WasmClient {
     type Request = FetchRequest;
     type Response = FetchResponse;
     type Conf = Conf;
 
     async fn new(conf: Self::Conf) -> Result<Self, ApiClientError> {
-        let client = Client {
-            base_url: conf.server_url,
+        let client = WasmClient {
+            nodes: resolve_nodes(&conf.server_url, &conf.fallback_urls, conf.base_path.as_deref())?,
             headers: conf.headers,
+            default_opts: RequestOpts {
+                deadline: conf.deadline.map(Duration::from_secs),
+                timeout: None,
+                cancel: None,
+            },
+            circuit_breaker: conf.circuit_breaker.map(CircuitBreaker::from),
+            throttle: conf.throttle.map(AdaptiveThrottle::from),
+            etag_cache: conf.etag_cache.then(EtagCache::default),
+            response_cache: conf.response_cache.map(ResponseCache::from),
+            slow_request_threshold: conf.slow_request_threshold.map(Duration::from_millis),
+            retry_policy: conf.retry.map(RetryPolicy::from),
+            metrics: None,
         };
-        // Ping the server with ConsensusTipRequest to check if the client is working
-        client.dispatcher(ConsensusTipRequest).await?;
+        if !conf.skip_health_check {
+            startup_ping(&client).await?;
+        }
         Ok(client)
     }
 
     fn process_schema(&self, schema: EndpointSchema) -> Result<Self::Request, ApiClientError> {
-        let url = schema.build_url(&self.base_url)?;
+        let url = schema.build_url(self.nodes.active())?;
         let method = match schema.method {
             SchemaMethod::Get => FetchMethod::Get,
             SchemaMethod::Post => FetchMethod::Post,
-            _ => return Err(ApiClientError::FixmePlaceholder("Unsupported method".to_string())),
+            SchemaMethod::Put => FetchMethod::Put,
+            SchemaMethod::Delete => FetchMethod::Delete,
         };
         let body = match schema.body {
             Body::Utf8(body) => Some(FetchBody::Utf8(body)),
@@ -61,59 +300,75 @@ impl ApiClient for Client {
     }
 
     async fn execute_request(&self, request: Self::Request) -> Result<Self::Response, ApiClientError> {
-        request
-            .execute()
-            .await
-            .map_err(|e| ApiClientError::FixmePlaceholder(format!("FIXME {}", e)))
+        request.execute().await.map_err(ApiClientError::WasmFetchError)
     }
 
-    // Dispatcher function that converts the request and handles execution
-    async fn dispatcher<R: SiaApiRequest>(&self, request: R) -> Result<R::Response, ApiClientError> {
-        let request = self.to_data_request(request)?; // Convert request to data request
+    // Convert the request, execute it and handle the response
+    async fn dispatch_once<R: SiaApiRequest>(&self, request: &R) -> Result<R::Response, ApiClientError> {
+        let cache_slot = self.response_cache.as_ref().and_then(|_| ResponseCache::slot(request));
+        let mut data_request = self.to_data_request(request)?; // Convert request to data request
+        let endpoint = data_request.uri.path().to_owned();
+
+        let etag_cache = match &self.etag_cache {
+            Some(cache) if matches!(data_request.method, FetchMethod::Get) => Some(cache),
+            _ => None,
+        };
+        let url = data_request.uri.clone();
+        let cached = etag_cache.and_then(|cache| cache.get(&url));
+        if let Some(cached) = &cached {
+            data_request.headers.insert("If-None-Match".to_owned(), cached.etag.clone());
+        }
 
         // Execute the request
-        let response = self.execute_request(request).await?;
-
-        match response.status {
-            StatusCode::OK => {
-                let response_body = match response.body {
-                    Some(FetchBody::Json(body)) => serde_json::from_value(body).map_err(ApiClientError::Serde)?,
-                    Some(FetchBody::Utf8(body)) => serde_json::from_str(&body).map_err(ApiClientError::Serde)?,
-                    _ => {
-                        return Err(ApiClientError::FixmePlaceholder(
-                            "Unsupported body type in response".to_string(),
-                        ))
-                    },
-                };
-                Ok(response_body)
+        let result = match self.execute_request(data_request).await {
+            Ok(response) => match (response.status, cached) {
+                (StatusCode::NOT_MODIFIED, Some(cached)) => cached.parse(),
+                _ => {
+                    let etag = etag_cache.and_then(|cache| etag_entry(&response).map(|entry| (cache, entry)));
+                    let body = self
+                        .response_cache
+                        .as_ref()
+                        .zip(cache_slot)
+                        .and_then(|(cache, slot)| cacheable_body(&response).map(|body| (cache, slot, body)));
+                    let parsed = parse_response::<R>(response);
+                    // as on native, only a body that parsed is cached so a malformed one is not served again
+                    if parsed.is_ok() {
+                        if let Some((cache, (etag, body))) = etag {
+                            cache.store(url, etag, body);
+                        }
+                        if let Some((cache, slot, body)) = body {
+                            cache.store(slot, body);
+                        }
+                    }
+                    parsed
+                },
             },
-            StatusCode::NO_CONTENT => {
-                if let Some(resp_type) = R::is_empty_response() {
-                    Ok(resp_type)
-                } else {
-                    Err(ApiClientError::UnexpectedEmptyResponse {
-                        expected_type: std::any::type_name::<R::Response>().to_string(),
-                    })
-                }
-            },
-            status => {
-                // Extract the body, using the Display implementation of Body or an empty string
-                let body = response
-                    .body
-                    .map(|b| format!("{}", b)) // Use Display trait to format Body
-                    .unwrap_or_else(|| "".to_string()); // If body is None, use an empty string
-    
-                Err(ApiClientError::UnexpectedHttpStatus {
-                    status,
-                    body,
-                })
-            }
-        }
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| e.with_request_context(endpoint, request))
     }
+
+    fn base_url(&self) -> &Url { self.nodes.active() }
+
+    fn node_pool(&self) -> Option<&NodePool> { Some(&self.nodes) }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> { self.circuit_breaker.as_ref() }
+
+    fn throttle(&self) -> Option<&AdaptiveThrottle> { self.throttle.as_ref() }
+
+    fn slow_request_threshold(&self) -> Option<Duration> { self.slow_request_threshold }
+
+    fn retry_policy(&self) -> Option<&RetryPolicy> { self.retry_policy.as_ref() }
+
+    fn metrics(&self) -> Option<&dyn Metrics> { self.metrics.as_deref() }
+
+    fn response_cache(&self) -> Option<&ResponseCache> { self.response_cache.as_ref() }
+
+    fn default_opts(&self) -> RequestOpts { self.default_opts.clone() }
 }
 
-// Implement the optional helper methods for ExampleClient
+// Implement the optional helper methods for WasmClient
 // Just this is needed to implement the `ApiClientHelpers` trait
 // unless custom implementations for the traits methods are needed
 #[async_trait]
-impl ApiClientHelpers for Client {}
+impl ApiClientHelpers for WasmClient {}
@@ -2,7 +2,6 @@ use futures::channel::oneshot;
 use http::{HeaderMap, StatusCode};
 use js_sys::Uint8Array;
 use serde_json::Value as JsonValue;
-use serde_wasm_bindgen;
 use std::collections::HashMap;
 use std::fmt;
 use thiserror::Error;
@@ -55,6 +54,8 @@ pub enum FetchError {
 pub enum FetchMethod {
     Get,
     Post,
+    Put,
+    Delete,
 }
 
 impl FetchMethod {
@@ -62,6 +63,8 @@ impl FetchMethod {
         match self {
             FetchMethod::Get => "GET",
             FetchMethod::Post => "POST",
+            FetchMethod::Put => "PUT",
+            FetchMethod::Delete => "DELETE",
         }
     }
 }
@@ -91,8 +94,8 @@ impl Body {
                 let js_array = Uint8Array::from(bytes.as_slice());
                 Ok(js_array.into())
             },
-            Body::Json(json) => serde_wasm_bindgen::to_value(&json)
-                .map_err(|e| FetchError::InvalidBody(format!("Failed to serialize body to Json. err: {}", e))),
+            // sent as text, converting to a JS object would round integers above 2^53
+            Body::Json(json) => Ok(JsValue::from_str(&json.to_string())),
         }
     }
 }
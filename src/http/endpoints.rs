@@ -1,26 +1,91 @@
+use crate::http::client::cache::CachePolicy;
 use crate::http::client::{ApiClientError, Body, EndpointSchema, EndpointSchemaBuilder, SchemaMethod};
-use crate::transaction::{SiacoinElement, V1Transaction, V2Transaction};
-use crate::types::{Address, BlockID, Currency, Event, H256};
+use crate::spend_policy::{SpendPolicy, SpendPolicyHelper};
+use crate::transaction::{SiacoinElement, SiafundElement, V1Transaction, V2Transaction};
+use crate::types::{Address, Block, BlockID, ChainIndex, ConsensusState, Currency, Event, Network, TransactionID,
+                   TxpoolTransactions};
+use chrono::{DateTime, Utc};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use serde_with::{serde_as, FromInto};
 use std::collections::HashMap;
+use std::marker::PhantomData;
 
 const ENDPOINT_ADDRESSES_BALANCE: &str = "api/addresses/{address}/balance";
 const ENDPOINT_ADDRESSES_EVENTS: &str = "api/addresses/{address}/events";
+const ENDPOINT_ADDRESSES_EVENTS_UNCONFIRMED: &str = "api/addresses/{address}/events/unconfirmed";
 const ENDPOINT_ADDRESSES_UTXOS_SIACOIN: &str = "api/addresses/{address}/outputs/siacoin";
+const ENDPOINT_ADDRESSES_UTXOS_SIAFUND: &str = "api/addresses/{address}/outputs/siafund";
+const ENDPOINT_CONSENSUS_BLOCKS: &str = "api/consensus/blocks/{id}";
+const ENDPOINT_CONSENSUS_INDEX: &str = "api/consensus/index/{height}";
+const ENDPOINT_CONSENSUS_NETWORK: &str = "api/consensus/network";
 const ENDPOINT_CONSENSUS_TIP: &str = "api/consensus/tip";
+const ENDPOINT_CONSENSUS_TIPSTATE: &str = "api/consensus/tipstate";
+const ENDPOINT_DEBUG_MINE: &str = "api/debug/mine";
 const ENDPOINT_EVENTS: &str = "api/events/{txid}";
+const ENDPOINT_STATE: &str = "api/state";
 const ENDPOINT_TXPOOL_BROADCAST: &str = "api/txpool/broadcast";
 const ENDPOINT_TXPOOL_FEE: &str = "api/txpool/fee";
 const ENDPOINT_TXPOOL_TRANSACTIONS: &str = "api/txpool/transactions";
+const ENDPOINT_WALLETS: &str = "api/wallets";
+const ENDPOINT_WALLET: &str = "api/wallets/{id}";
+const ENDPOINT_WALLET_ADDRESSES: &str = "api/wallets/{id}/addresses";
+const ENDPOINT_WALLET_BALANCE: &str = "api/wallets/{id}/balance";
+const ENDPOINT_WALLET_EVENTS: &str = "api/wallets/{id}/events";
+const ENDPOINT_WALLET_EVENTS_UNCONFIRMED: &str = "api/wallets/{id}/events/unconfirmed";
 
-pub trait SiaApiRequest: Send {
+pub trait SiaApiRequest: Send + Sync {
     type Response: DeserializeOwned;
 
     // Applicable for requests that return HTTP 204 No Content
     fn is_empty_response() -> Option<Self::Response> { None }
 
     fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError>;
+
+    /// Whether and for how long a `ResponseCache` may answer this request, only GET requests are ever cached
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::NoCache }
+
+    /// The address the request is about, attached to its errors, see `ApiClientError::Endpoint`
+    fn context_address(&self) -> Option<&Address> { None }
+
+    /// The transaction the request is about, attached to its errors, see `ApiClientError::Endpoint`
+    fn context_txid(&self) -> Option<TransactionID> { None }
+}
+
+/// Sends `R` to its endpoint but deserializes the response as `T` instead of `R::Response`.
+///
+/// Useful to read fields walletd added that `R::Response` does not model yet, eg. with `T` being
+/// `serde_json::Value` or a caller defined struct. See `ApiClient::dispatch_as`.
+pub struct ResponseAs<R, T> {
+    pub request: R,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<R, T> ResponseAs<R, T> {
+    pub fn new(request: R) -> Self {
+        ResponseAs {
+            request,
+            response: PhantomData,
+        }
+    }
+}
+
+impl<R: SiaApiRequest, T: DeserializeOwned> SiaApiRequest for ResponseAs<R, T> {
+    type Response = T;
+
+    // an empty response is read as JSON `null`, so `T` can be eg. `Option<_>`, `()` or `serde_json::Value`
+    fn is_empty_response() -> Option<Self::Response> {
+        R::is_empty_response().and_then(|_| serde_json::from_value(JsonValue::Null).ok())
+    }
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> { self.request.to_endpoint_schema() }
+
+    fn cache_policy(&self) -> CachePolicy { self.request.cache_policy() }
+
+    fn context_address(&self) -> Option<&Address> { self.request.context_address() }
+
+    fn context_txid(&self) -> Option<TransactionID> { self.request.context_txid() }
 }
 
 /// Represents the request-response pair for fetching the current consensus tip of the Sia network.
@@ -50,14 +115,132 @@ impl SiaApiRequest for ConsensusTipRequest {
     fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
         Ok(EndpointSchemaBuilder::new(ENDPOINT_CONSENSUS_TIP.to_owned(), SchemaMethod::Get).build())
     }
+
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Tip }
+}
+
+pub type ConsensusTipResponse = ChainIndex;
+
+/// Represents the request-response pair for fetching the consensus state at the current tip.
+///
+/// # Walletd Endpoint
+/// `GET /consensus/tipstate`
+///
+/// # Description
+/// Returns the full consensus state as of the current tip, including the targets, the accumulated work and the
+/// state element accumulator that the next block is validated against.
+///
+/// # Response
+/// - The response is a `ConsensusState`.
+///   This corresponds to the `consensus.State` type in Go.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ConsensusTipStateRequest;
+
+impl SiaApiRequest for ConsensusTipStateRequest {
+    type Response = ConsensusState;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(ENDPOINT_CONSENSUS_TIPSTATE.to_owned(), SchemaMethod::Get).build())
+    }
+
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Tip }
+}
+
+/// Represents the request-response pair for fetching the parameters of the network the node runs on.
+///
+/// # Walletd Endpoint
+/// `GET /consensus/network`
+///
+/// # Description
+/// Returns the network's name, coinbase and target parameters, the maturity delay of payouts and the heights of
+/// every hardfork, eg. the heights from which v2 transactions are allowed and required.
+///
+/// # Response
+/// - The response is a `Network`.
+///   This corresponds to the `consensus.Network` type in Go.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+/// - [Go Source for the Network Type](https://github.com/SiaFoundation/core/blob/300042fd2129381468356dcd87c5e9a6ad94c0ef/consensus/state.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ConsensusNetworkRequest;
+
+impl SiaApiRequest for ConsensusNetworkRequest {
+    type Response = Network;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(ENDPOINT_CONSENSUS_NETWORK.to_owned(), SchemaMethod::Get).build())
+    }
+
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Immutable }
 }
 
+/// Represents the request-response pair for fetching the block at a height of the node's best chain.
+///
+/// # Walletd Endpoint
+/// `GET /consensus/index/:height`
+///
+/// # Response
+/// - The response is a `ChainIndex`, the height and ID of the block, corresponding to `types.ChainIndex` in Go.
+///   Use `ConsensusBlockRequest` with the ID to fetch the block itself.
 #[derive(Deserialize, Serialize, Debug)]
-pub struct ConsensusTipResponse {
+pub struct ConsensusIndexRequest {
     pub height: u64,
+}
+
+impl SiaApiRequest for ConsensusIndexRequest {
+    type Response = ChainIndex;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut path_params = HashMap::new();
+        path_params.insert("height".to_owned(), self.height.to_string());
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_CONSENSUS_INDEX.to_owned(), SchemaMethod::Get)
+                .path_params(path_params)
+                .build(),
+        )
+    }
+}
+
+/// Represents the request-response pair for fetching a block by its ID.
+///
+/// # Walletd Endpoint
+/// `GET /consensus/blocks/:id`
+///
+/// # Description
+/// Returns the full block, including its miner payouts and its v1 and v2 transactions. Blocks of reverted chains
+/// are only returned while the node still stores them. `ApiClientHelpers::block_at_height` fetches the block at a
+/// height of the best chain.
+///
+/// # Response
+/// - The response is a `Block`.
+///   This corresponds to the `types.Block` type in Go.
+///
+/// # References
+/// - [Go Source for the Block Type](https://github.com/SiaFoundation/core/blob/300042fd2129381468356dcd87c5e9a6ad94c0ef/types/types.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ConsensusBlockRequest {
     pub id: BlockID,
 }
 
+impl SiaApiRequest for ConsensusBlockRequest {
+    type Response = Block;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut path_params = HashMap::new();
+        path_params.insert("id".to_owned(), self.id.to_string());
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_CONSENSUS_BLOCKS.to_owned(), SchemaMethod::Get)
+                .path_params(path_params)
+                .build(),
+        )
+    }
+
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Immutable }
+}
+
 /// Represents the request-response pair for fetching the balance of an individual address.
 ///
 /// # Walletd Endpoint
@@ -99,13 +282,20 @@ impl SiaApiRequest for AddressBalanceRequest {
                 .build(),
         )
     }
+
+    fn context_address(&self) -> Option<&Address> { Some(&self.address) }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct AddressBalanceResponse {
+    /// Explorers name it `unspentSiacoins`, see `ExplorerClient`
+    #[serde(alias = "unspentSiacoins")]
     pub siacoins: Currency,
     #[serde(rename = "immatureSiacoins")]
     pub immature_siacoins: Currency,
+    /// Older walletd versions omit it
+    #[serde(default, alias = "unspentSiafunds")]
+    pub siafunds: u64,
 }
 
 /// Represents the request-response pair for fetching a specific event by transaction ID (txid).
@@ -117,7 +307,8 @@ pub struct AddressBalanceResponse {
 /// Fetches an event based on the provided transaction ID (txid).
 ///
 /// # Fields
-/// - `txid`: The transaction ID for which to fetch the event. In Go, this corresponds to `types.Hash256`.
+/// - `txid`: The transaction ID for which to fetch the event, which is also the ID of the event. In Go, this
+///   corresponds to `types.Hash256`.
 ///   - [Go Source for Hash256](https://github.com/SiaFoundation/core/blob/300042fd2129381468356dcd87c5e9a6ad94c0ef/types/types.go#L63)
 ///
 /// # Response
@@ -130,7 +321,7 @@ pub struct AddressBalanceResponse {
 /// This type is ported from the Go codebase, representing the equivalent request-response pair in Rust.
 #[derive(Deserialize, Serialize, Debug)]
 pub struct GetEventRequest {
-    pub txid: H256,
+    pub txid: TransactionID,
 }
 
 impl SiaApiRequest for GetEventRequest {
@@ -139,7 +330,8 @@ impl SiaApiRequest for GetEventRequest {
     fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
         // Create the path_params HashMap to substitute {txid} in the path schema
         let mut path_params = HashMap::new();
-        path_params.insert("txid".to_owned(), self.txid.to_string());
+        // walletd accepts the bare hex of any hash here, whatever the prefix of the ID
+        path_params.insert("txid".to_owned(), self.txid.0.to_string());
 
         Ok(
             EndpointSchemaBuilder::new(ENDPOINT_EVENTS.to_owned(), SchemaMethod::Get)
@@ -147,6 +339,10 @@ impl SiaApiRequest for GetEventRequest {
                 .build(),
         )
     }
+
+    fn cache_policy(&self) -> CachePolicy { CachePolicy::Confirmed }
+
+    fn context_txid(&self) -> Option<TransactionID> { Some(self.txid) }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -159,14 +355,18 @@ pub struct GetEventResponse(pub Event);
 ///
 /// # Fields
 /// - `addr`: (`types.Address` in Go) the address for which events are fetched.
-/// - `limit`: (`i64` in Go) optional limit for the number of results.
+/// - `limit`: (`i64` in Go) optional limit for the number of results. walletd defaults to 100 and rejects
+///   limits above 1000.
 /// - `offset`: (`i64` in Go) optional offset for paginated results.
 ///
 /// # Response
-/// - `[]types.Event` in Go corresponds to `Vec<Event>` in Rust.
+/// - `[]types.Event` in Go corresponds to `Vec<Event>` in Rust, newest first.
 ///   - An event represents an on-chain event capable of influencing the state of a wallet.
 ///   - As per comments in the Go source: "Events can either be created by sending Siacoins between
 ///     addresses or they can be created by consensus (e.g. a miner payout, a siafund claim, or a contract)."
+///   - The kind of event is given by `Event::event_type`, its typed payload by `Event::data`.
+///
+/// Use `ApiClientHelpers::address_events_paged` to walk every page.
 ///
 /// # References
 /// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/134a28b063df60a687899ac33aa373bf461480bc/api/server.go#L761)
@@ -174,13 +374,13 @@ pub struct GetEventResponse(pub Event);
 ///
 /// This type is ported from the Go codebase, representing the equivalent request-response pair in Rust.
 #[derive(Deserialize, Serialize, Debug)]
-pub struct AddressesEventsRequest {
+pub struct AddressEventsRequest {
     pub address: Address,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
-impl SiaApiRequest for AddressesEventsRequest {
+impl SiaApiRequest for AddressEventsRequest {
     type Response = Vec<Event>;
 
     fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
@@ -202,9 +402,11 @@ impl SiaApiRequest for AddressesEventsRequest {
                 .build(),
         )
     }
+
+    fn context_address(&self) -> Option<&Address> { Some(&self.address) }
 }
 
-pub type AddressesEventsResponse = Vec<Event>;
+pub type AddressEventsResponse = Vec<Event>;
 
 /// Represents the request-response pair for getting Siacoin UTXOs owned by a specific address.
 ///
@@ -259,6 +461,59 @@ impl SiaApiRequest for GetAddressUtxosRequest {
                 .build(),
         )
     }
+
+    fn context_address(&self) -> Option<&Address> { Some(&self.address) }
+}
+
+/// Represents the request-response pair for getting Siafund UTXOs owned by a specific address.
+///
+/// # Walletd Endpoint
+/// `GET /addresses/:addr/outputs/siafund`
+///
+/// # Description
+/// Fetches any Siafund unspent transaction outputs (UTXOs) owned by the specified address. Paginated like
+/// `GetAddressUtxosRequest`.
+///
+/// # Response
+/// - The response is a `Vec<SiafundElement>` in Rust, corresponding to `[]types.SiafundElement` in Go.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+///
+/// This type is ported from the Go codebase, representing the equivalent request-response pair in Rust.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GetAddressSiafundUtxosRequest {
+    pub address: Address,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub type GetAddressSiafundUtxosResponse = Vec<SiafundElement>;
+
+impl SiaApiRequest for GetAddressSiafundUtxosRequest {
+    type Response = GetAddressSiafundUtxosResponse;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut path_params = HashMap::new();
+        path_params.insert("address".to_owned(), self.address.to_string());
+
+        let mut query_params = HashMap::new();
+        if let Some(limit) = self.limit {
+            query_params.insert("limit".to_owned(), limit.to_string());
+        }
+        if let Some(offset) = self.offset {
+            query_params.insert("offset".to_owned(), offset.to_string());
+        }
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_ADDRESSES_UTXOS_SIAFUND.to_owned(), SchemaMethod::Get)
+                .path_params(path_params)
+                .query_params(query_params)
+                .build(),
+        )
+    }
+
+    fn context_address(&self) -> Option<&Address> { Some(&self.address) }
 }
 
 /// Represents the request-response pair for broadcasting transactions.
@@ -314,6 +569,14 @@ impl SiaApiRequest for TxpoolBroadcastRequest {
                 .build(),
         )
     }
+
+    fn context_txid(&self) -> Option<TransactionID> {
+        match (&self.transactions[..], &self.v2transactions[..]) {
+            ([tx], []) => Some(tx.txid()),
+            ([], [tx]) => Some(tx.txid()),
+            _ => None,
+        }
+    }
 }
 
 /// Represents the request-response pair for fetching the current fee to broadcast a transaction.
@@ -328,6 +591,7 @@ impl SiaApiRequest for TxpoolBroadcastRequest {
 ///
 /// Most transactions are less than 1000 bytes, so using 1000 bytes as a constant size will work for
 /// most transactions.
+/// `ApiClientHelpers::estimate_miner_fee` does this multiplication.
 ///
 /// # Response
 /// - The response is a `types.Currency` from the Go codebase, represented as a `String` in Rust.
@@ -365,7 +629,8 @@ impl SiaApiRequest for TxpoolFeeRequest {
 /// with any registered wallet.
 ///
 /// # Response
-/// - This request returns `HTTP 204 NO CONTENT` in Go, which is represented by `EmptyResponse` in Rust.
+/// - The response is a `TxpoolTransactionsResponse`, corresponding to `api.TxpoolTransactionsResponse` in Go.
+///   It contains the v1 and v2 transactions of the pool in separate lists.
 ///
 /// # References
 /// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go#L282C18-L282C43)
@@ -374,14 +639,396 @@ impl SiaApiRequest for TxpoolFeeRequest {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct TxpoolTransactionsRequest;
 
+pub type TxpoolTransactionsResponse = TxpoolTransactions;
+
 impl SiaApiRequest for TxpoolTransactionsRequest {
+    type Response = TxpoolTransactionsResponse;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_TXPOOL_TRANSACTIONS.to_owned(), SchemaMethod::Get).build(), // No path_params, query_params, or body needed for this request
+        )
+    }
+}
+
+/// Represents the request-response pair for fetching the state of the walletd daemon itself.
+///
+/// # Walletd Endpoint
+/// `GET /state`
+///
+/// # Description
+/// Returns build and runtime information about the daemon, most notably its version.
+///
+/// # Response
+/// - The response is a `StateResponse`. Only the fields common to all walletd versions are included.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+///
+/// This type is ported from the Go codebase, representing the equivalent request-response pair in Rust.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StateRequest;
+
+impl SiaApiRequest for StateRequest {
+    type Response = StateResponse;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(ENDPOINT_STATE.to_owned(), SchemaMethod::Get).build())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StateResponse {
+    pub version: String,
+    #[serde(default)]
+    pub commit: String,
+    #[serde(default)]
+    pub os: String,
+}
+
+/// A wallet of walletd's wallet subsystem, grouping addresses whose balance and events walletd tracks together.
+///
+/// This corresponds to `wallet.Wallet` in Go. `metadata` is arbitrary JSON stored alongside the wallet.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Wallet {
+    pub id: WalletID,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub date_created: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+    #[serde(default)]
+    pub metadata: JsonValue,
+}
+
+/// ID walletd assigns to a wallet when it is created, `wallet.ID` in Go
+pub type WalletID = i64;
+
+/// An address added to a wallet, `wallet.Address` in Go
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletAddress {
+    pub address: Address,
+    #[serde(default)]
+    pub description: String,
+    /// Needed by walletd to construct transactions spending the address' outputs
+    #[serde_as(as = "Option<FromInto<SpendPolicyHelper>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend_policy: Option<SpendPolicy>,
+    #[serde(default)]
+    pub metadata: JsonValue,
+}
+
+fn wallet_path_params(id: WalletID) -> HashMap<String, String> {
+    let mut path_params = HashMap::new();
+    path_params.insert("id".to_owned(), id.to_string());
+    path_params
+}
+
+/// Represents the request-response pair for listing the wallets of walletd's wallet subsystem.
+///
+/// # Walletd Endpoint
+/// `GET /wallets`
+///
+/// # Response
+/// - The response is a `Vec<Wallet>`, corresponding to `[]wallet.Wallet` in Go.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalletsRequest;
+
+impl SiaApiRequest for WalletsRequest {
+    type Response = Vec<Wallet>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(ENDPOINT_WALLETS.to_owned(), SchemaMethod::Get).build())
+    }
+}
+
+/// Represents the request-response pair for creating a wallet.
+///
+/// # Walletd Endpoint
+/// `POST /wallets`
+///
+/// # Description
+/// Creates an empty wallet, addresses are added with `AddWalletAddressRequest`. The body corresponds to
+/// `api.WalletUpdateRequest` in Go.
+///
+/// # Response
+/// - The response is the created `Wallet`, including the ID walletd assigned to it.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AddWalletRequest {
+    pub name: String,
+    pub description: String,
+    pub metadata: JsonValue,
+}
+
+impl SiaApiRequest for AddWalletRequest {
+    type Response = Wallet;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let body = serde_json::to_string(self).map_err(ApiClientError::Serde)?;
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLETS.to_owned(), SchemaMethod::Post)
+                .body(Body::Utf8(body))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request for deleting a wallet.
+///
+/// # Walletd Endpoint
+/// `DELETE /wallets/:id`
+///
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeleteWalletRequest {
+    pub id: WalletID,
+}
+
+impl SiaApiRequest for DeleteWalletRequest {
     type Response = EmptyResponse;
 
     fn is_empty_response() -> Option<Self::Response> { Some(EmptyResponse) }
 
     fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
         Ok(
-            EndpointSchemaBuilder::new(ENDPOINT_TXPOOL_TRANSACTIONS.to_owned(), SchemaMethod::Get).build(), // No path_params, query_params, or body needed for this request
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET.to_owned(), SchemaMethod::Delete)
+                .path_params(wallet_path_params(self.id))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request-response pair for listing the addresses of a wallet.
+///
+/// # Walletd Endpoint
+/// `GET /wallets/:id/addresses`
+///
+/// # Response
+/// - The response is a `Vec<WalletAddress>`, corresponding to `[]wallet.Address` in Go.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalletAddressesRequest {
+    pub id: WalletID,
+}
+
+impl SiaApiRequest for WalletAddressesRequest {
+    type Response = Vec<WalletAddress>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET_ADDRESSES.to_owned(), SchemaMethod::Get)
+                .path_params(wallet_path_params(self.id))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request for adding an address to a wallet.
+///
+/// # Walletd Endpoint
+/// `PUT /wallets/:id/addresses`
+///
+/// # Description
+/// Adds `address` to the wallet, or updates its description, spend policy and metadata if the wallet already
+/// contains it.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AddWalletAddressRequest {
+    pub id: WalletID,
+    pub address: WalletAddress,
+}
+
+impl SiaApiRequest for AddWalletAddressRequest {
+    type Response = EmptyResponse;
+
+    fn is_empty_response() -> Option<Self::Response> { Some(EmptyResponse) }
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let body = serde_json::to_string(&self.address).map_err(ApiClientError::Serde)?;
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET_ADDRESSES.to_owned(), SchemaMethod::Put)
+                .path_params(wallet_path_params(self.id))
+                .body(Body::Utf8(body))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request-response pair for fetching the combined balance of a wallet's addresses.
+///
+/// # Walletd Endpoint
+/// `GET /wallets/:id/balance`
+///
+/// # Response
+/// - The response is an `AddressBalanceResponse`, the same shape walletd returns for a single address.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalletBalanceRequest {
+    pub id: WalletID,
+}
+
+impl SiaApiRequest for WalletBalanceRequest {
+    type Response = AddressBalanceResponse;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET_BALANCE.to_owned(), SchemaMethod::Get)
+                .path_params(wallet_path_params(self.id))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request-response pair for fetching the confirmed events of a wallet's addresses.
+///
+/// # Walletd Endpoint
+/// `GET /wallets/:id/events`
+///
+/// # Description
+/// Paginated like `AddressEventsRequest`, newest first.
+///
+/// # Response
+/// - The response is a `Vec<Event>`, corresponding to `[]wallet.Event` in Go.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalletEventsRequest {
+    pub id: WalletID,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl SiaApiRequest for WalletEventsRequest {
+    type Response = Vec<Event>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut query_params = HashMap::new();
+        if let Some(limit) = self.limit {
+            query_params.insert("limit".to_owned(), limit.to_string());
+        }
+        if let Some(offset) = self.offset {
+            query_params.insert("offset".to_owned(), offset.to_string());
+        }
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET_EVENTS.to_owned(), SchemaMethod::Get)
+                .path_params(wallet_path_params(self.id))
+                .query_params(query_params)
+                .build(),
         )
     }
 }
+
+/// Represents the request-response pair for fetching the events of an address's transactions that are still in
+/// the txpool.
+///
+/// # Walletd Endpoint
+/// `GET /addresses/:addr/events/unconfirmed`
+///
+/// # Description
+/// The events are built from the txpool, so their `index` is the current tip and they change or disappear as
+/// transactions are confirmed, replaced or evicted. `ApiClientHelpers::address_unconfirmed` sums them into the
+/// pending incoming and outgoing amounts.
+///
+/// # Response
+/// - The response is a `Vec<Event>`, corresponding to `[]wallet.Event` in Go.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AddressUnconfirmedEventsRequest {
+    pub address: Address,
+}
+
+impl SiaApiRequest for AddressUnconfirmedEventsRequest {
+    type Response = Vec<Event>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let mut path_params = HashMap::new();
+        path_params.insert("address".to_owned(), self.address.to_string());
+
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_ADDRESSES_EVENTS_UNCONFIRMED.to_owned(), SchemaMethod::Get)
+                .path_params(path_params)
+                .build(),
+        )
+    }
+
+    fn context_address(&self) -> Option<&Address> { Some(&self.address) }
+}
+
+/// Represents the request-response pair for fetching the events of a wallet's transactions that are still in
+/// the txpool.
+///
+/// # Walletd Endpoint
+/// `GET /wallets/:id/events/unconfirmed`
+///
+/// # Response
+/// - The response is a `Vec<Event>`, corresponding to `[]wallet.Event` in Go.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WalletUnconfirmedEventsRequest {
+    pub id: WalletID,
+}
+
+impl SiaApiRequest for WalletUnconfirmedEventsRequest {
+    type Response = Vec<Event>;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_WALLET_EVENTS_UNCONFIRMED.to_owned(), SchemaMethod::Get)
+                .path_params(wallet_path_params(self.id))
+                .build(),
+        )
+    }
+}
+
+/// Represents the request to mine blocks on the node itself.
+///
+/// # Walletd Endpoint
+/// `POST /debug/mine`
+///
+/// # Description
+/// Mines `blocks` blocks paying the miner payouts to `address`. Only routed if walletd runs with `-debug`,
+/// which is meant for test networks, eg. to fund addresses in integration tests.
+///
+/// # References
+/// - [Go Source for the HTTP Endpoint](https://github.com/SiaFoundation/walletd/blob/6ff23fe34f6fa45a19bfb6e4bacc8a16d2c48144/api/server.go)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DebugMineRequest {
+    pub blocks: u64,
+    pub address: Address,
+}
+
+impl SiaApiRequest for DebugMineRequest {
+    type Response = EmptyResponse;
+
+    fn is_empty_response() -> Option<Self::Response> { Some(EmptyResponse) }
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        let body = serde_json::to_string(self).map_err(ApiClientError::Serde)?;
+        Ok(
+            EndpointSchemaBuilder::new(ENDPOINT_DEBUG_MINE.to_owned(), SchemaMethod::Post)
+                .body(Body::Utf8(body))
+                .build(),
+        )
+    }
+}
+
+/// Issues a `GET` to an arbitrary path to check whether the server routes it.
+///
+/// The response body is not interpreted. Routes that only accept other methods respond with
+/// `405 Method Not Allowed`, which still indicates the route exists. See `capabilities::route_supported`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RouteProbeRequest {
+    pub path: String,
+}
+
+impl SiaApiRequest for RouteProbeRequest {
+    type Response = JsonValue;
+
+    fn is_empty_response() -> Option<Self::Response> { Some(JsonValue::Null) }
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(self.path.clone(), SchemaMethod::Get).build())
+    }
+}
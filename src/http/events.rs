@@ -0,0 +1,667 @@
+use crate::http::client::{sleep, ApiClient, ApiClientError};
+use crate::http::endpoints::{AddressEventsRequest, ConsensusTipRequest, GetEventRequest};
+use crate::http::tracker::is_not_found;
+use crate::types::{Address, ChainIndex, Currency, Event, EventDataWrapper, EventID, TransactionID};
+use core::time::Duration;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use instant::Instant;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct SubscriptionConf {
+    /// Time between two polls of the node
+    pub poll_interval: Duration,
+    /// Number of most recent events of the address fetched per poll. Events older than the window are
+    /// neither reported as new nor checked for reversals.
+    pub window: i64,
+}
+
+impl Default for SubscriptionConf {
+    fn default() -> Self {
+        SubscriptionConf {
+            poll_interval: Duration::from_secs(10),
+            window: 100,
+        }
+    }
+}
+
+/// A change to the events of an address, as yielded by `subscribe_address_events`
+#[derive(Clone, Debug)]
+pub enum AddressEvent {
+    /// An event not seen on a previous poll. `received` and `sent` are the siacoins it moved into and out of
+    /// the address, so an incoming payment has a `received` above its `sent`.
+    New {
+        event: Event,
+        received: Currency,
+        sent: Currency,
+    },
+    /// An event seen on a previous poll was reverted by a reorg. It is reported as `New` again if it is
+    /// included in the new chain.
+    Reverted { id: EventID, index: ChainIndex },
+}
+
+impl AddressEvent {
+    /// Whether this is a new event moving more siacoins into the address than out of it
+    pub fn is_incoming_payment(&self) -> bool {
+        matches!(self, AddressEvent::New { received, sent, .. } if received.0 > sent.0)
+    }
+}
+
+/// A change to the confirmation status of a transaction, as yielded by `subscribe_confirmed_transaction`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfirmationEvent {
+    /// The transaction is included in the block at `index`. Yielded again whenever the number of
+    /// confirmations grows, a transaction in the tip block has 1 confirmation.
+    Confirmed { index: ChainIndex, confirmations: u64 },
+    /// The block at `index` including the transaction was reverted by a reorg
+    Reverted { index: ChainIndex },
+}
+
+/// How `wait_for_confirmations` ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfirmationOutcome {
+    /// The transaction reached the requested confirmations in the block at `height`
+    Confirmed { height: u64 },
+    /// The block at `index` including the transaction was reverted before it reached the requested confirmations
+    Reorged { index: ChainIndex },
+    /// The timeout elapsed first, the transaction may still confirm later
+    TimedOut,
+}
+
+/// The siacoins `event` moved into and out of `address`
+pub fn event_amounts(event: &Event, address: &Address) -> (Currency, Currency) {
+    let mut received = Currency::default();
+    let mut sent = Currency::default();
+    match &event.data {
+        EventDataWrapper::MinerPayout(payout)
+        | EventDataWrapper::FoundationPayout(payout)
+        | EventDataWrapper::ClaimPayout(payout) => {
+            if &payout.siacoin_element.siacoin_output.address == address {
+                received += payout.siacoin_element.siacoin_output.value;
+            }
+        },
+        EventDataWrapper::V2Transaction(tx) => {
+            for input in tx.siacoin_inputs.iter() {
+                if &input.parent.siacoin_output.address == address {
+                    sent += input.parent.siacoin_output.value;
+                }
+            }
+            for output in tx.siacoin_outputs.iter() {
+                if &output.address == address {
+                    received += output.value;
+                }
+            }
+        },
+        EventDataWrapper::V1Transaction(tx) => {
+            for element in tx.spent_siacoin_elements.iter() {
+                if &element.siacoin_output.address == address {
+                    sent += element.siacoin_output.value;
+                }
+            }
+            for output in tx.transaction.siacoin_outputs.iter() {
+                if &output.address == address {
+                    received += output.value;
+                }
+            }
+        },
+        EventDataWrapper::V2FileContractResolution(resolution) => {
+            if &resolution.siacoin_element.siacoin_output.address == address {
+                received += resolution.siacoin_element.siacoin_output.value;
+            }
+        },
+        EventDataWrapper::EventV1ContractResolution(resolution) => {
+            if &resolution.siacoin_element.siacoin_output.address == address {
+                received += resolution.siacoin_element.siacoin_output.value;
+            }
+        },
+    }
+    (received, sent)
+}
+
+/// The txpool activity of an address, as returned by `ApiClientHelpers::address_unconfirmed`
+#[derive(Clone, Debug, Default)]
+pub struct UnconfirmedActivity {
+    /// The unconfirmed events of the address
+    pub events: Vec<Event>,
+    /// Siacoins the unconfirmed events move into the address, net of what each of them spends from it
+    pub incoming: Currency,
+    /// Siacoins the unconfirmed events move out of the address, net of the change each of them returns to it
+    pub outgoing: Currency,
+}
+
+impl UnconfirmedActivity {
+    /// Net each event with `event_amounts`, so a payment with change counts only its spent amount as outgoing
+    pub fn new(events: Vec<Event>, address: &Address) -> Self {
+        let mut incoming = Currency::default();
+        let mut outgoing = Currency::default();
+        for event in events.iter() {
+            let (received, sent) = event_amounts(event, address);
+            if received.0 > sent.0 {
+                incoming += Currency(received.0 - sent.0);
+            } else {
+                outgoing += Currency(sent.0 - received.0);
+            }
+        }
+        UnconfirmedActivity {
+            events,
+            incoming,
+            outgoing,
+        }
+    }
+}
+
+/// The events of an address seen so far, diffed against each poll of its most recent events
+#[derive(Clone, Debug)]
+pub struct AddressEventsState {
+    address: Address,
+    window: usize,
+    seen: HashMap<EventID, ChainIndex>,
+}
+
+impl AddressEventsState {
+    pub fn new(address: Address, window: usize) -> Self {
+        AddressEventsState {
+            address,
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Diff `fetched`, the most recent events of the address, against the events seen so far.
+    ///
+    /// An event seen before is reverted if it is missing from `fetched` although it is within the window,
+    /// ie. at or above the lowest height in `fetched` or anywhere if `fetched` is not full, or if it moved
+    /// to another block.
+    pub fn update(&mut self, fetched: Vec<Event>) -> Vec<AddressEvent> {
+        let lowest = if fetched.len() < self.window {
+            0
+        } else {
+            fetched.iter().map(|event| event.index.height).min().unwrap_or(0)
+        };
+        let fetched_index: HashMap<EventID, ChainIndex> = fetched.iter().map(|event| (event.id, event.index)).collect();
+
+        let mut reverted: Vec<(EventID, ChainIndex)> = self
+            .seen
+            .iter()
+            .filter(|(id, index)| match fetched_index.get(*id) {
+                Some(fetched) => fetched != *index,
+                None => index.height >= lowest,
+            })
+            .map(|(id, index)| (*id, *index))
+            .collect();
+        // newest first, matching the order walletd returns events in
+        reverted.sort_by_key(|(_, index)| std::cmp::Reverse(index.height));
+
+        let mut changes = Vec::new();
+        for (id, index) in reverted {
+            self.seen.remove(&id);
+            changes.push(AddressEvent::Reverted { id, index });
+        }
+        // report new events oldest first so callers observe them in chain order
+        for event in fetched.into_iter().rev() {
+            if self.seen.insert(event.id, event.index).is_none() {
+                let (received, sent) = event_amounts(&event, &self.address);
+                changes.push(AddressEvent::New { event, received, sent });
+            }
+        }
+        self.seen.retain(|_, index| index.height >= lowest);
+        changes
+    }
+}
+
+/// The confirmation status of a transaction, diffed against each poll
+#[derive(Clone, Debug, Default)]
+pub struct ConfirmationState {
+    last: Option<(ChainIndex, u64)>,
+}
+
+impl ConfirmationState {
+    /// Diff the block including the transaction, if any, and the current tip height against the last poll
+    pub fn update(&mut self, included_in: Option<ChainIndex>, tip_height: u64) -> Vec<ConfirmationEvent> {
+        let mut changes = Vec::new();
+        let current = included_in.map(|index| (index, tip_height.saturating_sub(index.height) + 1));
+        match (self.last, current) {
+            (Some((last, _)), Some((index, _))) if last != index => {
+                changes.push(ConfirmationEvent::Reverted { index: last });
+            },
+            (Some((last, _)), None) => changes.push(ConfirmationEvent::Reverted { index: last }),
+            _ => (),
+        }
+        if let Some((index, confirmations)) = current {
+            if self.last.map_or(true, |(last, n)| last != index || n < confirmations) {
+                changes.push(ConfirmationEvent::Confirmed { index, confirmations });
+            }
+        }
+        self.last = current;
+        changes
+    }
+}
+
+/// Poll `address`'s events every `conf.poll_interval` and yield the changes, see `AddressEvent`.
+///
+/// Events already present on the first poll are yielded as `AddressEvent::New` as well. A failed poll yields
+/// the error and polling continues after the next interval; drop the stream to unsubscribe.
+pub fn subscribe_address_events<C>(
+    client: C,
+    address: Address,
+    conf: SubscriptionConf,
+) -> impl Stream<Item = Result<AddressEvent, ApiClientError>>
+where
+    C: ApiClient + Send + Sync + 'static,
+{
+    let SubscriptionConf { poll_interval, window } = conf;
+    let state = AddressEventsState::new(address, window.max(1) as usize);
+    let polls = stream::unfold((client, state, true), move |(client, mut state, first)| async move {
+        if !first {
+            sleep(poll_interval).await;
+        }
+        let request = AddressEventsRequest {
+            address: state.address.clone(),
+            limit: Some(window),
+            offset: None,
+        };
+        let changes = match client.dispatcher(request).await {
+            Ok(events) => state.update(events).into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        Some((stream::iter(changes), (client, state, false)))
+    });
+    polls.flatten()
+}
+
+/// Poll the event of transaction `txid` and the consensus tip every `poll_interval` and yield the changes to
+/// its confirmation status, see `ConfirmationEvent`.
+///
+/// Nothing is yielded while the transaction is unconfirmed. A failed poll yields the error and polling
+/// continues after the next interval; drop the stream to unsubscribe.
+pub fn subscribe_confirmed_transaction<C>(
+    client: C,
+    txid: TransactionID,
+    poll_interval: Duration,
+) -> impl Stream<Item = Result<ConfirmationEvent, ApiClientError>>
+where
+    C: ApiClient + Send + Sync + 'static,
+{
+    let state = ConfirmationState::default();
+    let polls = stream::unfold((client, state, true), move |(client, mut state, first)| async move {
+        if !first {
+            sleep(poll_interval).await;
+        }
+        let changes = match poll_confirmation(&client, txid).await {
+            Ok((included_in, tip_height)) => state.update(included_in, tip_height).into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        Some((stream::iter(changes), (client, state, false)))
+    });
+    polls.flatten()
+}
+
+/// Poll like `subscribe_confirmed_transaction` until transaction `txid` has `confirmations` confirmations, the
+/// block including it is reverted or `timeout` elapses.
+///
+/// A reorg is detected when the event of the transaction disappears or moves to a block with a different ID,
+/// and ends the wait even if the transaction is included again in the new chain. A failed poll is returned as the
+/// error, the client's retry policy applies to each request.
+pub async fn wait_for_confirmations<C: ApiClient + Sync>(
+    client: &C,
+    txid: TransactionID,
+    confirmations: u64,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<ConfirmationOutcome, ApiClientError> {
+    let started = Instant::now();
+    let mut state = ConfirmationState::default();
+    loop {
+        let (included_in, tip_height) = poll_confirmation(client, txid).await?;
+        for change in state.update(included_in, tip_height) {
+            match change {
+                ConfirmationEvent::Reverted { index } => return Ok(ConfirmationOutcome::Reorged { index }),
+                ConfirmationEvent::Confirmed {
+                    index,
+                    confirmations: n,
+                } if n >= confirmations => return Ok(ConfirmationOutcome::Confirmed { height: index.height }),
+                ConfirmationEvent::Confirmed { .. } => (),
+            }
+        }
+        match timeout.checked_sub(started.elapsed()) {
+            Some(remaining) if remaining > Duration::from_secs(0) => sleep(remaining.min(poll_interval)).await,
+            _ => return Ok(ConfirmationOutcome::TimedOut),
+        }
+    }
+}
+
+async fn poll_confirmation<C: ApiClient + Sync>(
+    client: &C,
+    txid: TransactionID,
+) -> Result<(Option<ChainIndex>, u64), ApiClientError> {
+    let included_in = match client.dispatcher(GetEventRequest { txid }).await {
+        Ok(event) => Some(event.0.index),
+        Err(e) if is_not_found(&e) => None,
+        Err(e) => return Err(e),
+    };
+    let tip = client.dispatcher(ConsensusTipRequest).await?;
+    Ok((included_in, tip.height))
+}
+
+/// Walk every event of `address`, newest first, requesting `page_size` events at a time as the stream is polled.
+///
+/// Pages are requested by offset, so events confirmed while walking shift the later pages and may be yielded
+/// twice; dedupe by `Event::id` where that matters. The stream ends after yielding the first error.
+pub fn paged_address_events<C>(
+    client: &C,
+    address: Address,
+    page_size: i64,
+) -> impl Stream<Item = Result<Event, ApiClientError>> + '_
+where
+    C: ApiClient + Sync,
+{
+    let page_size = page_size.max(1);
+    let pages = stream::unfold(Some(0), move |offset| {
+        let address = address.clone();
+        async move {
+            let offset = offset?;
+            let request = AddressEventsRequest {
+                address,
+                limit: Some(page_size),
+                offset: Some(offset),
+            };
+            match client.dispatcher(request).await {
+                Ok(page) => {
+                    let fetched = page.len() as i64;
+                    let next = if fetched < page_size {
+                        None
+                    } else {
+                        Some(offset + fetched)
+                    };
+                    let events: Vec<_> = page.into_iter().map(Ok).collect();
+                    Some((stream::iter(events), next))
+                },
+                Err(e) => Some((stream::iter(vec![Err(e)]), None)),
+            }
+        }
+    });
+    pages.flatten()
+}
+
+#[derive(Clone, Debug)]
+pub struct EventStreamConf {
+    /// Number of events requested per page
+    pub page_size: i64,
+    /// Number of pages requested ahead of the one being yielded. Bounds the memory of the stream to about
+    /// `max_in_flight + 1` pages, no matter how many events the address has.
+    pub max_in_flight: usize,
+    /// Offset of the first event yielded, eg. to resume a walk after its first `n` events with `offset + n`
+    pub offset: i64,
+}
+
+impl Default for EventStreamConf {
+    fn default() -> Self {
+        EventStreamConf {
+            page_size: 100,
+            max_in_flight: 2,
+            offset: 0,
+        }
+    }
+}
+
+/// Walk the events of `address` from `conf.offset` on, newest first, like `paged_address_events` but with up to
+/// `conf.max_in_flight` pages requested concurrently.
+///
+/// Pages are requested ahead of the end of the history as well, so up to `max_in_flight - 1` requests beyond the
+/// last page are sent and dropped. An interrupted walk resumes where it stopped by setting `offset` to the offset
+/// of the first event not yet processed; events confirmed since shift the offsets just as they do for
+/// `paged_address_events`. The stream ends after yielding the first error.
+pub fn event_stream<C>(
+    client: &C,
+    address: Address,
+    conf: EventStreamConf,
+) -> impl Stream<Item = Result<Event, ApiClientError>> + '_
+where
+    C: ApiClient + Sync,
+{
+    let page_size = conf.page_size.max(1);
+    let start = conf.offset.max(0);
+    let pages = stream::iter(0..)
+        .map(move |page: i64| {
+            client.dispatcher(AddressEventsRequest {
+                address: address.clone(),
+                limit: Some(page_size),
+                offset: Some(start + page * page_size),
+            })
+        })
+        .buffered(conf.max_in_flight.max(1));
+    // stop at the first short page or error, dropping the pages requested past it
+    pages
+        .scan(false, move |done, page| {
+            if *done {
+                return future::ready(None);
+            }
+            let events: Vec<_> = match page {
+                Ok(page) => {
+                    *done = (page.len() as i64) < page_size;
+                    page.into_iter().map(Ok).collect()
+                },
+                Err(e) => {
+                    *done = true;
+                    vec![Err(e)]
+                },
+            };
+            future::ready(Some(stream::iter(events)))
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BlockID, H256};
+    use std::str::FromStr;
+
+    const ADDRESS: &str = "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f";
+
+    fn index(height: u64, block: u8) -> ChainIndex {
+        ChainIndex {
+            height,
+            id: BlockID(H256::from(format!("{:064x}", block).as_str())),
+        }
+    }
+
+    fn event(id: u8, height: u64, block: u8) -> Event {
+        let j = json!({
+            "id": format!("h:{:064x}", id),
+            "index": index(height, block),
+            "timestamp": "2024-07-18T19:04:16Z",
+            "maturityHeight": height,
+            "type": "v2Transaction",
+            "data": {
+                "siacoinOutputs": [
+                    {
+                        "value": "1000",
+                        "address": ADDRESS
+                    }
+                ],
+                "minerFee": "0"
+            }
+        });
+        serde_json::from_value(j).unwrap()
+    }
+
+    fn state() -> AddressEventsState { AddressEventsState::new(Address::from_str(ADDRESS).unwrap(), 3) }
+
+    fn ids(changes: &[AddressEvent]) -> Vec<(bool, u8)> {
+        changes
+            .iter()
+            .map(|change| match change {
+                AddressEvent::New { event, .. } => (true, H256::from(event.id).0[31]),
+                AddressEvent::Reverted { id, .. } => (false, H256::from(*id).0[31]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_address_events_new() {
+        let mut state = state();
+        let changes = state.update(vec![event(2, 11, 1), event(1, 10, 1)]);
+        assert_eq!(ids(&changes), vec![(true, 1), (true, 2)]);
+        assert!(changes[0].is_incoming_payment());
+        match &changes[0] {
+            AddressEvent::New { received, sent, .. } => {
+                assert_eq!(*received, Currency::from(1000u64));
+                assert_eq!(*sent, Currency::default());
+            },
+            other => panic!("expected New, got {:?}", other),
+        }
+
+        let changes = state.update(vec![event(3, 12, 1), event(2, 11, 1), event(1, 10, 1)]);
+        assert_eq!(ids(&changes), vec![(true, 3)]);
+    }
+
+    #[test]
+    fn test_address_events_reorg() {
+        let mut state = state();
+        state.update(vec![event(3, 12, 1), event(2, 11, 1), event(1, 10, 1)]);
+
+        // block 12 was reverted and event 2 was mined again in another block at height 11
+        let changes = state.update(vec![event(2, 11, 2), event(1, 10, 1)]);
+        assert_eq!(ids(&changes), vec![(false, 3), (false, 2), (true, 2)]);
+        assert!(state.update(vec![event(2, 11, 2), event(1, 10, 1)]).is_empty());
+    }
+
+    #[test]
+    fn test_address_events_window() {
+        let mut state = state();
+        state.update(vec![event(3, 12, 1), event(2, 11, 1), event(1, 10, 1)]);
+
+        // event 1 dropped out of the full window and is not reported as reverted
+        let changes = state.update(vec![event(5, 14, 1), event(4, 13, 1), event(3, 12, 1)]);
+        assert_eq!(ids(&changes), vec![(true, 4), (true, 5)]);
+    }
+
+    #[test]
+    fn test_confirmation_state() {
+        let mut state = ConfirmationState::default();
+        assert!(state.update(None, 10).is_empty());
+
+        assert_eq!(state.update(Some(index(11, 1)), 11), vec![
+            ConfirmationEvent::Confirmed {
+                index: index(11, 1),
+                confirmations: 1
+            }
+        ]);
+        assert!(state.update(Some(index(11, 1)), 11).is_empty());
+        assert_eq!(state.update(Some(index(11, 1)), 13), vec![
+            ConfirmationEvent::Confirmed {
+                index: index(11, 1),
+                confirmations: 3
+            }
+        ]);
+
+        assert_eq!(state.update(Some(index(12, 2)), 12), vec![
+            ConfirmationEvent::Reverted { index: index(11, 1) },
+            ConfirmationEvent::Confirmed {
+                index: index(12, 2),
+                confirmations: 1
+            },
+        ]);
+        assert_eq!(state.update(None, 12), vec![ConfirmationEvent::Reverted {
+            index: index(12, 2)
+        }]);
+        assert!(state.update(None, 12).is_empty());
+    }
+
+    #[test]
+    fn test_unconfirmed_activity() {
+        let address = Address::from_str(ADDRESS).unwrap();
+        let spend: Event = serde_json::from_value(json!({
+            "id": format!("h:{:064x}", 2),
+            "index": index(10, 1),
+            "timestamp": "2024-07-18T19:04:16Z",
+            "maturityHeight": 10,
+            "type": "v2Transaction",
+            "data": {
+                "siacoinInputs": [
+                    {
+                        "parent": {
+                            "id": format!("h:{:064x}", 3),
+                            "leafIndex": 0,
+                            "siacoinOutput": {
+                                "value": "5000",
+                                "address": ADDRESS
+                            },
+                            "maturityHeight": 0
+                        },
+                        "satisfiedPolicy": {
+                            "policy": {
+                                "type": "pk",
+                                "policy": "ed25519:0102030000000000000000000000000000000000000000000000000000000000"
+                            },
+                            "signatures": []
+                        }
+                    }
+                ],
+                "siacoinOutputs": [
+                    {
+                        "value": "1500",
+                        "address": ADDRESS
+                    }
+                ],
+                "minerFee": "0"
+            }
+        }))
+        .unwrap();
+
+        let activity = UnconfirmedActivity::new(vec![event(1, 10, 1), spend], &address);
+        assert_eq!(activity.events.len(), 2);
+        assert_eq!(activity.incoming, Currency(1000));
+        assert_eq!(activity.outgoing, Currency(3500));
+
+        let activity = UnconfirmedActivity::new(Vec::new(), &address);
+        assert_eq!(activity.incoming, Currency::default());
+        assert_eq!(activity.outgoing, Currency::default());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_wait_for_confirmations() {
+        use crate::http::mock::{fixtures, MockWalletd};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        const EVENT_PATH: &str = "/api/events/5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd";
+        let txid = TransactionID(H256::from(
+            "5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd",
+        ));
+        let poll_interval = Duration::from_millis(10);
+
+        // the fixture event is in the tip block
+        let mock = MockWalletd::start().await;
+        let client = mock.client().await.unwrap();
+        let outcome = wait_for_confirmations(&client, txid, 1, Duration::from_secs(5), poll_interval).await;
+        assert_eq!(outcome.unwrap(), ConfirmationOutcome::Confirmed { height: 203 });
+        let outcome = wait_for_confirmations(&client, txid, 2, Duration::from_millis(50), poll_interval).await;
+        assert_eq!(outcome.unwrap(), ConfirmationOutcome::TimedOut);
+
+        // the event disappears after the first poll
+        let mock = MockWalletd::start().await;
+        Mock::given(method("GET"))
+            .and(path(EVENT_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(fixtures::EVENT, "application/json"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(mock.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path(EVENT_PATH))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .with_priority(2)
+            .mount(mock.server())
+            .await;
+        let client = mock.client().await.unwrap();
+        let outcome = wait_for_confirmations(&client, txid, 2, Duration::from_secs(5), poll_interval).await;
+        match outcome.unwrap() {
+            ConfirmationOutcome::Reorged { index } => assert_eq!(index.height, 203),
+            other => panic!("expected Reorged, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! A mock walletd answering every supported endpoint with canned responses, so client tests do not need a node.
+//!
+//! Available to the crate's own tests and, behind the `test-utils` feature, to downstream crates.
+use crate::http::client::native::{Conf, IpFamily, NativeClient};
+use crate::http::client::{ApiClient, ApiClientError};
+use url::Url;
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Responses recorded from walletd, one per endpoint
+pub mod fixtures {
+    pub const ADDRESS_BALANCE: &str = include_str!("../../fixtures/walletd/address_balance.json");
+    pub const ADDRESS_EVENTS: &str = include_str!("../../fixtures/walletd/address_events.json");
+    pub const ADDRESS_OUTPUTS_SIACOIN: &str = include_str!("../../fixtures/walletd/address_outputs_siacoin.json");
+    pub const ADDRESS_OUTPUTS_SIAFUND: &str = include_str!("../../fixtures/walletd/address_outputs_siafund.json");
+    pub const CONSENSUS_BLOCK: &str = include_str!("../../fixtures/walletd/consensus_block.json");
+    pub const CONSENSUS_INDEX: &str = include_str!("../../fixtures/walletd/consensus_index.json");
+    pub const CONSENSUS_NETWORK: &str = include_str!("../../fixtures/walletd/consensus_network.json");
+    pub const CONSENSUS_TIP: &str = include_str!("../../fixtures/walletd/consensus_tip.json");
+    pub const CONSENSUS_TIPSTATE: &str = include_str!("../../fixtures/walletd/consensus_tipstate.json");
+    pub const EVENT: &str = include_str!("../../fixtures/walletd/event.json");
+    pub const STATE: &str = include_str!("../../fixtures/walletd/state.json");
+    pub const TXPOOL_FEE: &str = include_str!("../../fixtures/walletd/txpool_fee.json");
+    pub const TXPOOL_TRANSACTIONS: &str = include_str!("../../fixtures/walletd/txpool_transactions.json");
+    pub const WALLET: &str = include_str!("../../fixtures/walletd/wallet.json");
+    pub const WALLET_ADDRESSES: &str = include_str!("../../fixtures/walletd/wallet_addresses.json");
+    pub const WALLETS: &str = include_str!("../../fixtures/walletd/wallets.json");
+}
+
+/// Method, path pattern, status and body of each route mounted by `MockWalletd::start`
+const ROUTES: &[(&str, &str, u16, &str)] = &[
+    ("GET", r"^/api/addresses/[^/]+/balance$", 200, fixtures::ADDRESS_BALANCE),
+    ("GET", r"^/api/addresses/[^/]+/events$", 200, fixtures::ADDRESS_EVENTS),
+    ("GET", r"^/api/addresses/[^/]+/events/unconfirmed$", 200, "[]"),
+    (
+        "GET",
+        r"^/api/addresses/[^/]+/outputs/siacoin$",
+        200,
+        fixtures::ADDRESS_OUTPUTS_SIACOIN,
+    ),
+    (
+        "GET",
+        r"^/api/addresses/[^/]+/outputs/siafund$",
+        200,
+        fixtures::ADDRESS_OUTPUTS_SIAFUND,
+    ),
+    ("GET", r"^/api/consensus/blocks/[^/]+$", 200, fixtures::CONSENSUS_BLOCK),
+    ("GET", r"^/api/consensus/index/\d+$", 200, fixtures::CONSENSUS_INDEX),
+    ("GET", r"^/api/consensus/network$", 200, fixtures::CONSENSUS_NETWORK),
+    ("GET", r"^/api/consensus/tip$", 200, fixtures::CONSENSUS_TIP),
+    ("GET", r"^/api/consensus/tipstate$", 200, fixtures::CONSENSUS_TIPSTATE),
+    ("GET", r"^/api/events/[^/]+$", 200, fixtures::EVENT),
+    ("GET", r"^/api/state$", 200, fixtures::STATE),
+    ("POST", r"^/api/txpool/broadcast$", 204, ""),
+    ("GET", r"^/api/txpool/fee$", 200, fixtures::TXPOOL_FEE),
+    ("GET", r"^/api/txpool/transactions$", 200, fixtures::TXPOOL_TRANSACTIONS),
+    ("GET", r"^/api/wallets$", 200, fixtures::WALLETS),
+    ("POST", r"^/api/wallets$", 200, fixtures::WALLET),
+    ("DELETE", r"^/api/wallets/\d+$", 204, ""),
+    ("GET", r"^/api/wallets/\d+/addresses$", 200, fixtures::WALLET_ADDRESSES),
+    ("PUT", r"^/api/wallets/\d+/addresses$", 204, ""),
+    ("GET", r"^/api/wallets/\d+/balance$", 200, fixtures::ADDRESS_BALANCE),
+    ("GET", r"^/api/wallets/\d+/events$", 200, fixtures::ADDRESS_EVENTS),
+    ("GET", r"^/api/wallets/\d+/events/unconfirmed$", 200, "[]"),
+];
+
+/// Priority of the routes mounted with `MockWalletd::respond`, taking precedence over the fixtures
+const OVERRIDE_PRIORITY: u8 = 1;
+
+/// A walletd stand-in listening on a random local port
+pub struct MockWalletd {
+    server: MockServer,
+}
+
+impl MockWalletd {
+    /// Start a server answering each endpoint with its fixture
+    pub async fn start() -> Self {
+        let mock = Self::start_empty().await;
+        for (http_method, pattern, status, body) in ROUTES {
+            let mut response = ResponseTemplate::new(*status);
+            if !body.is_empty() {
+                response = response.set_body_raw(*body, "application/json");
+            }
+            Mock::given(method(*http_method))
+                .and(path_regex(*pattern))
+                .respond_with(response)
+                .mount(&mock.server)
+                .await;
+        }
+        mock
+    }
+
+    /// Start a server without any routes, every request is answered with `404 Not Found`
+    pub async fn start_empty() -> Self {
+        MockWalletd {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn url(&self) -> Url { Url::parse(&self.server.uri()).expect("MockServer::uri is a valid URL") }
+
+    /// The underlying server, eg. to mount custom `Mock`s or inspect received requests
+    pub fn server(&self) -> &MockServer { &self.server }
+
+    /// Answer `http_method` requests to exactly `route`, eg. `/api/txpool/broadcast`, with `response` instead
+    /// of the fixture
+    pub async fn respond(&self, http_method: &str, route: &str, response: ResponseTemplate) {
+        Mock::given(method(http_method))
+            .and(path(route))
+            .respond_with(response)
+            .with_priority(OVERRIDE_PRIORITY)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Like `respond`, for the next `times` matching requests only, after which the fixture answers them again
+    pub async fn respond_times(&self, http_method: &str, route: &str, response: ResponseTemplate, times: u64) {
+        Mock::given(method(http_method))
+            .and(path(route))
+            .respond_with(response)
+            .up_to_n_times(times)
+            .with_priority(OVERRIDE_PRIORITY)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// The number of requests received for exactly `route`
+    pub async fn received(&self, route: &str) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|request| request.url.path() == route)
+            .count()
+    }
+
+    /// A client configuration pointing at this server with every optional behavior disabled
+    pub fn conf(&self) -> Conf {
+        Conf {
+            server_url: self.url(),
+            base_path: None,
+            fallback_urls: Vec::new(),
+            password: None,
+            timeout: Some(10),
+            deadline: None,
+            circuit_breaker: None,
+            throttle: None,
+            ip_family: IpFamily::Any,
+            etag_cache: false,
+            response_cache: None,
+            slow_request_threshold: None,
+            retry: None,
+            pool: Default::default(),
+            user_agent: None,
+            headers: Default::default(),
+            proxy: None,
+            tls: Default::default(),
+            skip_health_check: false,
+        }
+    }
+
+    pub async fn client(&self) -> Result<NativeClient, ApiClientError> { NativeClient::new(self.conf()).await }
+}
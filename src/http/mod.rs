@@ -1,2 +1,9 @@
+pub mod capabilities;
 pub mod client;
 pub mod endpoints;
+#[cfg(feature = "watcher")] pub mod events;
+#[cfg(all(any(test, feature = "test-utils"), not(target_arch = "wasm32")))]
+pub mod mock;
+pub mod scan;
+#[cfg(feature = "watcher")] pub mod tracker;
+#[cfg(feature = "webhook")] pub mod webhook;
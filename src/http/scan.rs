@@ -0,0 +1,191 @@
+//! Discovery of the used addresses of a wallet restored from its seed, see `scan_addresses`
+use crate::http::client::{ApiClient, ApiClientError, RequestOpts};
+use crate::http::endpoints::{AddressBalanceRequest, AddressBalanceResponse, AddressEventsRequest};
+use crate::types::Address;
+use crate::Seed;
+
+/// Addresses checked for events concurrently while scanning
+const SCAN_CONCURRENCY: usize = 8;
+
+/// Derives the address at each index of a wallet
+pub trait AddressSource {
+    fn address(&self, index: u64) -> Address;
+}
+
+/// The standard addresses of `Seed::keypair`
+impl AddressSource for Seed {
+    fn address(&self, index: u64) -> Address { self.keypair(index).address() }
+}
+
+impl<F: Fn(u64) -> Address> AddressSource for F {
+    fn address(&self, index: u64) -> Address { self(index) }
+}
+
+#[derive(Debug)]
+pub struct ScannedAddress {
+    pub index: u64,
+    pub address: Address,
+    pub balance: AddressBalanceResponse,
+}
+
+#[derive(Debug, Default)]
+pub struct AddressScan {
+    /// Every address with at least one event, by index
+    pub used: Vec<ScannedAddress>,
+    /// Index of the last used address, `None` if no address was used
+    pub last_used_index: Option<u64>,
+}
+
+impl AddressScan {
+    /// The index to derive the next receive address from
+    pub fn next_index(&self) -> u64 { self.last_used_index.map_or(0, |index| index + 1) }
+}
+
+/// Check the addresses of `key_source` from index 0 upwards for events and stop once `gap_limit` consecutive
+/// addresses were never used. The balance of each used address is fetched as well.
+///
+/// An address counts as used if it has any event, so addresses that were emptied are still found. Fails with
+/// the first error, as a scan with missing addresses would restore an incomplete wallet.
+pub async fn scan_addresses<C, K>(client: &C, key_source: &K, gap_limit: u64) -> Result<AddressScan, ApiClientError>
+where
+    C: ApiClient + Sync,
+    K: AddressSource + Sync,
+{
+    scan_addresses_with_opts(client, key_source, gap_limit, RequestOpts::default()).await
+}
+
+/// Like `scan_addresses`, with `opts` applied to every request, eg. a `RequestOpts::cancel` token to abort the
+/// scan with `ApiClientError::Cancelled`
+pub async fn scan_addresses_with_opts<C, K>(
+    client: &C,
+    key_source: &K,
+    gap_limit: u64,
+    opts: RequestOpts,
+) -> Result<AddressScan, ApiClientError>
+where
+    C: ApiClient + Sync,
+    K: AddressSource + Sync,
+{
+    let mut used = Vec::new();
+    let mut unused_run = 0;
+    let mut next_index = 0;
+    while unused_run < gap_limit {
+        // only check as many addresses as could still complete the gap, so the scan never goes past it
+        let indices = next_index..next_index + (gap_limit - unused_run);
+        next_index = indices.end;
+        let addresses: Vec<_> = indices.map(|index| (index, key_source.address(index))).collect();
+        let requests = addresses
+            .iter()
+            .map(|(_, address)| AddressEventsRequest {
+                address: address.clone(),
+                limit: Some(1),
+                offset: None,
+            })
+            .collect();
+        let results = client
+            .dispatch_batch_with_opts(requests, SCAN_CONCURRENCY, opts.clone())
+            .await;
+        for ((index, address), events) in addresses.into_iter().zip(results) {
+            if events?.is_empty() {
+                unused_run += 1;
+            } else {
+                unused_run = 0;
+                used.push((index, address));
+            }
+        }
+    }
+
+    let requests = used
+        .iter()
+        .map(|(_, address)| AddressBalanceRequest {
+            address: address.clone(),
+        })
+        .collect();
+    let balances = client.dispatch_batch_with_opts(requests, SCAN_CONCURRENCY, opts).await;
+    let used = used
+        .into_iter()
+        .zip(balances)
+        .map(|((index, address), balance)| {
+            Ok(ScannedAddress {
+                index,
+                address,
+                balance: balance?,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiClientError>>()?;
+    Ok(AddressScan {
+        last_used_index: used.last().map(|scanned| scanned.index),
+        used,
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::http::client::cancel::CancellationToken;
+    use crate::http::mock::{fixtures, MockWalletd};
+    use wiremock::matchers::{method, path_regex};
+    use wiremock::{Mock, ResponseTemplate};
+
+    fn address(index: u64) -> Address { Seed::from_bytes([1; 32]).address(index) }
+
+    async fn mock_with_used(indices: &[u64]) -> MockWalletd {
+        let mock = MockWalletd::start().await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/addresses/[^/]+/events$"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("[]", "application/json"))
+            .with_priority(2)
+            .mount(mock.server())
+            .await;
+        for index in indices {
+            // path params are percent-encoded, so the `:` of the prefix as well
+            let route = format!("/api/addresses/addr%3A{}/events", address(*index).str_without_prefix());
+            let events = ResponseTemplate::new(200).set_body_raw(fixtures::ADDRESS_EVENTS, "application/json");
+            mock.respond("GET", &route, events).await;
+        }
+        mock
+    }
+
+    #[tokio::test]
+    async fn test_scan_addresses_gap() {
+        let mock = mock_with_used(&[0, 2, 6]).await;
+        let client = mock.client().await.unwrap();
+        let seed = Seed::from_bytes([1; 32]);
+
+        // the gap between 2 and 6 exceeds the limit
+        let scan = scan_addresses(&client, &seed, 3).await.unwrap();
+        let indices: Vec<_> = scan.used.iter().map(|scanned| scanned.index).collect();
+        assert_eq!(indices, vec![0, 2]);
+        assert_eq!(scan.last_used_index, Some(2));
+        assert_eq!(scan.next_index(), 3);
+        assert_eq!(scan.used[1].address, address(2));
+
+        let scan = scan_addresses(&client, &seed, 5).await.unwrap();
+        assert_eq!(scan.last_used_index, Some(6));
+        assert_eq!(scan.used.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_scan_addresses_cancelled() {
+        let mock = mock_with_used(&[0]).await;
+        let client = mock.client().await.unwrap();
+        let sent = mock.server().received_requests().await.unwrap().len();
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let opts = RequestOpts::with_cancellation(token);
+        let err = scan_addresses_with_opts(&client, &address, 20, opts).await.unwrap_err();
+        assert!(matches!(err, ApiClientError::Cancelled));
+        assert_eq!(mock.server().received_requests().await.unwrap().len(), sent);
+    }
+
+    #[tokio::test]
+    async fn test_scan_addresses_unused_wallet() {
+        let mock = mock_with_used(&[]).await;
+        let client = mock.client().await.unwrap();
+
+        let scan = scan_addresses(&client, &address, 20).await.unwrap();
+        assert!(scan.used.is_empty());
+        assert_eq!(scan.next_index(), 0);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::http::client::{ApiClient, ApiClientError};
+use crate::http::endpoints::{GetEventRequest, TxpoolBroadcastRequest, TxpoolTransactionsRequest};
+use crate::transaction::V2Transaction;
+use crate::types::TransactionID;
+use core::time::Duration;
+use instant::Instant;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug)]
+pub struct TrackerConf {
+    /// Minimum time between two broadcasts of a transaction missing from the txpool
+    pub rebroadcast_interval: Duration,
+    /// Time after the first broadcast at which an unconfirmed transaction should be replaced with a higher fee
+    pub fee_bump_after: Duration,
+    /// Time after the first broadcast at which an unconfirmed transaction is given up on
+    pub abandon_after: Duration,
+}
+
+impl Default for TrackerConf {
+    fn default() -> Self {
+        TrackerConf {
+            rebroadcast_interval: Duration::from_secs(60),
+            fee_bump_after: Duration::from_secs(30 * 60),
+            abandon_after: Duration::from_secs(6 * 60 * 60),
+        }
+    }
+}
+
+/// The status of a tracked transaction as observed by `TxTracker::poll`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxStatus {
+    Confirmed {
+        height: u64,
+    },
+    InTxpool,
+    /// Neither confirmed nor in the txpool, eg. evicted or never relayed
+    Missing,
+}
+
+#[derive(Debug)]
+pub enum TrackerEvent {
+    /// The transaction was included in a block and is no longer tracked, nor is any transaction conflicting with it
+    Confirmed {
+        txid: TransactionID,
+        height: u64,
+    },
+    /// The transaction had dropped out of the txpool and was broadcast again
+    Rebroadcast {
+        txid: TransactionID,
+    },
+    RebroadcastFailed {
+        txid: TransactionID,
+        error: ApiClientError,
+    },
+    /// The transaction has been unconfirmed for `TrackerConf::fee_bump_after`. The caller should build a
+    /// replacement with a higher fee and hand it over with `TxTracker::replace`. Emitted once per transaction.
+    FeeBumpRequired {
+        txid: TransactionID,
+    },
+    /// The transaction stayed unconfirmed for `TrackerConf::abandon_after` and is no longer tracked, nor are the
+    /// transactions it replaced
+    Abandoned {
+        txid: TransactionID,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackedTransaction {
+    pub tx: V2Transaction,
+    pub first_broadcast: Instant,
+    pub last_broadcast: Instant,
+    pub rebroadcasts: u32,
+    pub fee_bump_requested: bool,
+    /// Set once the transaction was replaced. It is then only watched for a confirmation, since it may still be
+    /// mined instead of its replacement.
+    pub replaced: bool,
+    /// Tracked transactions spending the same inputs, ie. the ones this one replaced or was replaced by
+    pub conflicts: Vec<TransactionID>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    Confirm(u64),
+    Rebroadcast,
+    RequestFeeBump,
+    Abandon,
+}
+
+impl TrackedTransaction {
+    fn new(tx: V2Transaction, now: Instant) -> Self {
+        TrackedTransaction {
+            tx,
+            first_broadcast: now,
+            last_broadcast: now,
+            rebroadcasts: 0,
+            fee_bump_requested: false,
+            replaced: false,
+            conflicts: Vec::new(),
+        }
+    }
+
+    fn actions(&self, status: TxStatus, conf: &TrackerConf, now: Instant) -> Vec<Action> {
+        let age = now.saturating_duration_since(self.first_broadcast);
+        match status {
+            TxStatus::Confirmed { height } => return vec![Action::Confirm(height)],
+            // its replacement is rebroadcast, bumped and abandoned instead
+            _ if self.replaced => return Vec::new(),
+            _ if age >= conf.abandon_after => return vec![Action::Abandon],
+            _ => (),
+        }
+
+        let mut actions = Vec::new();
+        let since_broadcast = now.saturating_duration_since(self.last_broadcast);
+        if status == TxStatus::Missing && since_broadcast >= conf.rebroadcast_interval {
+            actions.push(Action::Rebroadcast);
+        }
+        if !self.fee_bump_requested && age >= conf.fee_bump_after {
+            actions.push(Action::RequestFeeBump);
+        }
+        actions
+    }
+}
+
+pub(crate) fn is_not_found(error: &ApiClientError) -> bool {
+    matches!(error.http_status(), Some((status, _)) if status == http::StatusCode::NOT_FOUND)
+}
+
+/// Tracks locally broadcast transactions until they are confirmed or abandoned.
+///
+/// The tracker does not run on its own; `poll` should be called periodically, eg. once per
+/// `TrackerConf::rebroadcast_interval`. Each call checks every tracked transaction, rebroadcasts those that
+/// dropped out of the txpool and reports what happened as `TrackerEvent`s.
+#[derive(Clone, Debug, Default)]
+pub struct TxTracker {
+    conf: TrackerConf,
+    txs: HashMap<TransactionID, TrackedTransaction>,
+}
+
+impl TxTracker {
+    pub fn new(conf: TrackerConf) -> Self {
+        TxTracker {
+            conf,
+            txs: HashMap::new(),
+        }
+    }
+
+    /// Start tracking `tx`, which is expected to have just been broadcast
+    pub fn track(&mut self, tx: V2Transaction) -> TransactionID {
+        let txid = tx.txid();
+        self.txs.insert(txid, TrackedTransaction::new(tx, Instant::now()));
+        txid
+    }
+
+    /// Replace the tracked transaction `txid` with `replacement`, eg. after a fee bump.
+    /// The replacement is expected to have just been broadcast and starts with fresh timers. `txid` stays tracked
+    /// as a conflicting predecessor until either of them is confirmed, as the original may still be mined first.
+    pub fn replace(&mut self, txid: &TransactionID, replacement: V2Transaction) -> TransactionID {
+        let new_txid = replacement.txid();
+        let mut conflicts: Vec<TransactionID> = match self.txs.get(txid) {
+            Some(original) => original.conflicts.iter().copied().chain(Some(*txid)).collect(),
+            None => Vec::new(),
+        };
+        conflicts.retain(|conflict| *conflict != new_txid);
+        for conflict in &conflicts {
+            if let Some(tracked) = self.txs.get_mut(conflict) {
+                tracked.replaced = true;
+                tracked.conflicts.push(new_txid);
+            }
+        }
+
+        let mut tracked = TrackedTransaction::new(replacement, Instant::now());
+        tracked.conflicts = conflicts;
+        self.txs.insert(new_txid, tracked);
+        new_txid
+    }
+
+    pub fn untrack(&mut self, txid: &TransactionID) -> Option<TrackedTransaction> { self.txs.remove(txid) }
+
+    pub fn get(&self, txid: &TransactionID) -> Option<&TrackedTransaction> { self.txs.get(txid) }
+
+    pub fn is_empty(&self) -> bool { self.txs.is_empty() }
+
+    /// Stop tracking `txid` along with the transactions conflicting with it
+    fn remove_with_conflicts(&mut self, txid: &TransactionID) {
+        if let Some(tracked) = self.txs.remove(txid) {
+            for conflict in &tracked.conflicts {
+                self.txs.remove(conflict);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize { self.txs.len() }
+
+    async fn status<C: ApiClient + Sync>(
+        client: &C,
+        txid: &TransactionID,
+        txpool: &HashSet<TransactionID>,
+    ) -> Result<TxStatus, ApiClientError> {
+        if txpool.contains(txid) {
+            return Ok(TxStatus::InTxpool);
+        }
+        match client.dispatcher(GetEventRequest { txid: *txid }).await {
+            Ok(event) => Ok(TxStatus::Confirmed {
+                height: event.0.index.height,
+            }),
+            Err(e) if is_not_found(&e) => Ok(TxStatus::Missing),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Check the status of every tracked transaction and act on it.
+    ///
+    /// Errors querying the node abort the poll without changing any state, so the next poll retries.
+    /// Failed rebroadcasts are reported as `TrackerEvent::RebroadcastFailed` instead.
+    pub async fn poll<C: ApiClient + Sync>(&mut self, client: &C) -> Result<Vec<TrackerEvent>, ApiClientError> {
+        if self.txs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let txpool = client.dispatcher(TxpoolTransactionsRequest).await?;
+        let txpool: HashSet<TransactionID> = txpool.v2transactions.iter().map(|tx| tx.txid()).collect();
+
+        let mut statuses = Vec::with_capacity(self.txs.len());
+        for txid in self.txs.keys() {
+            statuses.push((*txid, Self::status(client, txid, &txpool).await?));
+        }
+
+        let now = Instant::now();
+        let mut events = Vec::new();
+        for (txid, status) in statuses {
+            let actions = match self.txs.get(&txid) {
+                Some(tracked) => tracked.actions(status, &self.conf, now),
+                None => continue,
+            };
+            for action in actions {
+                match action {
+                    Action::Confirm(height) => {
+                        self.remove_with_conflicts(&txid);
+                        events.push(TrackerEvent::Confirmed { txid, height });
+                    },
+                    Action::Abandon => {
+                        self.remove_with_conflicts(&txid);
+                        events.push(TrackerEvent::Abandoned { txid });
+                    },
+                    Action::Rebroadcast => {
+                        if let Some(tracked) = self.txs.get_mut(&txid) {
+                            events.push(Self::rebroadcast(client, txid, tracked, now).await);
+                        }
+                    },
+                    Action::RequestFeeBump => {
+                        if let Some(tracked) = self.txs.get_mut(&txid) {
+                            tracked.fee_bump_requested = true;
+                        }
+                        events.push(TrackerEvent::FeeBumpRequired { txid });
+                    },
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    async fn rebroadcast<C: ApiClient + Sync>(
+        client: &C,
+        txid: TransactionID,
+        tracked: &mut TrackedTransaction,
+        now: Instant,
+    ) -> TrackerEvent {
+        // count failed attempts too so a rejected transaction is not resent on every poll
+        tracked.last_broadcast = now;
+        tracked.rebroadcasts += 1;
+        let request = TxpoolBroadcastRequest {
+            transactions: vec![],
+            v2transactions: vec![tracked.tx.clone()],
+        };
+        match client.dispatcher(request).await {
+            Ok(_) => TrackerEvent::Rebroadcast { txid },
+            Err(error) => TrackerEvent::RebroadcastFailed { txid, error },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conf() -> TrackerConf {
+        TrackerConf {
+            rebroadcast_interval: Duration::from_secs(60),
+            fee_bump_after: Duration::from_secs(600),
+            abandon_after: Duration::from_secs(3600),
+        }
+    }
+
+    fn tracked(now: Instant) -> TrackedTransaction { TrackedTransaction::new(V2Transaction::default(), now) }
+
+    #[test]
+    fn test_tracker_actions_confirmed() {
+        let start = Instant::now();
+        let tracked = tracked(start);
+        let status = TxStatus::Confirmed { height: 10 };
+
+        assert_eq!(tracked.actions(status, &conf(), start), vec![Action::Confirm(10)]);
+        // confirmation takes precedence over abandoning
+        let late = start + Duration::from_secs(7200);
+        assert_eq!(tracked.actions(status, &conf(), late), vec![Action::Confirm(10)]);
+    }
+
+    #[test]
+    fn test_tracker_actions_rebroadcast() {
+        let start = Instant::now();
+        let tracked = tracked(start);
+
+        assert!(tracked.actions(TxStatus::Missing, &conf(), start).is_empty());
+        let later = start + Duration::from_secs(61);
+        assert_eq!(tracked.actions(TxStatus::Missing, &conf(), later), vec![
+            Action::Rebroadcast
+        ]);
+        assert!(tracked.actions(TxStatus::InTxpool, &conf(), later).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_actions_fee_bump_once() {
+        let start = Instant::now();
+        let mut tracked = tracked(start);
+        let later = start + Duration::from_secs(601);
+
+        assert_eq!(tracked.actions(TxStatus::InTxpool, &conf(), later), vec![
+            Action::RequestFeeBump
+        ]);
+        assert_eq!(tracked.actions(TxStatus::Missing, &conf(), later), vec![
+            Action::Rebroadcast,
+            Action::RequestFeeBump
+        ]);
+
+        tracked.fee_bump_requested = true;
+        assert!(tracked.actions(TxStatus::InTxpool, &conf(), later).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_actions_abandon() {
+        let start = Instant::now();
+        let tracked = tracked(start);
+        let late = start + Duration::from_secs(3600);
+
+        assert_eq!(tracked.actions(TxStatus::InTxpool, &conf(), late), vec![
+            Action::Abandon
+        ]);
+        assert_eq!(tracked.actions(TxStatus::Missing, &conf(), late), vec![Action::Abandon]);
+    }
+
+    #[test]
+    fn test_tracker_replace() {
+        let mut tracker = TxTracker::new(conf());
+        let txid = tracker.track(V2Transaction::default());
+        assert_eq!(tracker.len(), 1);
+
+        let replacement = V2Transaction {
+            miner_fee: 1.into(),
+            ..Default::default()
+        };
+        let new_txid = tracker.replace(&txid, replacement);
+        assert_ne!(txid, new_txid);
+        assert_eq!(tracker.len(), 2);
+        let original = tracker.get(&txid).unwrap();
+        assert!(original.replaced);
+        assert_eq!(original.conflicts, vec![new_txid]);
+        let tracked = tracker.get(&new_txid).unwrap();
+        assert!(!tracked.replaced);
+        assert!(!tracked.fee_bump_requested);
+        assert_eq!(tracked.conflicts, vec![txid]);
+
+        let second = V2Transaction {
+            miner_fee: 2.into(),
+            ..Default::default()
+        };
+        let second_txid = tracker.replace(&new_txid, second);
+        assert_eq!(tracker.get(&second_txid).unwrap().conflicts, vec![txid, new_txid]);
+        assert_eq!(tracker.get(&txid).unwrap().conflicts, vec![new_txid, second_txid]);
+        assert!(tracker.get(&new_txid).unwrap().replaced);
+
+        // whichever one is confirmed, the others are dropped with it
+        tracker.remove_with_conflicts(&txid);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_tracker_actions_replaced() {
+        let start = Instant::now();
+        let mut tracked = tracked(start);
+        tracked.replaced = true;
+        let late = start + Duration::from_secs(7200);
+
+        assert!(tracked.actions(TxStatus::Missing, &conf(), late).is_empty());
+        assert!(tracked.actions(TxStatus::InTxpool, &conf(), late).is_empty());
+        assert_eq!(
+            tracked.actions(TxStatus::Confirmed { height: 10 }, &conf(), late),
+            vec![Action::Confirm(10)]
+        );
+    }
+}
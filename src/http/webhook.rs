@@ -0,0 +1,544 @@
+use crate::http::client::sleep;
+use crate::http::events::{AddressEvent, ConfirmationEvent};
+use crate::http::tracker::TrackerEvent;
+use crate::types::{Address, ChainIndex, Currency, EventID, TransactionID};
+use async_trait::async_trait;
+use core::time::Duration;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use url::Url;
+
+/// Header carrying the unix timestamp the payload was signed at
+pub const HEADER_TIMESTAMP: &str = "X-Sia-Timestamp";
+/// Header carrying `sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`
+pub const HEADER_SIGNATURE: &str = "X-Sia-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("WebhookError error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("WebhookError error: transport error: {0}")]
+    Transport(String),
+    #[error("WebhookError error: unexpected status {0}")]
+    UnexpectedHttpStatus(http::StatusCode),
+    #[error("WebhookError error: delivery to {url} failed after {attempts} attempts: {last}")]
+    DeliveryFailed {
+        url: Url,
+        attempts: u32,
+        last: Box<WebhookError>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEventKind {
+    NewDeposit,
+    ConfirmationReached,
+    Reorg,
+    LowBalance,
+}
+
+/// The `data` of a webhook payload
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    /// An incoming payment to `address`, `id` is the ID of its event, which is the transaction ID for transactions
+    NewDeposit {
+        address: Address,
+        id: EventID,
+        amount: Currency,
+        height: u64,
+    },
+    ConfirmationReached {
+        txid: TransactionID,
+        height: u64,
+        confirmations: u64,
+    },
+    /// The block `reverted` including the event `id`, or the transaction of the same ID, was reverted by a reorg
+    Reorg { id: EventID, reverted: ChainIndex },
+    LowBalance {
+        address: Address,
+        balance: Currency,
+        threshold: Currency,
+    },
+}
+
+impl WebhookEvent {
+    pub fn kind(&self) -> WebhookEventKind {
+        match self {
+            WebhookEvent::NewDeposit { .. } => WebhookEventKind::NewDeposit,
+            WebhookEvent::ConfirmationReached { .. } => WebhookEventKind::ConfirmationReached,
+            WebhookEvent::Reorg { .. } => WebhookEventKind::Reorg,
+            WebhookEvent::LowBalance { .. } => WebhookEventKind::LowBalance,
+        }
+    }
+
+    /// A `LowBalance` event if `balance` is below `threshold`
+    pub fn low_balance(address: Address, balance: Currency, threshold: Currency) -> Option<Self> {
+        if balance.0 >= threshold.0 {
+            return None;
+        }
+        Some(WebhookEvent::LowBalance {
+            address,
+            balance,
+            threshold,
+        })
+    }
+
+    /// Map the tracker events worth notifying about, ie. confirmations of tracked transactions, counting
+    /// confirmations up to `tip_height`
+    pub fn from_tracker_event(event: &TrackerEvent, tip_height: u64) -> Option<Self> {
+        match event {
+            TrackerEvent::Confirmed { txid, height } => Some(WebhookEvent::ConfirmationReached {
+                txid: *txid,
+                height: *height,
+                confirmations: tip_height.saturating_sub(*height) + 1,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Map a change yielded by `subscribe_address_events` for `address`: incoming payments are deposits and
+    /// reverted events are reorgs, other new events are not notified about
+    pub fn from_address_event(address: &Address, event: &AddressEvent) -> Option<Self> {
+        match event {
+            AddressEvent::New { event, received, sent } if received.0 > sent.0 => Some(WebhookEvent::NewDeposit {
+                address: address.clone(),
+                id: event.id,
+                amount: Currency(received.0 - sent.0),
+                height: event.index.height,
+            }),
+            AddressEvent::New { .. } => None,
+            AddressEvent::Reverted { id, index } => Some(WebhookEvent::Reorg {
+                id: *id,
+                reverted: *index,
+            }),
+        }
+    }
+
+    /// Map a change yielded by `subscribe_confirmed_transaction` for transaction `txid`
+    pub fn from_confirmation_event(txid: TransactionID, event: &ConfirmationEvent) -> Self {
+        match event {
+            ConfirmationEvent::Confirmed { index, confirmations } => WebhookEvent::ConfirmationReached {
+                txid,
+                height: index.height,
+                confirmations: *confirmations,
+            },
+            ConfirmationEvent::Reverted { index } => WebhookEvent::Reorg {
+                id: txid.into(),
+                reverted: *index,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    timestamp: i64,
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhookEndpoint {
+    pub url: Url,
+    /// Shared secret used to sign payloads sent to `url`
+    pub secret: String,
+    /// Event kinds sent to `url`, all if not set
+    #[serde(default)]
+    pub events: Option<Vec<WebhookEventKind>>,
+}
+
+impl WebhookEndpoint {
+    fn wants(&self, kind: WebhookEventKind) -> bool {
+        match &self.events {
+            Some(events) => events.contains(&kind),
+            None => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhookConf {
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Attempts per delivery including the first one
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay in milliseconds before the first retry, doubled after each further failure up to `max_retry_delay`
+    #[serde(default = "default_retry_delay")]
+    pub retry_delay: u64,
+    /// Longest delay in milliseconds between two attempts
+    #[serde(default = "default_max_retry_delay")]
+    pub max_retry_delay: u64,
+}
+
+fn default_max_attempts() -> u32 { 5 }
+
+fn default_retry_delay() -> u64 { 1000 }
+
+fn default_max_retry_delay() -> u64 { 60_000 }
+
+/// Sends a single signed webhook request.
+///
+/// Kept separate from `ApiClient` since webhooks go to arbitrary URLs and need custom headers.
+#[async_trait]
+pub trait WebhookSender: Send + Sync {
+    /// POST `body` as JSON to `url` with the extra `headers`. Non-2xx responses must be returned as errors.
+    async fn post(&self, url: &Url, headers: &[(&'static str, String)], body: Vec<u8>) -> Result<(), WebhookError>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl WebhookSender for reqwest::Client {
+    async fn post(&self, url: &Url, headers: &[(&'static str, String)], body: Vec<u8>) -> Result<(), WebhookError> {
+        let mut request = reqwest::Client::post(self, url.clone())
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| WebhookError::Transport(e.to_string()))?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status => Err(WebhookError::UnexpectedHttpStatus(status)),
+        }
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of `"<timestamp>.<body>"` keyed with `secret`
+pub fn sign_payload(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Check a received `HEADER_SIGNATURE` value in constant time, for use by webhook receivers
+pub fn verify_signature(secret: &[u8], timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let signature = match signature.strip_prefix("sha256=").map(hex::decode) {
+        Some(Ok(signature)) => signature,
+        _ => return false,
+    };
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Delivers `WebhookEvent`s to the configured endpoints.
+///
+/// Events come from whatever drives the wallet, eg. the streams of `subscribe_address_events` and
+/// `subscribe_confirmed_transaction` via `WebhookEvent::from_address_event` and
+/// `WebhookEvent::from_confirmation_event`, or `TxTracker::poll` via `WebhookEvent::from_tracker_event`.
+/// Deliveries are retried with exponential backoff; each endpoint is delivered to independently.
+#[derive(Clone, Debug)]
+pub struct WebhookNotifier<S> {
+    conf: WebhookConf,
+    sender: S,
+}
+
+impl<S: WebhookSender> WebhookNotifier<S> {
+    pub fn new(conf: WebhookConf, sender: S) -> Self { WebhookNotifier { conf, sender } }
+
+    /// Deliver `event` to every endpoint subscribed to its kind.
+    /// Returns one error per endpoint that could not be delivered to.
+    pub async fn notify(&self, event: &WebhookEvent) -> Result<(), Vec<WebhookError>> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let body = serde_json::to_vec(&WebhookPayload { timestamp, event }).map_err(|e| vec![e.into()])?;
+
+        let mut errors = Vec::new();
+        for endpoint in self.conf.endpoints.iter().filter(|e| e.wants(event.kind())) {
+            if let Err(e) = self.deliver(endpoint, timestamp, &body).await {
+                errors.push(e);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Deliver `events` in order, see `notify`
+    pub async fn notify_all<'a, I>(&self, events: I) -> Result<(), Vec<WebhookError>>
+    where
+        I: IntoIterator<Item = &'a WebhookEvent>,
+    {
+        let mut errors = Vec::new();
+        for event in events {
+            if let Err(e) = self.notify(event).await {
+                errors.extend(e);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    async fn deliver(&self, endpoint: &WebhookEndpoint, timestamp: i64, body: &[u8]) -> Result<(), WebhookError> {
+        let signature = sign_payload(endpoint.secret.as_bytes(), timestamp, body);
+        let headers = [
+            (HEADER_TIMESTAMP, timestamp.to_string()),
+            (HEADER_SIGNATURE, format!("sha256={}", signature)),
+        ];
+
+        let max_attempts = self.conf.max_attempts.max(1);
+        let max_delay = Duration::from_millis(self.conf.max_retry_delay);
+        let mut delay = Duration::from_millis(self.conf.retry_delay).min(max_delay);
+        let mut attempt = 1;
+        loop {
+            match self.sender.post(&endpoint.url, &headers, body.to_vec()).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= max_attempts => {
+                    return Err(WebhookError::DeliveryFailed {
+                        url: endpoint.url.clone(),
+                        attempts: attempt,
+                        last: Box::new(e),
+                    })
+                },
+                Err(_) => {
+                    sleep(delay).await;
+                    delay = delay.saturating_mul(2).min(max_delay);
+                    attempt += 1;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use crate::types::H256;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    type Request = (Url, Vec<(&'static str, String)>, Vec<u8>);
+
+    /// Fails the first `failures` requests, then records the bodies it receives
+    #[derive(Default)]
+    struct FlakySender {
+        failures: u32,
+        attempts: AtomicU32,
+        received: Mutex<Vec<Request>>,
+    }
+
+    #[async_trait]
+    impl WebhookSender for FlakySender {
+        async fn post(&self, url: &Url, headers: &[(&'static str, String)], body: Vec<u8>) -> Result<(), WebhookError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(WebhookError::UnexpectedHttpStatus(http::StatusCode::BAD_GATEWAY));
+            }
+            self.received
+                .lock()
+                .unwrap()
+                .push((url.clone(), headers.to_vec(), body));
+            Ok(())
+        }
+    }
+
+    fn endpoint(url: &str, events: Option<Vec<WebhookEventKind>>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            url: Url::parse(url).unwrap(),
+            secret: "secret".to_owned(),
+            events,
+        }
+    }
+
+    fn conf(endpoints: Vec<WebhookEndpoint>) -> WebhookConf {
+        WebhookConf {
+            endpoints,
+            max_attempts: 3,
+            retry_delay: 0,
+            max_retry_delay: 0,
+        }
+    }
+
+    fn confirmation() -> WebhookEvent {
+        WebhookEvent::ConfirmationReached {
+            txid: TransactionID::default(),
+            height: 10,
+            confirmations: 1,
+        }
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        let mut mac = HmacSha256::new_from_slice(b"Jefe").unwrap();
+        mac.update(b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(mac.finalize().into_bytes()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let signature = sign_payload(b"secret", 1700000000, b"{}");
+        assert!(verify_signature(
+            b"secret",
+            1700000000,
+            b"{}",
+            &format!("sha256={}", signature)
+        ));
+        assert!(!verify_signature(
+            b"secret",
+            1700000001,
+            b"{}",
+            &format!("sha256={}", signature)
+        ));
+        assert!(!verify_signature(
+            b"other",
+            1700000000,
+            b"{}",
+            &format!("sha256={}", signature)
+        ));
+        assert!(!verify_signature(b"secret", 1700000000, b"{}", &signature));
+    }
+
+    #[test]
+    fn test_webhook_event_serialize() {
+        let json = serde_json::to_value(WebhookPayload {
+            timestamp: 1,
+            event: &confirmation(),
+        })
+        .unwrap();
+        assert_eq!(json["timestamp"], 1);
+        assert_eq!(json["type"], "confirmationReached");
+        assert_eq!(json["data"]["height"], 10);
+    }
+
+    #[test]
+    fn test_webhook_low_balance() {
+        let address = Address(H256::default());
+        assert!(WebhookEvent::low_balance(address.clone(), 10.into(), 10.into()).is_none());
+        let event = WebhookEvent::low_balance(address, 9.into(), 10.into()).unwrap();
+        assert_eq!(event.kind(), WebhookEventKind::LowBalance);
+    }
+
+    #[test]
+    fn test_webhook_events_from_streams() {
+        let address = Address(H256::default());
+        let index = ChainIndex {
+            height: 10,
+            id: crate::types::BlockID(H256::default()),
+        };
+        let event: crate::types::Event = serde_json::from_value(json!({
+            "id": format!("h:{:064x}", 1),
+            "index": index,
+            "timestamp": "2024-07-18T19:04:16Z",
+            "maturityHeight": 10,
+            "type": "v2Transaction",
+            "data": {
+                "siacoinOutputs": [],
+                "minerFee": "0"
+            }
+        }))
+        .unwrap();
+        let id = event.id;
+
+        let deposit = AddressEvent::New {
+            event: event.clone(),
+            received: 1000.into(),
+            sent: 400.into(),
+        };
+        assert_eq!(
+            WebhookEvent::from_address_event(&address, &deposit),
+            Some(WebhookEvent::NewDeposit {
+                address: address.clone(),
+                id,
+                amount: 600.into(),
+                height: 10,
+            })
+        );
+        let payment = AddressEvent::New {
+            event,
+            received: 400.into(),
+            sent: 1000.into(),
+        };
+        assert_eq!(WebhookEvent::from_address_event(&address, &payment), None);
+        let reverted = AddressEvent::Reverted { id, index };
+        assert_eq!(
+            WebhookEvent::from_address_event(&address, &reverted),
+            Some(WebhookEvent::Reorg { id, reverted: index })
+        );
+
+        let txid = TransactionID::default();
+        let confirmed = ConfirmationEvent::Confirmed {
+            index,
+            confirmations: 6,
+        };
+        assert_eq!(
+            WebhookEvent::from_confirmation_event(txid, &confirmed),
+            WebhookEvent::ConfirmationReached {
+                txid,
+                height: 10,
+                confirmations: 6,
+            }
+        );
+        assert_eq!(
+            WebhookEvent::from_confirmation_event(txid, &ConfirmationEvent::Reverted { index }).kind(),
+            WebhookEventKind::Reorg
+        );
+        assert_eq!(
+            WebhookEvent::from_tracker_event(&TrackerEvent::Confirmed { txid, height: 10 }, 12),
+            Some(WebhookEvent::ConfirmationReached {
+                txid,
+                height: 10,
+                confirmations: 3,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notify_retries() {
+        let sender = FlakySender {
+            failures: 2,
+            ..Default::default()
+        };
+        let notifier = WebhookNotifier::new(conf(vec![endpoint("https://example.com/hook", None)]), sender);
+        notifier.notify(&confirmation()).await.unwrap();
+
+        assert_eq!(notifier.sender.attempts.load(Ordering::SeqCst), 3);
+        let received = notifier.sender.received.lock().unwrap();
+        let (_, headers, body) = &received[0];
+        let timestamp: i64 = headers[0].1.parse().unwrap();
+        assert!(verify_signature(b"secret", timestamp, body, &headers[1].1));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notify_gives_up() {
+        let sender = FlakySender {
+            failures: 5,
+            ..Default::default()
+        };
+        let notifier = WebhookNotifier::new(conf(vec![endpoint("https://example.com/hook", None)]), sender);
+        let errors = notifier.notify(&confirmation()).await.unwrap_err();
+
+        assert_eq!(notifier.sender.attempts.load(Ordering::SeqCst), 3);
+        assert!(matches!(errors[..], [WebhookError::DeliveryFailed { attempts: 3, .. }]));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notify_filters_kinds() {
+        let endpoints = vec![
+            endpoint("https://example.com/all", None),
+            endpoint("https://example.com/deposits", Some(vec![WebhookEventKind::NewDeposit])),
+        ];
+        let notifier = WebhookNotifier::new(conf(endpoints), FlakySender::default());
+        notifier.notify(&confirmation()).await.unwrap();
+
+        let received = notifier.sender.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0.as_str(), "https://example.com/all");
+    }
+}
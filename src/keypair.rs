@@ -0,0 +1,286 @@
+//! Ed25519 keys and the seeds they are derived from
+use crate::blake2b_internal::hash_blake2b_single;
+use crate::spend_policy::UnlockCondition;
+use crate::types::Address;
+use crate::{PublicKey, Signature};
+use ed25519_dalek::{Keypair as Ed25519Keypair, PublicKey as Ed25519PublicKey, SecretKey,
+                    SignatureError as Ed25519SignatureError, Signer};
+use hex::FromHexError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryInto;
+use std::fmt;
+use std::ops::Deref;
+use thiserror::Error;
+
+/// Length in bytes of a seed, and of the secret key derived from it
+pub const SEED_LENGTH: usize = 32;
+
+/// Number of words of a siad seed phrase dictionary
+pub const DICTIONARY_SIZE: usize = 1626;
+
+/// Words of a dictionary are identified by their first 3 characters
+const UNIQUE_PREFIX_LENGTH: usize = 3;
+
+/// Length of the blake2b checksum appended to the seed before it is encoded as a phrase
+const SEED_CHECKSUM_LENGTH: usize = 6;
+
+#[derive(Debug, Error)]
+pub enum KeypairError {
+    #[error("KeypairError error: invalid secret key: {0}")]
+    InvalidSecretKey(#[source] Ed25519SignatureError),
+    #[error("KeypairError error: invalid hex encoding: {0}")]
+    InvalidHex(#[from] FromHexError),
+    #[error("KeypairError error: expected {SEED_LENGTH} bytes, found {0}")]
+    InvalidLength(usize),
+    #[error("KeypairError error: dictionary must contain {DICTIONARY_SIZE} words, found {0}")]
+    InvalidDictionary(usize),
+    #[error("KeypairError error: unknown word {0}")]
+    UnknownWord(String),
+    #[error("KeypairError error: seed phrase encodes {0} bytes, expected {}", SEED_LENGTH + SEED_CHECKSUM_LENGTH)]
+    InvalidPhraseLength(usize),
+    #[error("KeypairError error: invalid seed phrase checksum")]
+    InvalidChecksum,
+    #[cfg(feature = "getrandom")]
+    #[error("KeypairError error: failed to generate randomness: {0}")]
+    Random(#[from] getrandom::Error),
+}
+
+pub struct Keypair(pub Ed25519Keypair);
+
+impl Keypair {
+    pub fn from_private_bytes(bytes: &[u8]) -> Result<Self, KeypairError> {
+        let secret = SecretKey::from_bytes(bytes).map_err(KeypairError::InvalidSecretKey)?;
+        let public = Ed25519PublicKey::from(&secret);
+        Ok(Keypair(Ed25519Keypair { secret, public }))
+    }
+
+    /// Import a hex encoded 32 byte private key
+    pub fn from_hex(s: &str) -> Result<Self, KeypairError> {
+        let bytes = hex::decode(s)?;
+        if bytes.len() != SEED_LENGTH {
+            return Err(KeypairError::InvalidLength(bytes.len()));
+        }
+        Keypair::from_private_bytes(&bytes)
+    }
+
+    /// Generate a new keypair from the OS random number generator
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Result<Self, KeypairError> {
+        let mut secret = [0u8; SEED_LENGTH];
+        getrandom::getrandom(&mut secret)?;
+        Keypair::from_private_bytes(&secret)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature { self.0.sign(message).into() }
+
+    pub fn public(&self) -> PublicKey { PublicKey(self.0.public) }
+
+    /// The standard unlock condition of the key: the key itself, no timelock and a single required signature
+    pub fn unlock_condition(&self) -> UnlockCondition { UnlockCondition::standard_unlock(self.public()) }
+
+    /// The address of the standard unlock condition, as used by walletd and siad
+    pub fn address(&self) -> Address { self.unlock_condition().address() }
+}
+
+impl Deref for Keypair {
+    type Target = Ed25519Keypair;
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+/// The 32 bytes of entropy a wallet's keys are derived from.
+///
+/// The key at `index` is derived the same way as by siad and walletd, so a seed imported from either yields the
+/// same addresses.
+/// ```ignore
+/// let seed = Seed::from_phrase(phrase, &dictionary)?;
+/// let keypair = seed.keypair(0);
+/// println!("{}", keypair.address());
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct Seed([u8; SEED_LENGTH]);
+
+impl Seed {
+    pub fn from_bytes(bytes: [u8; SEED_LENGTH]) -> Self { Seed(bytes) }
+
+    pub fn from_hex(s: &str) -> Result<Self, KeypairError> {
+        let bytes = hex::decode(s)?;
+        let len = bytes.len();
+        bytes.try_into().map(Seed).map_err(|_| KeypairError::InvalidLength(len))
+    }
+
+    /// Generate a new seed from the OS random number generator
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Result<Self, KeypairError> {
+        let mut seed = [0u8; SEED_LENGTH];
+        getrandom::getrandom(&mut seed)?;
+        Ok(Seed(seed))
+    }
+
+    /// Decode a siad seed phrase of 28 or 29 words.
+    ///
+    /// Only the first 3 characters of each word are significant. The dictionary is not bundled with this crate yet,
+    /// `dictionary` must be the 1626 words of the language the phrase was created in, in siad's order, eg. the
+    /// `English` dictionary of the NebulousLabs entropy-mnemonics Go package used by siad.
+    pub fn from_phrase(phrase: &str, dictionary: &[&str]) -> Result<Self, KeypairError> {
+        check_dictionary(dictionary)?;
+        let digits = phrase
+            .split_whitespace()
+            .map(|word| {
+                let word = word.to_lowercase();
+                let prefix: String = word.chars().take(UNIQUE_PREFIX_LENGTH).collect();
+                dictionary
+                    .iter()
+                    .position(|entry| entry.starts_with(&prefix))
+                    .map(|index| index as u32)
+                    .ok_or(KeypairError::UnknownWord(word))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if digits.is_empty() {
+            return Err(KeypairError::InvalidPhraseLength(0));
+        }
+        let bytes: Vec<u8> = convert_base(&digits, DICTIONARY_SIZE as u32, 256)
+            .into_iter()
+            .map(|byte| byte as u8)
+            .collect();
+        if bytes.len() != SEED_LENGTH + SEED_CHECKSUM_LENGTH {
+            return Err(KeypairError::InvalidPhraseLength(bytes.len()));
+        }
+        let (seed, checksum) = bytes.split_at(SEED_LENGTH);
+        let expected = hash_blake2b_single(seed).0;
+        if expected[..SEED_CHECKSUM_LENGTH] != *checksum {
+            return Err(KeypairError::InvalidChecksum);
+        }
+        let mut bytes = [0u8; SEED_LENGTH];
+        bytes.copy_from_slice(seed);
+        Ok(Seed(bytes))
+    }
+
+    /// Encode the seed as a siad seed phrase using `dictionary`, see `from_phrase`
+    pub fn to_phrase(&self, dictionary: &[&str]) -> Result<String, KeypairError> {
+        check_dictionary(dictionary)?;
+        let checksum = hash_blake2b_single(&self.0).0;
+        let digits: Vec<u32> = self
+            .0
+            .iter()
+            .chain(checksum[..SEED_CHECKSUM_LENGTH].iter())
+            .map(|byte| *byte as u32)
+            .collect();
+        let words: Vec<&str> = convert_base(&digits, 256, DICTIONARY_SIZE as u32)
+            .into_iter()
+            .map(|index| dictionary[index as usize])
+            .collect();
+        Ok(words.join(" "))
+    }
+
+    /// The keypair at `index`, whose secret key is `blake2b(seed | index)` with the index as 8 little endian bytes
+    pub fn keypair(&self, index: u64) -> Keypair {
+        let mut preimage = Vec::with_capacity(SEED_LENGTH + 8);
+        preimage.extend_from_slice(&self.0);
+        preimage.extend_from_slice(&index.to_le_bytes());
+        let secret = hash_blake2b_single(&preimage).0;
+        Keypair::from_private_bytes(&secret).expect("any 32 bytes are a valid ed25519 secret key")
+    }
+
+    pub fn as_bytes(&self) -> &[u8; SEED_LENGTH] { &self.0 }
+}
+
+/// Deliberately omits the seed itself, so it does not end up in logs
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str("Seed(..)") }
+}
+
+/// Serialized as hex, as accepted by `Seed::from_hex`
+impl Serialize for Seed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex::encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Seed {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Seed::from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+fn check_dictionary(dictionary: &[&str]) -> Result<(), KeypairError> {
+    match dictionary.len() {
+        DICTIONARY_SIZE => Ok(()),
+        len => Err(KeypairError::InvalidDictionary(len)),
+    }
+}
+
+/// Convert little endian digits between bases the way siad's `mnemonics` package does, which treats every digit
+/// as one more than its value so leading zeros are preserved: `digits` represent `sum((d_i + 1) * from^i) - 1`.
+fn convert_base(digits: &[u32], from: u32, to: u32) -> Vec<u32> {
+    // little endian limbs of an arbitrary precision integer
+    let mut value: Vec<u32> = vec![0];
+    for digit in digits.iter().rev() {
+        mul_add(&mut value, from, digit + 1);
+    }
+    sub_one(&mut value);
+
+    let mut converted = Vec::new();
+    loop {
+        match small_value(&value) {
+            Some(rest) if rest < to => {
+                converted.push(rest);
+                return converted;
+            },
+            _ => {
+                converted.push(div_rem(&mut value, to));
+                sub_one(&mut value);
+            },
+        }
+    }
+}
+
+fn mul_add(value: &mut Vec<u32>, mul: u32, add: u32) {
+    let mut carry = add as u64;
+    for limb in value.iter_mut() {
+        let product = *limb as u64 * mul as u64 + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        value.push(carry as u32);
+    }
+}
+
+fn div_rem(value: &mut Vec<u32>, div: u32) -> u32 {
+    let mut rem = 0u64;
+    for limb in value.iter_mut().rev() {
+        let current = (rem << 32) | *limb as u64;
+        *limb = (current / div as u64) as u32;
+        rem = current % div as u64;
+    }
+    while value.len() > 1 && value.last() == Some(&0) {
+        value.pop();
+    }
+    rem as u32
+}
+
+/// Callers only subtract from values known to be positive
+fn sub_one(value: &mut [u32]) {
+    for limb in value.iter_mut() {
+        let (result, borrow) = limb.overflowing_sub(1);
+        *limb = result;
+        if !borrow {
+            return;
+        }
+    }
+}
+
+fn small_value(value: &[u32]) -> Option<u32> {
+    match value.split_first() {
+        Some((first, rest)) if rest.iter().all(|limb| *limb == 0) => Some(*first),
+        _ => None,
+    }
+}
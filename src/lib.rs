@@ -1,42 +1,47 @@
-use derive_more::Display;
-use ed25519_dalek::{Keypair as Ed25519Keypair, PublicKey as Ed25519PublicKey, SecretKey,
-                    Signature as Ed25519Signature, SignatureError as Ed25519SignatureError, Signer};
+use ed25519_dalek::{PublicKey as Ed25519PublicKey, Signature as Ed25519Signature,
+                    SignatureError as Ed25519SignatureError};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
+use thiserror::Error;
 
+#[cfg(feature = "backup")] pub mod backup;
 pub mod blake2b_internal;
 pub mod encoding;
 pub mod hash;
-pub mod http;
+#[cfg(feature = "http")] pub mod http;
+pub mod keypair;
+#[cfg(all(feature = "local-node", not(target_arch = "wasm32")))]
+pub mod local_node;
+pub mod payment_uri;
+pub mod prelude;
+#[cfg(feature = "price")] pub mod price;
+#[cfg(feature = "qr")] pub mod qr;
+#[cfg(all(feature = "rpc", not(target_arch = "wasm32")))]
+pub mod rpc;
+#[cfg(feature = "wallet")] pub mod signing_session;
+#[cfg(feature = "snapshot")] pub mod snapshot;
 pub mod specifier;
 pub mod spend_policy;
+pub mod swap;
 pub mod transaction;
 pub mod types;
+#[cfg(feature = "wallet")] pub mod utxo;
 
-#[derive(Debug, Display)]
-pub enum KeypairError {
-    InvalidSecretKey(Ed25519SignatureError),
-}
+/// The `ApiClient` implementation of the target platform and its configuration
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub use http::client::native::{ClientConfBuilder, Conf as SiaClientConf, NativeClient as SiaClient};
+#[cfg(all(feature = "http", target_arch = "wasm32"))]
+pub use http::client::wasm::{ClientConfBuilder, Conf as SiaClientConf, WasmClient as SiaClient};
+
+pub use keypair::{Keypair, KeypairError, Seed};
 
 #[cfg(test)] mod tests;
 #[cfg(test)]
 #[macro_use]
 extern crate serde_json;
 
-pub struct Keypair(pub Ed25519Keypair);
-
-impl Keypair {
-    pub fn from_private_bytes(bytes: &[u8]) -> Result<Self, KeypairError> {
-        let secret = SecretKey::from_bytes(bytes).map_err(KeypairError::InvalidSecretKey)?;
-        let public = Ed25519PublicKey::from(&secret);
-        Ok(Keypair(Ed25519Keypair { secret, public }))
-    }
-
-    pub fn sign(&self, message: &[u8]) -> Signature { self.0.sign(message).into() }
-}
-
 #[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Signature(pub Ed25519Signature);
 
@@ -57,10 +62,12 @@ impl Deref for Signature {
     fn deref(&self) -> &Self::Target { &self.0 }
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Error)]
 pub enum SignatureError {
-    ParseError(ed25519_dalek::ed25519::Error),
-    InvalidSignature(Ed25519SignatureError),
+    #[error("SignatureError error: failed to parse: {0}")]
+    ParseError(#[source] ed25519_dalek::ed25519::Error),
+    #[error("SignatureError error: invalid signature: {0}")]
+    InvalidSignature(#[source] Ed25519SignatureError),
 }
 
 impl From<ed25519_dalek::ed25519::Error> for SignatureError {
@@ -104,13 +111,3 @@ impl Deref for PublicKey {
 impl fmt::Display for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", hex::encode(self.as_bytes())) }
 }
-
-impl Deref for Keypair {
-    type Target = Ed25519Keypair;
-
-    fn deref(&self) -> &Self::Target { &self.0 }
-}
-
-impl Keypair {
-    pub fn public(&self) -> PublicKey { PublicKey(self.0.public) }
-}
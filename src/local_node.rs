@@ -0,0 +1,340 @@
+//! Dev utility launching a throwaway walletd for integration tests, see `LocalNode`
+use crate::http::client::native::{Conf, NativeClient};
+use crate::http::client::{sleep, ApiClient, ApiClientError};
+use core::time::Duration;
+use instant::Instant;
+use sha2::{Digest, Sha256};
+use std::io::{self, Cursor};
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use tempfile::TempDir;
+use thiserror::Error;
+use url::Url;
+
+/// The walletd release downloaded by default
+pub const WALLETD_VERSION: &str = "v0.8.0";
+/// Path of an existing walletd binary to use instead of downloading one
+pub const WALLETD_BIN_ENV: &str = "WALLETD_BIN";
+/// Hex encoded SHA-256 of the release archive for the current platform, overriding the pinned one, see
+/// `LocalNodeConf::sha256`
+pub const WALLETD_SHA256_ENV: &str = "WALLETD_SHA256";
+
+/// Hex encoded SHA-256 of the release archives by release tag and `release_asset`, checked before a downloaded
+/// walletd is run. A release or platform missing here needs `LocalNodeConf::sha256`.
+const PINNED_SHA256: &[(&str, &str, &str)] = &[];
+
+const RELEASES_URL: &str = "https://github.com/SiaFoundation/walletd/releases/download";
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Times walletd is started on a new port if another process took the one picked for it
+const SPAWN_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum LocalNodeError {
+    #[error("LocalNodeError error: {0}")]
+    Io(#[from] io::Error),
+    #[error("LocalNodeError error: failed to download walletd: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("LocalNodeError error: failed to download walletd from {url}: status {status}")]
+    DownloadStatus { url: String, status: http::StatusCode },
+    #[error("LocalNodeError error: failed to extract walletd: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("LocalNodeError error: no walletd binary in release archive")]
+    BinaryNotFound,
+    #[error("LocalNodeError error: no walletd release for {0}")]
+    UnsupportedPlatform(String),
+    #[error("LocalNodeError error: no SHA-256 pinned for {0}, set `LocalNodeConf::sha256` or $WALLETD_SHA256")]
+    ChecksumRequired(String),
+    #[error("LocalNodeError error: SHA-256 of {asset} is {found}, expected {expected}")]
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        found: String,
+    },
+    #[error("LocalNodeError error: walletd exited during startup with {0}")]
+    Exited(ExitStatus),
+    #[error("LocalNodeError error: walletd not ready after {timeout:?}: {last_error}")]
+    NotReady {
+        timeout: Duration,
+        last_error: ApiClientError,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalNodeConf {
+    /// walletd release tag, ignored if `bin` is set
+    pub version: String,
+    /// Existing walletd binary, defaults to `$WALLETD_BIN` or a downloaded release
+    pub bin: Option<PathBuf>,
+    /// Hex encoded SHA-256 of the release archive of `version` for the current platform, see `release_asset`,
+    /// overriding the one pinned for it, see `pinned_sha256`. Defaults to `$WALLETD_SHA256`. The archive is checked
+    /// against it when downloaded and every time it is reused from `cache_dir`.
+    pub sha256: Option<String>,
+    /// Where downloaded release archives are kept between runs, created readable by the current user only
+    pub cache_dir: PathBuf,
+    pub network: String,
+    pub api_password: String,
+    pub startup_timeout: Duration,
+}
+
+impl Default for LocalNodeConf {
+    fn default() -> Self {
+        LocalNodeConf {
+            version: WALLETD_VERSION.to_owned(),
+            bin: std::env::var_os(WALLETD_BIN_ENV).map(PathBuf::from),
+            sha256: std::env::var(WALLETD_SHA256_ENV).ok(),
+            cache_dir: default_cache_dir(),
+            network: "zen".to_owned(),
+            api_password: "password".to_owned(),
+            startup_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A walletd process running against a temporary data directory.
+///
+/// The process is killed and the data directory, holding the extracted binary of a downloaded release, removed
+/// when dropped.
+/// ```ignore
+/// let node = LocalNode::spawn(LocalNodeConf::default()).await?;
+/// let tip = node.client().dispatcher(ConsensusTipRequest).await?;
+/// ```
+pub struct LocalNode {
+    child: Child,
+    client: NativeClient,
+    api_url: Url,
+    // kept alive until the process is stopped
+    _data_dir: TempDir,
+}
+
+impl LocalNode {
+    /// Download walletd if needed, start it on a free localhost port and wait until its API responds
+    pub async fn spawn(conf: LocalNodeConf) -> Result<Self, LocalNodeError> {
+        let data_dir = tempfile::Builder::new().prefix("walletd-").tempdir()?;
+        let bin = match &conf.bin {
+            Some(bin) => bin.clone(),
+            None => {
+                let asset = release_asset()?;
+                let sha256 = match conf.sha256.as_deref() {
+                    Some(sha256) => sha256,
+                    None => pinned_sha256(&conf.version, &asset)
+                        .ok_or_else(|| LocalNodeError::ChecksumRequired(format!("{} {}", conf.version, asset)))?,
+                };
+                download_walletd(&conf.version, sha256, &conf.cache_dir, data_dir.path()).await?
+            },
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let port = free_port()?;
+            let api_url = Url::parse(&format!("http://127.0.0.1:{}/", port)).expect("valid url");
+            let mut child = Command::new(&bin)
+                .arg("-dir")
+                .arg(data_dir.path())
+                .arg("-http")
+                .arg(format!("127.0.0.1:{}", port))
+                .arg("-network")
+                .arg(&conf.network)
+                .env("WALLETD_API_PASSWORD", &conf.api_password)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::inherit())
+                .spawn()?;
+
+            match wait_ready(&mut child, &api_url, &conf).await {
+                Ok(client) => {
+                    return Ok(LocalNode {
+                        child,
+                        client,
+                        api_url,
+                        _data_dir: data_dir,
+                    })
+                },
+                Err(e) => {
+                    child.kill().ok();
+                    child.wait().ok();
+                    // the port is free when picked but only bound by walletd later, another process may have taken
+                    // it in between
+                    let port_taken = matches!(e, LocalNodeError::Exited(_))
+                        && TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_err();
+                    if !port_taken || attempt == SPAWN_ATTEMPTS {
+                        return Err(e);
+                    }
+                },
+            }
+        }
+    }
+
+    /// A client configured for this node
+    pub fn client(&self) -> &NativeClient { &self.client }
+
+    pub fn api_url(&self) -> &Url { &self.api_url }
+}
+
+impl Drop for LocalNode {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+async fn wait_ready(child: &mut Child, api_url: &Url, conf: &LocalNodeConf) -> Result<NativeClient, LocalNodeError> {
+    let client_conf = Conf {
+        server_url: api_url.clone(),
+        base_path: None,
+        fallback_urls: Vec::new(),
+        password: Some(conf.api_password.clone()),
+        timeout: Some(5),
+        deadline: None,
+        circuit_breaker: None,
+        throttle: None,
+        ip_family: Default::default(),
+        etag_cache: false,
+        response_cache: None,
+        slow_request_threshold: None,
+        retry: None,
+        pool: Default::default(),
+        user_agent: None,
+        headers: Default::default(),
+        proxy: None,
+        tls: Default::default(),
+        skip_health_check: false,
+    };
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Err(LocalNodeError::Exited(status));
+        }
+        // `NativeClient::new` pings the node, so it only succeeds once the API is up
+        match NativeClient::new(client_conf.clone()).await {
+            Ok(client) => return Ok(client),
+            Err(e) if started.elapsed() >= conf.startup_timeout => {
+                return Err(LocalNodeError::NotReady {
+                    timeout: conf.startup_timeout,
+                    last_error: e,
+                })
+            },
+            Err(_) => sleep(READINESS_POLL_INTERVAL).await,
+        }
+    }
+}
+
+fn free_port() -> io::Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Name of the release archive for the current platform, eg. `walletd_linux_amd64.zip`
+pub fn release_asset() -> Result<String, LocalNodeError> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "windows",
+        other => return Err(LocalNodeError::UnsupportedPlatform(other.to_owned())),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => return Err(LocalNodeError::UnsupportedPlatform(format!("{}/{}", os, other))),
+    };
+    Ok(format!("walletd_{}_{}.zip", os, arch))
+}
+
+/// The SHA-256 pinned for `asset` of the walletd release `version`, if any
+pub fn pinned_sha256(version: &str, asset: &str) -> Option<&'static str> {
+    PINNED_SHA256
+        .iter()
+        .find(|(pinned_version, pinned_asset, _)| *pinned_version == version && *pinned_asset == asset)
+        .map(|(_, _, sha256)| *sha256)
+}
+
+/// `sia-rust/walletd` in the user's cache directory, eg. `~/.cache` on Linux
+pub fn default_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("sia-rust")
+        .join("walletd")
+}
+
+/// Create `dir` accessible by the current user only. Fails if it exists and belongs to another user.
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Check that the hex encoded SHA-256 of `archive` is `expected`
+pub fn verify_sha256(asset: &str, archive: &[u8], expected: &str) -> Result<(), LocalNodeError> {
+    let found = hex::encode(Sha256::digest(archive));
+    if !found.eq_ignore_ascii_case(expected.trim()) {
+        return Err(LocalNodeError::ChecksumMismatch {
+            asset: asset.to_owned(),
+            expected: expected.to_owned(),
+            found,
+        });
+    }
+    Ok(())
+}
+
+/// Fetch the walletd release `version`, reusing the archive kept in `cache_dir` if it still matches `sha256`,
+/// and unpack its binary into `dest`
+pub async fn download_walletd(
+    version: &str,
+    sha256: &str,
+    cache_dir: &Path,
+    dest: &Path,
+) -> Result<PathBuf, LocalNodeError> {
+    let asset = release_asset()?;
+    let cached = cache_dir.join(version).join(&asset);
+    let archive = match std::fs::read(&cached) {
+        // a corrupted or tampered archive is downloaded again
+        Ok(archive) if verify_sha256(&asset, &archive, sha256).is_ok() => archive,
+        _ => {
+            let url = format!("{}/{}/{}", RELEASES_URL, version, asset);
+            let response = reqwest::get(&url).await?;
+            if !response.status().is_success() {
+                return Err(LocalNodeError::DownloadStatus {
+                    url,
+                    status: response.status(),
+                });
+            }
+            let archive = response.bytes().await?.to_vec();
+            verify_sha256(&asset, &archive, sha256)?;
+
+            create_private_dir(cache_dir)?;
+            create_private_dir(cached.parent().expect("archive has a parent"))?;
+            // write next to the final path and rename, so concurrent test runs never see a partial archive
+            let partial = cached.with_extension(format!("partial-{}", std::process::id()));
+            std::fs::write(&partial, &archive)?;
+            std::fs::rename(&partial, &cached)?;
+            archive
+        },
+    };
+
+    let bin_name = if cfg!(windows) { "walletd.exe" } else { "walletd" };
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+    let index = (0..archive.len())
+        .find(|i| {
+            archive
+                .by_index(*i)
+                .map(|file| Path::new(file.name()).file_name() == Some(bin_name.as_ref()))
+                .unwrap_or(false)
+        })
+        .ok_or(LocalNodeError::BinaryNotFound)?;
+
+    let bin = dest.join(bin_name);
+    let mut file = std::fs::File::create(&bin)?;
+    io::copy(&mut archive.by_index(index)?, &mut file)?;
+    drop(file);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(bin)
+}
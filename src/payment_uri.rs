@@ -0,0 +1,124 @@
+use crate::types::{Address, Currency, ParseAddressError};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use url::form_urlencoded;
+
+const PAYMENT_URI_SCHEME: &str = "sia:";
+
+/// A payment request, eg. as shown in a QR code
+///
+/// Encoded as `sia:<address hex>?amount=<hastings>&label=<label>&message=<message>` where every query
+/// parameter is optional. The address hex is the `Address` without its `addr:` prefix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentUri {
+    pub address: Address,
+    /// Amount requested in hastings
+    pub amount: Option<Currency>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PaymentUriError {
+    #[error("PaymentUriError error: invalid address: {0}")]
+    InvalidAddress(#[from] ParseAddressError),
+    #[error("PaymentUriError error: invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("PaymentUriError error: duplicate query parameter: {0}")]
+    DuplicateParameter(String),
+}
+
+impl PaymentUri {
+    pub fn new(address: Address) -> Self {
+        PaymentUri {
+            address,
+            amount: None,
+            label: None,
+            message: None,
+        }
+    }
+
+    pub fn amount(mut self, amount: Currency) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+impl From<Address> for PaymentUri {
+    fn from(address: Address) -> Self { PaymentUri::new(address) }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", PAYMENT_URI_SCHEME, self.address.str_without_prefix())?;
+
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(amount) = &self.amount {
+            query.append_pair("amount", &amount.0.to_string());
+        }
+        if let Some(label) = &self.label {
+            query.append_pair("label", label);
+        }
+        if let Some(message) = &self.message {
+            query.append_pair("message", message);
+        }
+        let query = query.finish();
+        if !query.is_empty() {
+            write!(f, "?{}", query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `PaymentUri` or a plain address, either with or without the `addr:` prefix.
+/// This accepts anything a user might scan or paste as a payment destination.
+impl FromStr for PaymentUri {
+    type Err = PaymentUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let without_scheme = match s.get(..PAYMENT_URI_SCHEME.len()) {
+            Some(scheme) if scheme.eq_ignore_ascii_case(PAYMENT_URI_SCHEME) => &s[PAYMENT_URI_SCHEME.len()..],
+            _ => s,
+        };
+        let (address, query) = match without_scheme.find('?') {
+            Some(i) => (&without_scheme[..i], &without_scheme[i + 1..]),
+            None => (without_scheme, ""),
+        };
+        let address = match address.strip_prefix("addr:") {
+            Some(_) => Address::from_str(address)?,
+            None => Address::from_str(&format!("addr:{}", address))?,
+        };
+
+        let mut uri = PaymentUri::new(address);
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let duplicate = match key.as_ref() {
+                "amount" => {
+                    let amount = value
+                        .parse::<u128>()
+                        .map_err(|_| PaymentUriError::InvalidAmount(value.to_string()))?;
+                    uri.amount.replace(Currency(amount)).is_some()
+                },
+                "label" => uri.label.replace(value.to_string()).is_some(),
+                "message" => uri.message.replace(value.to_string()).is_some(),
+                // ignore unknown parameters for forward compatibility
+                _ => false,
+            };
+            if duplicate {
+                return Err(PaymentUriError::DuplicateParameter(key.to_string()));
+            }
+        }
+        Ok(uri)
+    }
+}
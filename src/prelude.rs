@@ -0,0 +1,17 @@
+//! The types and traits most users of the crate need, `use sia_rust::prelude::*;`
+//!
+//! Prefer these over the paths of the defining modules, which may move between releases.
+pub use crate::spend_policy::{SpendPolicy, UnlockCondition};
+pub use crate::transaction::{SiacoinElement, SiacoinOutput, V2Transaction, V2TransactionBuilder};
+pub use crate::types::{Address, BlockID, ChainIndex, ConsensusState, Currency, Event, EventID, Siacoins,
+                       TransactionID, H256};
+pub use crate::{Keypair, PublicKey, Seed, Signature};
+
+#[cfg(feature = "http")]
+pub use crate::http::client::cancel::CancellationToken;
+#[cfg(feature = "http")]
+pub use crate::http::client::{ApiClient, ApiClientError, ApiClientHelpers, RequestOpts};
+#[cfg(feature = "http")]
+pub use crate::http::endpoints::SiaApiRequest;
+#[cfg(feature = "http")]
+pub use crate::{SiaClient, SiaClientConf};
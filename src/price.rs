@@ -0,0 +1,241 @@
+use crate::http::client::{ApiClient, ApiClientError, EndpointSchema, EndpointSchemaBuilder, SchemaMethod};
+use crate::http::endpoints::{AddressBalanceResponse, SiaApiRequest};
+use crate::transaction::HASTINGS_PER_SIACOIN;
+use crate::types::Currency;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PriceError {
+    #[error("PriceError error: {0}")]
+    Client(#[from] ApiClientError),
+    #[error("PriceError error: no rate at {pointer} in response: {response}")]
+    MissingRate { pointer: String, response: String },
+    #[error("PriceError error: historical rates are not supported by this source")]
+    HistoricalUnsupported,
+}
+
+/// A source of SC/USD exchange rates, used only to display amounts in fiat.
+/// Rates are never used for anything consensus or fee related.
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// USD value of one siacoin at `at`, or the current rate if `at` is `None`
+    async fn sc_usd_rate(&self, at: Option<DateTime<Utc>>) -> Result<f64, PriceError>;
+}
+
+/// Configuration of `HttpPriceSource`
+///
+/// eg. for an endpoint responding with `{"rates":{"sc":{"usd":0.0042}}}`, `rate_pointer` is `/rates/sc/usd`.
+#[derive(Clone, Debug)]
+pub struct HttpPriceSourceConf {
+    /// Path of the price endpoint relative to the client's base URL
+    pub path: String,
+    /// JSON pointer (RFC 6901) to the rate within the response. The rate may be a number or a numeric string.
+    pub rate_pointer: String,
+    /// Query parameter carrying a unix timestamp for historical rates.
+    /// Historical lookups fail with `PriceError::HistoricalUnsupported` if unset.
+    pub timestamp_param: Option<String>,
+}
+
+/// Reference `PriceSource` fetching rates from a configurable HTTP endpoint.
+///
+/// Any `ApiClient` can be used; its base URL should point at the price service rather than walletd.
+#[derive(Clone, Debug)]
+pub struct HttpPriceSource<C: ApiClient> {
+    client: C,
+    conf: HttpPriceSourceConf,
+}
+
+impl<C: ApiClient> HttpPriceSource<C> {
+    pub fn new(client: C, conf: HttpPriceSourceConf) -> Self { HttpPriceSource { client, conf } }
+}
+
+/// GET request for an arbitrary price endpoint, see `HttpPriceSource`
+#[derive(Debug)]
+pub struct PriceRequest {
+    pub path: String,
+    pub query_params: HashMap<String, String>,
+}
+
+impl SiaApiRequest for PriceRequest {
+    type Response = JsonValue;
+
+    fn to_endpoint_schema(&self) -> Result<EndpointSchema, ApiClientError> {
+        Ok(EndpointSchemaBuilder::new(self.path.clone(), SchemaMethod::Get)
+            .query_params(self.query_params.clone())
+            .build())
+    }
+}
+
+/// Extract the rate at `pointer` from a price endpoint response
+pub fn rate_from_response(response: &JsonValue, pointer: &str) -> Result<f64, PriceError> {
+    let rate = match response.pointer(pointer) {
+        Some(JsonValue::Number(n)) => n.as_f64(),
+        Some(JsonValue::String(s)) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    rate.filter(|rate| rate.is_finite() && *rate >= 0.0)
+        .ok_or_else(|| PriceError::MissingRate {
+            pointer: pointer.to_owned(),
+            response: response.to_string(),
+        })
+}
+
+#[async_trait]
+impl<C> PriceSource for HttpPriceSource<C>
+where
+    C: ApiClient + Send + Sync,
+{
+    async fn sc_usd_rate(&self, at: Option<DateTime<Utc>>) -> Result<f64, PriceError> {
+        let mut query_params = HashMap::new();
+        if let Some(at) = at {
+            let param = self
+                .conf
+                .timestamp_param
+                .as_ref()
+                .ok_or(PriceError::HistoricalUnsupported)?;
+            query_params.insert(param.clone(), at.timestamp().to_string());
+        }
+        let request = PriceRequest {
+            path: self.conf.path.clone(),
+            query_params,
+        };
+        let response = self.client.dispatcher(request).await?;
+        rate_from_response(&response, &self.conf.rate_pointer)
+    }
+}
+
+/// An amount together with its USD value
+#[derive(Clone, Debug, PartialEq)]
+pub struct FiatAmount {
+    pub amount: Currency,
+    pub usd: f64,
+    /// The SC/USD rate `usd` was computed with
+    pub rate: f64,
+    /// When the rate applies, `None` for the current rate
+    pub at: Option<DateTime<Utc>>,
+}
+
+impl FiatAmount {
+    pub fn new(amount: Currency, rate: f64, at: Option<DateTime<Utc>>) -> Self {
+        FiatAmount {
+            usd: amount.0 as f64 / HASTINGS_PER_SIACOIN as f64 * rate,
+            amount,
+            rate,
+            at,
+        }
+    }
+}
+
+/// Annotate `amount` with its USD value at `at`, or at the current rate if `at` is `None`
+pub async fn annotate<P: PriceSource + ?Sized>(
+    source: &P,
+    amount: Currency,
+    at: Option<DateTime<Utc>>,
+) -> Result<FiatAmount, PriceError> {
+    let rate = source.sc_usd_rate(at).await?;
+    Ok(FiatAmount::new(amount, rate, at))
+}
+
+/// USD values of a balance at the current rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct FiatBalance {
+    pub siacoins: FiatAmount,
+    pub immature_siacoins: FiatAmount,
+}
+
+pub async fn annotate_balance<P: PriceSource + ?Sized>(
+    source: &P,
+    balance: &AddressBalanceResponse,
+) -> Result<FiatBalance, PriceError> {
+    let rate = source.sc_usd_rate(None).await?;
+    Ok(FiatBalance {
+        siacoins: FiatAmount::new(balance.siacoins, rate, None),
+        immature_siacoins: FiatAmount::new(balance.immature_siacoins, rate, None),
+    })
+}
+
+/// Annotate amounts with the USD value at the time each was transferred, eg. for a transaction report built
+/// from `Event::timestamp`. The rate is requested once per distinct timestamp.
+pub async fn annotate_historical<P, I>(source: &P, amounts: I) -> Result<Vec<FiatAmount>, PriceError>
+where
+    P: PriceSource + ?Sized,
+    I: IntoIterator<Item = (Currency, DateTime<Utc>)>,
+{
+    let mut rates: HashMap<DateTime<Utc>, f64> = HashMap::new();
+    let mut annotated = Vec::new();
+    for (amount, at) in amounts {
+        let rate = match rates.get(&at) {
+            Some(rate) => *rate,
+            None => {
+                let rate = source.sc_usd_rate(Some(at)).await?;
+                rates.insert(at, rate);
+                rate
+            },
+        };
+        annotated.push(FiatAmount::new(amount, rate, Some(at)));
+    }
+    Ok(annotated)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Rate of 0.01 USD for the current rate, otherwise 0.001 USD per day since the epoch
+    #[derive(Default)]
+    struct FixedSource {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for FixedSource {
+        async fn sc_usd_rate(&self, at: Option<DateTime<Utc>>) -> Result<f64, PriceError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(at.map(|at| (at.timestamp() / 86400) as f64 * 0.001).unwrap_or(0.01))
+        }
+    }
+
+    #[test]
+    fn test_rate_from_response() {
+        let response = json!({"rates": {"sc": {"usd": 0.0042, "eur": "0.0039"}}});
+        assert_eq!(rate_from_response(&response, "/rates/sc/usd").unwrap(), 0.0042);
+        assert_eq!(rate_from_response(&response, "/rates/sc/eur").unwrap(), 0.0039);
+        assert!(matches!(
+            rate_from_response(&response, "/rates/sc/gbp"),
+            Err(PriceError::MissingRate { .. })
+        ));
+        assert!(rate_from_response(&json!({"usd": -1}), "/usd").is_err());
+        assert!(rate_from_response(&json!({"usd": "abc"}), "/usd").is_err());
+    }
+
+    #[test]
+    fn test_fiat_amount() {
+        let amount = Currency(1_500_000_000_000_000_000_000_000);
+        let fiat = FiatAmount::new(amount, 0.01, None);
+        assert!((fiat.usd - 0.015).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_annotate_historical_caches_rates() {
+        let source = FixedSource::default();
+        let day_two = Utc.timestamp_opt(2 * 86400, 0).unwrap();
+        let day_three = Utc.timestamp_opt(3 * 86400, 0).unwrap();
+        let one_sc = Currency(1_000_000_000_000_000_000_000_000);
+
+        let amounts = vec![(one_sc, day_two), (one_sc, day_three), (one_sc, day_two)];
+        let annotated = annotate_historical(&source, amounts).await.unwrap();
+        let usd: Vec<f64> = annotated.iter().map(|a| a.usd).collect();
+        assert_eq!(usd, vec![0.002, 0.003, 0.002]);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+
+        let current = annotate(&source, one_sc, None).await.unwrap();
+        assert_eq!(current.usd, 0.01);
+        assert_eq!(current.at, None);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::payment_uri::PaymentUri;
+use crate::types::Address;
+use qrcode::{Color, EcLevel, QrCode};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// Modules of light border around the code, as required by the QR specification
+const QUIET_ZONE: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum QrError {
+    #[error("QrError error: failed to encode QR code: {0}")]
+    Encode(#[from] qrcode::types::QrError),
+    #[error("QrError error: failed to encode PNG: {0}")]
+    Png(#[from] png::EncodingError),
+    #[error("QrError error: image too large: {0} pixels wide")]
+    TooLarge(usize),
+}
+
+/// A rendered QR code without quiet zone. `modules` is `width * width` long in row-major order,
+/// `true` being a dark module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrMatrix {
+    pub width: usize,
+    pub modules: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn encode(data: &str) -> Result<Self, QrError> {
+        let code = QrCode::with_error_correction_level(data, EcLevel::M)?;
+        let modules = code.to_colors().into_iter().map(|c| c == Color::Dark).collect();
+        Ok(QrMatrix {
+            width: code.width(),
+            modules,
+        })
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool { self.modules[y * self.width + x] }
+
+    /// Render as a grayscale PNG with `scale` pixels per module, including the quiet zone
+    pub fn to_png(&self, scale: u32) -> Result<Vec<u8>, QrError> {
+        let scale = scale.max(1) as usize;
+        let size = (self.width + 2 * QUIET_ZONE) * scale;
+        let size_u32 = u32::try_from(size).map_err(|_| QrError::TooLarge(size))?;
+
+        let mut pixels = vec![u8::MAX; size * size];
+        for y in 0..self.width {
+            for x in (0..self.width).filter(|x| self.is_dark(*x, y)) {
+                for row in 0..scale {
+                    let start = ((y + QUIET_ZONE) * scale + row) * size + (x + QUIET_ZONE) * scale;
+                    pixels[start..start + scale].iter_mut().for_each(|p| *p = 0);
+                }
+            }
+        }
+
+        let mut png_bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png_bytes, size_u32, size_u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+        writer.finish()?;
+        Ok(png_bytes)
+    }
+}
+
+/// Types that can be shown as a QR code. Scanned payloads can be parsed back with `PaymentUri::from_str`.
+pub trait ToQr {
+    fn qr_payload(&self) -> String;
+
+    fn to_qr_matrix(&self) -> Result<QrMatrix, QrError> { QrMatrix::encode(&self.qr_payload()) }
+
+    fn to_qr_png(&self, scale: u32) -> Result<Vec<u8>, QrError> { self.to_qr_matrix()?.to_png(scale) }
+}
+
+impl ToQr for Address {
+    fn qr_payload(&self) -> String { self.to_string() }
+}
+
+impl ToQr for PaymentUri {
+    fn qr_payload(&self) -> String { self.to_string() }
+}
@@ -0,0 +1,446 @@
+use crate::http::endpoints::AddressBalanceResponse;
+use crate::types::{Address, Currency, Event, TransactionID};
+use async_trait::async_trait;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, ORIGIN};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+/// Maximum accepted request body size in bytes
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+const DEFAULT_HISTORY_LIMIT: u64 = 100;
+const MAX_HISTORY_LIMIT: u64 = 1000;
+/// Length in bytes of tokens made by `RpcServerConf::with_random_token`, hex encoded
+const RANDOM_TOKEN_LEN: usize = 32;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Application defined code for errors returned by the wallet
+const WALLET_ERROR: i64 = -32000;
+
+pub type RpcWalletError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Wallet operations exposed by the RPC server
+#[async_trait]
+pub trait RpcWallet: Send + Sync {
+    async fn balance(&self) -> Result<AddressBalanceResponse, RpcWalletError>;
+
+    async fn create_address(&self, label: Option<String>) -> Result<Address, RpcWalletError>;
+
+    /// Build, sign and broadcast a transaction sending `amount` hastings to `address`
+    async fn send(&self, address: Address, amount: Currency) -> Result<TransactionID, RpcWalletError>;
+
+    /// Wallet events, most recent first
+    async fn history(&self, offset: u64, limit: u64) -> Result<Vec<Event>, RpcWalletError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RpcServerError {
+    #[error("RpcServerError error: refusing to listen on non-loopback address {0}")]
+    NotLoopback(SocketAddr),
+    #[error("RpcServerError error: an auth token is required since the server can send funds")]
+    MissingAuthToken,
+    #[error("RpcServerError error: failed to generate auth token: {0}")]
+    Random(#[from] getrandom::Error),
+    #[error("RpcServerError error: {0}")]
+    Hyper(#[from] hyper::Error),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RpcServerConf {
+    /// Must be a loopback address
+    pub listen: SocketAddr,
+    /// Token required as `Authorization: Bearer <token>`. `serve` refuses to start without one, since any local
+    /// process could otherwise call `send`.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+impl RpcServerConf {
+    /// Listen on `listen` with a newly generated random token, see `auth_token`
+    pub fn with_random_token(listen: SocketAddr) -> Result<Self, RpcServerError> {
+        let mut token = [0u8; RANDOM_TOKEN_LEN];
+        getrandom::getrandom(&mut token)?;
+        Ok(RpcServerConf {
+            listen,
+            auth_token: Some(hex::encode(token)),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: JsonValue,
+    #[serde(default)]
+    pub id: JsonValue,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: JsonValue,
+}
+
+impl RpcResponse {
+    fn result(id: JsonValue, result: JsonValue) -> Self {
+        RpcResponse {
+            jsonrpc: "2.0",
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    fn error(id: JsonValue, code: i64, message: impl Into<String>) -> Self {
+        RpcResponse {
+            jsonrpc: "2.0",
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+            id,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct CreateAddressParams {
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SendParams {
+    address: Address,
+    amount: Currency,
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    #[serde(default)]
+    offset: u64,
+    #[serde(default = "default_history_limit")]
+    limit: u64,
+}
+
+fn default_history_limit() -> u64 { DEFAULT_HISTORY_LIMIT }
+
+/// Params may be omitted for methods without required params
+fn parse_params<T: serde::de::DeserializeOwned>(params: JsonValue) -> Result<T, RpcError> {
+    let params = match params {
+        JsonValue::Null => JsonValue::Object(Default::default()),
+        params => params,
+    };
+    serde_json::from_value(params).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+async fn call<W: RpcWallet + ?Sized>(wallet: &W, method: &str, params: JsonValue) -> Result<JsonValue, RpcError> {
+    let wallet_error = |e: RpcWalletError| RpcError {
+        code: WALLET_ERROR,
+        message: e.to_string(),
+    };
+    let result = match method {
+        "get_balance" => serde_json::to_value(wallet.balance().await.map_err(wallet_error)?),
+        "create_address" => {
+            let params: CreateAddressParams = parse_params(params)?;
+            let address = wallet.create_address(params.label).await.map_err(wallet_error)?;
+            serde_json::to_value(address)
+        },
+        "send" => {
+            let params: SendParams = parse_params(params)?;
+            let txid = wallet.send(params.address, params.amount).await.map_err(wallet_error)?;
+            serde_json::to_value(txid)
+        },
+        "history" => {
+            let params: HistoryParams = parse_params(params)?;
+            if params.limit > MAX_HISTORY_LIMIT {
+                return Err(RpcError {
+                    code: INVALID_PARAMS,
+                    message: format!("limit must be at most {}", MAX_HISTORY_LIMIT),
+                });
+            }
+            let events = wallet
+                .history(params.offset, params.limit)
+                .await
+                .map_err(wallet_error)?;
+            serde_json::to_value(events)
+        },
+        _ => {
+            return Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method {}", method),
+            })
+        },
+    };
+    result.map_err(|e| RpcError {
+        code: WALLET_ERROR,
+        message: e.to_string(),
+    })
+}
+
+/// Handle a single JSON-RPC 2.0 request body. Batches are not supported.
+pub async fn handle_rpc<W: RpcWallet + ?Sized>(wallet: &W, body: &[u8]) -> RpcResponse {
+    let request: RpcRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(e) => return RpcResponse::error(JsonValue::Null, PARSE_ERROR, e.to_string()),
+    };
+    if request.jsonrpc != "2.0" {
+        return RpcResponse::error(request.id, INVALID_REQUEST, "jsonrpc must be \"2.0\"");
+    }
+    match call(wallet, &request.method, request.params).await {
+        Ok(result) => RpcResponse::result(request.id, result),
+        Err(e) => RpcResponse::error(request.id, e.code, e.message),
+    }
+}
+
+fn plain_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle_http<W: RpcWallet + ?Sized>(wallet: &W, auth_token: &str, request: Request<Body>) -> Response<Body> {
+    // browsers always send an Origin header, rejecting it keeps web pages from driving the wallet
+    if request.headers().contains_key(ORIGIN) {
+        return plain_response(StatusCode::FORBIDDEN);
+    }
+    let expected = format!("Bearer {}", auth_token);
+    // compared in constant time so the token can not be guessed byte by byte from response times
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .map_or(false, |value| bool::from(value.as_bytes().ct_eq(expected.as_bytes())));
+    if !authorized {
+        return plain_response(StatusCode::UNAUTHORIZED);
+    }
+    if request.method() != Method::POST {
+        return plain_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    if hyper::body::HttpBody::size_hint(request.body()).lower() > MAX_BODY_SIZE as u64 {
+        return plain_response(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) if body.len() <= MAX_BODY_SIZE => body,
+        Ok(_) => return plain_response(StatusCode::PAYLOAD_TOO_LARGE),
+        Err(_) => return plain_response(StatusCode::BAD_REQUEST),
+    };
+
+    let rpc_response = handle_rpc(wallet, &body).await;
+    match serde_json::to_vec(&rpc_response) {
+        Ok(json) => {
+            let mut response = Response::new(Body::from(json));
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static("application/json"),
+            );
+            response
+        },
+        Err(_) => plain_response(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Serve the JSON-RPC facade of `wallet` on `conf.listen` until `shutdown` resolves.
+///
+/// All requests are `POST /` with a JSON-RPC 2.0 body. Methods are `get_balance`, `create_address`
+/// (`{"label"}`), `send` (`{"address", "amount"}`) and `history` (`{"offset", "limit"}`, at most 1000 events).
+/// Every request must carry `conf.auth_token`, see `RpcServerConf::with_random_token`.
+pub async fn serve<W, F>(wallet: Arc<W>, conf: RpcServerConf, shutdown: F) -> Result<(), RpcServerError>
+where
+    W: RpcWallet + 'static,
+    F: Future<Output = ()>,
+{
+    if !conf.listen.ip().is_loopback() {
+        return Err(RpcServerError::NotLoopback(conf.listen));
+    }
+    let auth_token: Arc<String> = match conf.auth_token {
+        Some(token) if !token.is_empty() => Arc::new(token),
+        _ => return Err(RpcServerError::MissingAuthToken),
+    };
+
+    let make_service = make_service_fn(move |_| {
+        let wallet = wallet.clone();
+        let auth_token = auth_token.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let wallet = wallet.clone();
+                let auth_token = auth_token.clone();
+                async move { Ok::<_, Infallible>(handle_http(&*wallet, &auth_token, request).await) }
+            }))
+        }
+    });
+
+    Server::try_bind(&conf.listen)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::H256;
+
+    struct TestWallet;
+
+    #[async_trait]
+    impl RpcWallet for TestWallet {
+        async fn balance(&self) -> Result<AddressBalanceResponse, RpcWalletError> {
+            Ok(AddressBalanceResponse {
+                siacoins: 10.into(),
+                immature_siacoins: 0.into(),
+                siafunds: 0,
+            })
+        }
+
+        async fn create_address(&self, _label: Option<String>) -> Result<Address, RpcWalletError> {
+            Ok(Address(H256::default()))
+        }
+
+        async fn send(&self, _address: Address, amount: Currency) -> Result<TransactionID, RpcWalletError> {
+            if amount.0 > 10 {
+                return Err("insufficient balance".into());
+            }
+            Ok(TransactionID::default())
+        }
+
+        async fn history(&self, _offset: u64, _limit: u64) -> Result<Vec<Event>, RpcWalletError> { Ok(vec![]) }
+    }
+
+    async fn rpc(body: JsonValue) -> JsonValue {
+        let response = handle_rpc(&TestWallet, body.to_string().as_bytes()).await;
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_balance() {
+        let response = rpc(json!({"jsonrpc": "2.0", "method": "get_balance", "id": 1})).await;
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": {"siacoins": "10", "immatureSiacoins": "0"}, "id": 1})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rpc_send() {
+        let address = Address(H256::default()).to_string();
+        let response = rpc(json!({
+            "jsonrpc": "2.0",
+            "method": "send",
+            "params": {"address": address, "amount": "11"},
+            "id": "a"
+        }))
+        .await;
+        assert_eq!(response["error"]["code"], WALLET_ERROR);
+        assert_eq!(response["error"]["message"], "insufficient balance");
+
+        let response = rpc(json!({"jsonrpc": "2.0", "method": "send", "params": {"address": address}, "id": 2})).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = rpc(json!({
+            "jsonrpc": "2.0",
+            "method": "send",
+            "params": {"address": address, "amount": "1"},
+            "id": 3
+        }))
+        .await;
+        assert_eq!(response["result"], json!(TransactionID::default()));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_invalid_requests() {
+        let response = handle_rpc(&TestWallet, b"{").await;
+        assert_eq!(response.error.unwrap().code, PARSE_ERROR);
+
+        let response = rpc(json!({"jsonrpc": "1.0", "method": "get_balance", "id": 1})).await;
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        let response = rpc(json!({"jsonrpc": "2.0", "method": "drain", "id": 1})).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_rejects_browsers_and_bad_tokens() {
+        let request = || Request::post("/").body(Body::from("{}")).unwrap();
+
+        let mut with_origin = request();
+        with_origin
+            .headers_mut()
+            .insert(ORIGIN, "https://evil.example".parse().unwrap());
+        let response = handle_http(&TestWallet, "token", with_origin).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = handle_http(&TestWallet, "token", request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut wrong_token = request();
+        wrong_token
+            .headers_mut()
+            .insert(AUTHORIZATION, "Bearer tokem".parse().unwrap());
+        let response = handle_http(&TestWallet, "token", wrong_token).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut authorized = request();
+        authorized
+            .headers_mut()
+            .insert(AUTHORIZATION, "Bearer token".parse().unwrap());
+        let response = handle_http(&TestWallet, "token", authorized).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_history_limit() {
+        let response = rpc(json!({"jsonrpc": "2.0", "method": "history", "params": {"limit": 1000}, "id": 1})).await;
+        assert_eq!(response["result"], json!([]));
+
+        let response = rpc(json!({"jsonrpc": "2.0", "method": "history", "params": {"limit": 1001}, "id": 2})).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_serve_requires_loopback() {
+        let conf = RpcServerConf::with_random_token("0.0.0.0:0".parse().unwrap()).unwrap();
+        let result = serve(Arc::new(TestWallet), conf, async {}).await;
+        assert!(matches!(result, Err(RpcServerError::NotLoopback(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_serve_requires_auth_token() {
+        let conf = RpcServerConf {
+            listen: "127.0.0.1:0".parse().unwrap(),
+            auth_token: None,
+        };
+        let result = serve(Arc::new(TestWallet), conf, async {}).await;
+        assert!(matches!(result, Err(RpcServerError::MissingAuthToken)));
+
+        let conf = RpcServerConf::with_random_token("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(conf.auth_token.unwrap().len(), 64);
+    }
+}
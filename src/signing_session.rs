@@ -0,0 +1,431 @@
+use crate::encoding::{PrefixedH256, PrefixedPublicKey, PrefixedSignature};
+use crate::spend_policy::{SpendPolicy, UnlockKey};
+use crate::transaction::{SatisfiedPolicy, V2Transaction};
+use crate::types::H256;
+use crate::{Keypair, PublicKey, Signature};
+use ed25519_dalek::Verifier;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, FromInto};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// Signers are identified by their hex encoded public key
+#[derive(Debug, Error, PartialEq)]
+pub enum SigningSessionError {
+    #[error("SigningSessionError error: signature is for sig hash {got}, expected {expected}")]
+    SigHashMismatch { expected: H256, got: H256 },
+    #[error("SigningSessionError error: {0} is not a signer of any input")]
+    UnknownSigner(String),
+    #[error("SigningSessionError error: invalid signature from {0}")]
+    InvalidSignature(String),
+    #[error("SigningSessionError error: keypair does not match requested signer {0}")]
+    WrongKeypair(String),
+    #[error("SigningSessionError error: input {0} is not satisfied by the collected signatures")]
+    Unsatisfied(usize),
+}
+
+/// A request for one signer to sign the transaction of a `SigningSession`.
+///
+/// Carries the full transaction so the signer can inspect what they are signing and recompute the sig hash
+/// instead of trusting the coordinator.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SigningRequest {
+    #[serde_as(as = "FromInto<PrefixedH256>")]
+    #[serde(rename = "sigHash")]
+    pub sig_hash: H256,
+    #[serde_as(as = "FromInto<PrefixedPublicKey>")]
+    pub signer: PublicKey,
+    pub transaction: V2Transaction,
+}
+
+impl SigningRequest {
+    /// Sign the transaction after checking that it hashes to `sig_hash`
+    pub fn sign(&self, keypair: &Keypair) -> Result<PartialSignature, SigningSessionError> {
+        if keypair.public() != self.signer {
+            return Err(SigningSessionError::WrongKeypair(self.signer.to_string()));
+        }
+        let sig_hash = self.transaction.input_sig_hash();
+        if sig_hash != self.sig_hash {
+            return Err(SigningSessionError::SigHashMismatch {
+                expected: sig_hash,
+                got: self.sig_hash,
+            });
+        }
+        Ok(PartialSignature {
+            sig_hash,
+            signer: self.signer,
+            signature: keypair.sign(&sig_hash.0),
+        })
+    }
+}
+
+/// A signer's response to a `SigningRequest`
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PartialSignature {
+    #[serde_as(as = "FromInto<PrefixedH256>")]
+    #[serde(rename = "sigHash")]
+    pub sig_hash: H256,
+    #[serde_as(as = "FromInto<PrefixedPublicKey>")]
+    pub signer: PublicKey,
+    #[serde_as(as = "FromInto<PrefixedSignature>")]
+    pub signature: Signature,
+}
+
+/// Signatures still needed for one input of a `SigningSession`, see `SigningSession::missing_signatures`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissingSignatures {
+    /// Index of the input, siacoin inputs first followed by siafund inputs
+    pub input: usize,
+    /// Fewest additional signatures that would satisfy the input, `None` if no signature can, eg. for opaque
+    /// policies or missing preimages
+    pub required: Option<usize>,
+    /// Keys of the policy that have not signed yet
+    pub signers: Vec<PublicKey>,
+}
+
+/// Collects the signatures of a multisig or threshold transaction from independent signers.
+///
+/// The coordinator creates the session from an unsigned transaction, hands each signer its `SigningRequest`
+/// over any channel, merges the returned `PartialSignature`s and finalizes once every input is satisfied.
+/// All types serialize to JSON, so a session can be persisted between rounds.
+/// ```ignore
+/// let mut session = SigningSession::new(unsigned_tx);
+/// for request in session.requests() {
+///     send_to_signer(serde_json::to_string(&request)?);
+/// }
+/// // on each signer: request.sign(&keypair)?
+/// session.add_signature(partial)?;
+/// if session.is_complete() {
+///     let tx = session.finalize()?;
+/// }
+/// ```
+///
+/// Without a coordinator, the session itself can be passed from signer to signer, each calling `sign`, or handed
+/// to all signers at once and the signed copies combined with `merge`.
+///
+/// A deserialized session is checked as if it was built anew: its sig hash must match the transaction and every
+/// signature is validated as by `add_signature`.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "RawSigningSession")]
+pub struct SigningSession {
+    transaction: V2Transaction,
+    #[serde_as(as = "FromInto<PrefixedH256>")]
+    #[serde(rename = "sigHash")]
+    sig_hash: H256,
+    signatures: Vec<PartialSignature>,
+}
+
+/// A `SigningSession` as serialized, before any of it is checked
+#[serde_as]
+#[derive(Deserialize)]
+struct RawSigningSession {
+    transaction: V2Transaction,
+    #[serde_as(as = "FromInto<PrefixedH256>")]
+    #[serde(rename = "sigHash")]
+    sig_hash: H256,
+    signatures: Vec<PartialSignature>,
+}
+
+impl TryFrom<RawSigningSession> for SigningSession {
+    type Error = SigningSessionError;
+
+    fn try_from(raw: RawSigningSession) -> Result<Self, Self::Error> {
+        let mut session = SigningSession::new(raw.transaction);
+        if raw.sig_hash != session.sig_hash {
+            return Err(SigningSessionError::SigHashMismatch {
+                expected: session.sig_hash,
+                got: raw.sig_hash,
+            });
+        }
+        for partial in raw.signatures {
+            session.add_signature(partial)?;
+        }
+        Ok(session)
+    }
+}
+
+/// A transaction together with the signatures collected for it so far, the name other wallets use for a
+/// `SigningSession`
+pub type PartiallySignedTransaction = SigningSession;
+
+impl SigningSession {
+    pub fn new(transaction: V2Transaction) -> Self {
+        SigningSession {
+            sig_hash: transaction.input_sig_hash(),
+            transaction,
+            signatures: Vec::new(),
+        }
+    }
+
+    pub fn sig_hash(&self) -> H256 { self.sig_hash }
+
+    pub fn transaction(&self) -> &V2Transaction { &self.transaction }
+
+    /// Every key that appears in the policy of an input, in order of first appearance
+    pub fn signers(&self) -> Vec<PublicKey> {
+        let mut signers = Vec::new();
+        for satisfied_policy in self.satisfied_policies() {
+            collect_keys(&satisfied_policy.policy, &mut signers);
+        }
+        signers
+    }
+
+    /// Signers that have not returned a signature yet
+    pub fn pending_signers(&self) -> Vec<PublicKey> {
+        self.signers()
+            .into_iter()
+            .filter(|signer| self.signature_of(signer).is_none())
+            .collect()
+    }
+
+    /// One request per pending signer
+    pub fn requests(&self) -> Vec<SigningRequest> {
+        self.pending_signers()
+            .into_iter()
+            .map(|signer| SigningRequest {
+                sig_hash: self.sig_hash,
+                signer,
+                transaction: self.transaction.clone(),
+            })
+            .collect()
+    }
+
+    fn signature_of(&self, signer: &PublicKey) -> Option<Signature> {
+        self.signatures
+            .iter()
+            .find(|partial| partial.signer == *signer)
+            .map(|partial| partial.signature)
+    }
+
+    fn satisfied_policies(&self) -> impl Iterator<Item = &SatisfiedPolicy> {
+        let siacoin = self.transaction.siacoin_inputs.iter().map(|si| &si.satisfied_policy);
+        let siafund = self.transaction.siafund_inputs.iter().map(|si| &si.satisfied_policy);
+        siacoin.chain(siafund)
+    }
+
+    /// Sign as `keypair` and add the signature, for a session passed directly between signers
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), SigningSessionError> {
+        self.add_signature(PartialSignature {
+            sig_hash: self.sig_hash,
+            signer: keypair.public(),
+            signature: keypair.sign(&self.sig_hash.0),
+        })
+    }
+
+    /// Add the signatures collected by `other`, a copy of this session signed independently.
+    ///
+    /// The signatures are validated as by `add_signature`, so a session received from an untrusted signer can be
+    /// merged safely.
+    pub fn merge(&mut self, other: SigningSession) -> Result<(), SigningSessionError> {
+        if other.sig_hash != self.sig_hash {
+            return Err(SigningSessionError::SigHashMismatch {
+                expected: self.sig_hash,
+                got: other.sig_hash,
+            });
+        }
+        for partial in other.signatures {
+            self.add_signature(partial)?;
+        }
+        Ok(())
+    }
+
+    /// The inputs not yet satisfied by the collected signatures, and what each still needs
+    pub fn missing_signatures(&self) -> Vec<MissingSignatures> {
+        self.satisfied_policies()
+            .enumerate()
+            .filter(|(_, satisfied_policy)| self.satisfy(satisfied_policy).is_none())
+            .map(|(input, satisfied_policy)| {
+                let mut signers = Vec::new();
+                collect_keys(&satisfied_policy.policy, &mut signers);
+                signers.retain(|signer| self.signature_of(signer).is_none());
+                MissingSignatures {
+                    input,
+                    required: self
+                        .required_signatures(&satisfied_policy.policy, !satisfied_policy.preimages.is_empty()),
+                    signers,
+                }
+            })
+            .collect()
+    }
+
+    /// Fewest signatures that would satisfy `policy` in addition to the collected ones
+    fn required_signatures(&self, policy: &SpendPolicy, has_preimages: bool) -> Option<usize> {
+        match policy {
+            SpendPolicy::Above(_) | SpendPolicy::After(_) => Some(0),
+            SpendPolicy::PublicKey(public_key) => Some(self.signature_of(public_key).map_or(1, |_| 0)),
+            SpendPolicy::Hash(_) => has_preimages.then_some(0),
+            SpendPolicy::Threshold { n, of } => {
+                let mut required: Vec<usize> = of
+                    .iter()
+                    .filter_map(|sub_policy| self.required_signatures(sub_policy, has_preimages))
+                    .collect();
+                if required.len() < *n as usize {
+                    return None;
+                }
+                required.sort_unstable();
+                Some(required.iter().take(*n as usize).sum())
+            },
+            SpendPolicy::Opaque(_) => None,
+            SpendPolicy::UnlockConditions(unlock_condition) => {
+                let mut signed = 0;
+                let mut unsigned = 0;
+                for unlock_key in &unlock_condition.unlock_keys {
+                    if let UnlockKey::Ed25519(public_key) = unlock_key {
+                        match self.signature_of(public_key) {
+                            Some(_) => signed += 1,
+                            None => unsigned += 1,
+                        }
+                    }
+                }
+                let required = (unlock_condition.signatures_required as usize).saturating_sub(signed);
+                (required <= unsigned).then_some(required)
+            },
+        }
+    }
+
+    /// Validate and merge a signature returned by a signer. Only the first valid signature of each signer is kept.
+    pub fn add_signature(&mut self, partial: PartialSignature) -> Result<(), SigningSessionError> {
+        if partial.sig_hash != self.sig_hash {
+            return Err(SigningSessionError::SigHashMismatch {
+                expected: self.sig_hash,
+                got: partial.sig_hash,
+            });
+        }
+        if !self.signers().contains(&partial.signer) {
+            return Err(SigningSessionError::UnknownSigner(partial.signer.to_string()));
+        }
+        if partial.signer.verify(&self.sig_hash.0, &partial.signature).is_err() {
+            return Err(SigningSessionError::InvalidSignature(partial.signer.to_string()));
+        }
+        if self.signature_of(&partial.signer).is_none() {
+            self.signatures.push(partial);
+        }
+        Ok(())
+    }
+
+    /// Whether the collected signatures satisfy every input
+    pub fn is_complete(&self) -> bool {
+        self.satisfied_policies()
+            .all(|satisfied_policy| self.satisfy(satisfied_policy).is_some())
+    }
+
+    /// Attach the collected signatures to the transaction.
+    ///
+    /// Signatures are ordered as the policy is evaluated and only as many as each threshold needs are included,
+    /// since consensus rejects superfluous signatures. Branches of a threshold that are not used are replaced by
+    /// their opaque address, see `SpendPolicy::opaque`, so consensus does not expect a signature from keys that did
+    /// not sign. This does not change the address of the policy.
+    pub fn finalize(self) -> Result<V2Transaction, SigningSessionError> {
+        let mut transaction = self.transaction.clone();
+        let satisfied_policies = transaction
+            .siacoin_inputs
+            .iter_mut()
+            .map(|si| &mut si.satisfied_policy)
+            .chain(transaction.siafund_inputs.iter_mut().map(|si| &mut si.satisfied_policy));
+        for (index, satisfied_policy) in satisfied_policies.enumerate() {
+            let (policy, signatures) = self
+                .satisfy(satisfied_policy)
+                .ok_or(SigningSessionError::Unsatisfied(index))?;
+            satisfied_policy.policy = policy;
+            satisfied_policy.signatures = signatures;
+        }
+        Ok(transaction)
+    }
+
+    /// The policy to reveal and the signatures satisfying `satisfied_policy` in evaluation order, `None` if it can
+    /// not be satisfied yet
+    fn satisfy(&self, satisfied_policy: &SatisfiedPolicy) -> Option<(SpendPolicy, Vec<Signature>)> {
+        let mut signatures = Vec::new();
+        let mut preimages = satisfied_policy.preimages.len();
+        self.satisfy_policy(&satisfied_policy.policy, &mut signatures, &mut preimages)
+            .map(|policy| (policy, signatures))
+    }
+
+    /// `policy` with the threshold branches it is not satisfied through made opaque
+    fn satisfy_policy(
+        &self,
+        policy: &SpendPolicy,
+        signatures: &mut Vec<Signature>,
+        preimages: &mut usize,
+    ) -> Option<SpendPolicy> {
+        match policy {
+            // height and time locks are checked by consensus when the transaction is broadcast
+            SpendPolicy::Above(_) | SpendPolicy::After(_) => Some(policy.clone()),
+            SpendPolicy::PublicKey(public_key) => {
+                signatures.push(self.signature_of(public_key)?);
+                Some(policy.clone())
+            },
+            // preimages are supplied by whoever built the transaction, assume they match in order
+            SpendPolicy::Hash(_) => {
+                *preimages = preimages.checked_sub(1)?;
+                Some(policy.clone())
+            },
+            SpendPolicy::Threshold { n, of } => {
+                let mut remaining = *n as usize;
+                let mut revealed = Vec::with_capacity(of.len());
+                for sub_policy in of {
+                    let (checkpoint, preimages_checkpoint) = (signatures.len(), *preimages);
+                    let satisfied = match remaining {
+                        0 => None,
+                        _ => self.satisfy_policy(sub_policy, signatures, preimages),
+                    };
+                    match satisfied {
+                        Some(sub_policy) => {
+                            remaining -= 1;
+                            revealed.push(sub_policy);
+                        },
+                        None => {
+                            signatures.truncate(checkpoint);
+                            *preimages = preimages_checkpoint;
+                            revealed.push(SpendPolicy::opaque(sub_policy));
+                        },
+                    }
+                }
+                (remaining == 0).then_some(SpendPolicy::Threshold { n: *n, of: revealed })
+            },
+            SpendPolicy::Opaque(_) => None,
+            SpendPolicy::UnlockConditions(unlock_condition) => {
+                let mut remaining = unlock_condition.signatures_required;
+                for unlock_key in &unlock_condition.unlock_keys {
+                    if remaining == 0 {
+                        break;
+                    }
+                    if let UnlockKey::Ed25519(public_key) = unlock_key {
+                        if let Some(signature) = self.signature_of(public_key) {
+                            signatures.push(signature);
+                            remaining -= 1;
+                        }
+                    }
+                }
+                (remaining == 0).then(|| policy.clone())
+            },
+        }
+    }
+}
+
+fn collect_keys(policy: &SpendPolicy, keys: &mut Vec<PublicKey>) {
+    let mut push = |key: &PublicKey| {
+        if !keys.contains(key) {
+            keys.push(*key);
+        }
+    };
+    match policy {
+        SpendPolicy::PublicKey(public_key) => push(public_key),
+        SpendPolicy::UnlockConditions(unlock_condition) => {
+            for unlock_key in &unlock_condition.unlock_keys {
+                if let UnlockKey::Ed25519(public_key) = unlock_key {
+                    push(public_key);
+                }
+            }
+        },
+        SpendPolicy::Threshold { of, .. } => {
+            for sub_policy in of {
+                collect_keys(sub_policy, keys);
+            }
+        },
+        SpendPolicy::Above(_) | SpendPolicy::After(_) | SpendPolicy::Hash(_) | SpendPolicy::Opaque(_) => (),
+    }
+}
@@ -0,0 +1,242 @@
+use crate::http::client::{ApiClient, ApiClientError};
+use crate::http::endpoints::{AddressEventsRequest, ConsensusIndexRequest, ConsensusTipRequest, GetAddressUtxosRequest};
+use crate::transaction::SiacoinElement;
+use crate::types::{Address, ChainIndex, Event, EventID};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// Identifies a serialized `WalletSnapshot`
+pub const SNAPSHOT_MAGIC: &str = "sia-rust-snapshot";
+
+/// Version of the snapshot layout, bumped for changes older readers can not handle
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// Blocks below the snapshot tip whose events are fetched again by `WalletSnapshot::rescan_recent`, so events of a
+/// shallow reorg are replaced
+pub const DEFAULT_REORG_DEPTH: u64 = 6;
+
+/// Number of events or outputs requested per page
+const PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("SnapshotError error: {0}")]
+    Client(#[from] ApiClientError),
+    #[error("SnapshotError error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("SnapshotError error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("SnapshotError error: not a wallet snapshot")]
+    InvalidMagic,
+    #[error("SnapshotError error: unsupported snapshot version {0}, expected at most {SNAPSHOT_VERSION}")]
+    UnsupportedVersion(u16),
+}
+
+/// Events and unspent outputs of a single address
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AddressSnapshot {
+    pub address: Address,
+    /// Newest first by block height, see `merge_events`
+    pub events: Vec<Event>,
+    pub utxos: Vec<SiacoinElement>,
+}
+
+/// The chain state relevant to a set of addresses, valid as of `tip`.
+///
+/// Exporting a snapshot and importing it on another machine followed by `rescan_recent` replaces a rescan of the
+/// addresses' full history with fetching their events since `tip`.
+/// ```ignore
+/// let snapshot = WalletSnapshot::export(&client, addresses).await?;
+/// snapshot.write_to(File::create("wallet.snapshot")?)?;
+///
+/// let mut snapshot = WalletSnapshot::read_from(File::open("wallet.snapshot")?)?;
+/// snapshot.rescan_recent(&client, DEFAULT_REORG_DEPTH).await?;
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WalletSnapshot {
+    pub magic: String,
+    pub version: u16,
+    /// Tip the snapshot was taken at. Events of later blocks may already be included.
+    pub tip: ChainIndex,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    pub addresses: Vec<AddressSnapshot>,
+}
+
+/// Outcome of `WalletSnapshot::rescan_recent`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecentRescan {
+    pub previous_tip: ChainIndex,
+    pub tip: ChainIndex,
+    /// Number of events that were not in the snapshot before
+    pub new_events: usize,
+    /// Whether `previous_tip` was no longer on the best chain, so the full history of every address was fetched again
+    pub full_rescan: bool,
+}
+
+impl WalletSnapshot {
+    pub fn new(tip: ChainIndex, addresses: Vec<AddressSnapshot>) -> Self {
+        WalletSnapshot {
+            magic: SNAPSHOT_MAGIC.to_owned(),
+            version: SNAPSHOT_VERSION,
+            tip,
+            created_at: Utc::now(),
+            addresses,
+        }
+    }
+
+    /// Fetch the events and unspent outputs of `addresses` from the node
+    pub async fn export<C: ApiClient + Sync>(client: &C, addresses: Vec<Address>) -> Result<Self, SnapshotError> {
+        // the tip is read first, anything that happens while paging is fetched again by `rescan_recent`
+        let tip = client.dispatcher(ConsensusTipRequest).await?;
+        let mut snapshots = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let events = fetch_events_since(client, &address, None).await?;
+            let utxos = fetch_utxos(client, &address).await?;
+            snapshots.push(AddressSnapshot { address, events, utxos });
+        }
+        Ok(WalletSnapshot::new(tip, snapshots))
+    }
+
+    /// Bring the snapshot up to the node's current tip by paging the recent events of each address again.
+    ///
+    /// Events of the last `reorg_depth` blocks before the snapshot tip are dropped and fetched again along with
+    /// everything newer, and the unspent outputs are replaced by the node's current set. Consensus updates are not
+    /// applied, so the cost grows with the number of events since the snapshot rather than the number of blocks.
+    ///
+    /// If the snapshot tip is no longer on the node's best chain, the reorg may be deeper than `reorg_depth`, so
+    /// every event is dropped and the full history of each address is fetched again instead.
+    pub async fn rescan_recent<C: ApiClient + Sync>(
+        &mut self,
+        client: &C,
+        reorg_depth: u64,
+    ) -> Result<RecentRescan, SnapshotError> {
+        let tip = client.dispatcher(ConsensusTipRequest).await?;
+        let full_rescan = !self.tip_on_best_chain(client, &tip).await?;
+        let cutoff = (!full_rescan).then(|| self.tip.height.saturating_sub(reorg_depth));
+        let mut new_events = 0;
+        for snapshot in &mut self.addresses {
+            let fetched = fetch_events_since(client, &snapshot.address, cutoff).await?;
+            new_events += merge_events(&mut snapshot.events, fetched, cutoff);
+            snapshot.utxos = fetch_utxos(client, &snapshot.address).await?;
+        }
+        let previous_tip = std::mem::replace(&mut self.tip, tip);
+        Ok(RecentRescan {
+            previous_tip,
+            tip,
+            new_events,
+            full_rescan,
+        })
+    }
+
+    /// Whether the snapshot tip is still part of the best chain, which ends in `tip`
+    async fn tip_on_best_chain<C: ApiClient + Sync>(
+        &self,
+        client: &C,
+        tip: &ChainIndex,
+    ) -> Result<bool, SnapshotError> {
+        if tip.height < self.tip.height {
+            return Ok(false);
+        }
+        let index = client
+            .dispatcher(ConsensusIndexRequest {
+                height: self.tip.height,
+            })
+            .await?;
+        Ok(index.id == self.tip.id)
+    }
+
+    /// Write the snapshot as gzip compressed JSON
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), SnapshotError> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Read a snapshot written by `write_to`
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        let snapshot: WalletSnapshot = serde_json::from_reader(GzDecoder::new(reader))?;
+        if snapshot.magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Page through the events of `address`, newest first, stopping at the first event that matured at or below
+/// `min_height`. walletd orders events by maturity height, so every later one is older still.
+async fn fetch_events_since<C: ApiClient + Sync>(
+    client: &C,
+    address: &Address,
+    min_height: Option<u64>,
+) -> Result<Vec<Event>, SnapshotError> {
+    let mut events = Vec::new();
+    loop {
+        let page = client
+            .dispatcher(AddressEventsRequest {
+                address: address.clone(),
+                limit: Some(PAGE_SIZE),
+                offset: Some(events.len() as i64),
+            })
+            .await?;
+        let page_len = page.len();
+        for event in page {
+            if min_height.map(|h| event.maturity_height <= h).unwrap_or(false) {
+                return Ok(events);
+            }
+            events.push(event);
+        }
+        if page_len < PAGE_SIZE as usize {
+            return Ok(events);
+        }
+    }
+}
+
+async fn fetch_utxos<C: ApiClient + Sync>(client: &C, address: &Address) -> Result<Vec<SiacoinElement>, SnapshotError> {
+    let mut utxos = Vec::new();
+    loop {
+        let page = client
+            .dispatcher(GetAddressUtxosRequest {
+                address: address.clone(),
+                limit: Some(PAGE_SIZE),
+                offset: Some(utxos.len() as i64),
+            })
+            .await?;
+        let page_len = page.len();
+        utxos.extend(page);
+        if page_len < PAGE_SIZE as usize {
+            return Ok(utxos);
+        }
+    }
+}
+
+/// Replace the events of blocks above `cutoff` with `fetched`, every event if `cutoff` is `None`, returning the
+/// number of events not known before.
+/// Events are kept newest first by the height of their block, ties ordered by ID so the order is deterministic.
+pub fn merge_events(events: &mut Vec<Event>, fetched: Vec<Event>, cutoff: Option<u64>) -> usize {
+    let previous: HashSet<EventID> = events.iter().map(|event| event.id).collect();
+    events.retain(|event| cutoff.map_or(false, |cutoff| event.index.height <= cutoff));
+    let mut kept: HashSet<EventID> = events.iter().map(|event| event.id).collect();
+    let mut new_events = 0;
+    for event in fetched {
+        if kept.insert(event.id) {
+            if !previous.contains(&event.id) {
+                new_events += 1;
+            }
+            events.push(event);
+        }
+    }
+    events.sort_by_key(|event| Reverse((event.index.height, event.id)));
+    new_events
+}
@@ -1,5 +1,7 @@
-use crate::encoding::{Encodable, Encoder};
+use crate::encoding::{Decodable, Decoder, DecodingError, Encodable, Encoder};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::str::FromStr;
 
@@ -26,13 +28,14 @@ define_byte_array_const!(FILE_CONTRACT, 16, "file contract");
 define_byte_array_const!(STORAGE_PROOF, 16, "storage proof");
 define_byte_array_const!(FOUNDATION, 16, "foundation");
 define_byte_array_const!(ENTROPY, 16, "entropy");
-// Sia Go technically supports arbitrary Specifiers
-// we will use "unknown" as a catch all in serde and encoding
-define_byte_array_const!(UNKNOWN, 16, "unknown");
 
 // https://github.com/SiaFoundation/core/blob/6c19657baf738c6b730625288e9b5413f77aa659/types/types.go#L40-L49
 /// A Specifier is a fixed-size, 0-padded identifier.
+///
+/// Sia Go supports arbitrary specifiers; those without a variant are kept as `Unknown` with their raw bytes so they
+/// are encoded back unchanged.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Specifier {
     Ed25519,
     SiacoinOutput,
@@ -41,15 +44,39 @@ pub enum Specifier {
     StorageProof,
     Foundation,
     Entropy,
-    Unknown,
+    Unknown([u8; 16]),
 }
 
 impl Encodable for Specifier {
     fn encode(&self, encoder: &mut Encoder) { encoder.write_slice(self.as_bytes()); }
 }
 
+impl Decodable for Specifier {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        let bytes: [u8; 16] = decoder.read_array()?;
+        Ok(Specifier::from_bytes(bytes))
+    }
+}
+
 impl Specifier {
-    pub fn as_bytes(&self) -> &'static [u8; 16] {
+    /// The variant with the given bytes, `Unknown` if there is none
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        [
+            Specifier::Ed25519,
+            Specifier::SiacoinOutput,
+            Specifier::SiafundOutput,
+            Specifier::FileContract,
+            Specifier::StorageProof,
+            Specifier::Foundation,
+            Specifier::Entropy,
+        ]
+        .iter()
+        .find(|specifier| *specifier.as_bytes() == bytes)
+        .cloned()
+        .unwrap_or(Specifier::Unknown(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
         match self {
             Specifier::Ed25519 => &ED25519,
             Specifier::SiacoinOutput => &SIACOIN_OUTPUT,
@@ -58,47 +85,49 @@ impl Specifier {
             Specifier::StorageProof => &STORAGE_PROOF,
             Specifier::Foundation => &FOUNDATION,
             Specifier::Entropy => &ENTROPY,
-            Specifier::Unknown => &UNKNOWN,
+            Specifier::Unknown(bytes) => bytes,
         }
     }
 
-    pub fn from_str_expect(s: &str) -> Self { Specifier::from_str(s).expect("from_str cannot return Err") }
-
-    pub fn to_str(&self) -> &'static str {
-        match self {
-            Specifier::Ed25519 => "ed25519",
-            Specifier::SiacoinOutput => "siacoin output",
-            Specifier::SiafundOutput => "siafund output",
-            Specifier::FileContract => "file contract",
-            Specifier::StorageProof => "storage proof",
-            Specifier::Foundation => "foundation",
-            Specifier::Entropy => "entropy",
-            Specifier::Unknown => "unknown",
-        }
+    /// The specifier without its 0-padding, as Go's `Specifier.String`
+    pub fn to_str(&self) -> Cow<'_, str> {
+        let bytes = self.as_bytes();
+        let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        String::from_utf8_lossy(&bytes[..len])
     }
 }
 
+/// The string is longer than the 16 bytes of a specifier
 #[derive(Debug)]
 pub struct ParseSpecifierError;
 
+impl Display for ParseSpecifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { write!(f, "specifier longer than 16 bytes") }
+}
+
 impl FromStr for Specifier {
     type Err = ParseSpecifierError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let r = match s {
-            "ed25519" => Specifier::Ed25519,
-            "siacoin output" => Specifier::SiacoinOutput,
-            "siafund output" => Specifier::SiafundOutput,
-            "file contract" => Specifier::FileContract,
-            "storage proof" => Specifier::StorageProof,
-            "foundation" => Specifier::Foundation,
-            "entropy" => Specifier::Entropy,
-            _ => Specifier::Unknown,
-        };
-        Ok(r)
+        if s.len() > 16 {
+            return Err(ParseSpecifierError);
+        }
+        let mut bytes = [0u8; 16];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(Specifier::from_bytes(bytes))
     }
 }
 
+impl TryFrom<String> for Specifier {
+    type Error = ParseSpecifierError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> { Specifier::from_str(&value) }
+}
+
+impl From<Specifier> for String {
+    fn from(value: Specifier) -> Self { value.to_string() }
+}
+
 impl Display for Specifier {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { write!(f, "{}", self.to_str()) }
 }
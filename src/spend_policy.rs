@@ -1,5 +1,5 @@
 use crate::blake2b_internal::{public_key_leaf, sigs_required_leaf, standard_unlock_hash, timelock_leaf, Accumulator};
-use crate::encoding::{Encodable, Encoder, PrefixedH256, PrefixedPublicKey};
+use crate::encoding::{Decodable, Decoder, DecodingError, Encodable, Encoder, PrefixedH256, PrefixedPublicKey};
 use crate::specifier::Specifier;
 use crate::transaction::{Preimage, SatisfiedPolicy};
 use crate::types::{Address, H256};
@@ -12,8 +12,11 @@ use nom::IResult;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
+use thiserror::Error;
 
 const POLICY_VERSION: u8 = 1u8;
+/// Deepest nesting of thresholds accepted when decoding a policy
+const MAX_POLICY_DEPTH: usize = 64;
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum SpendPolicy {
@@ -80,6 +83,18 @@ impl Encodable for SpendPolicy {
     }
 }
 
+impl Decodable for SpendPolicy {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        match decoder.read_u8()? {
+            POLICY_VERSION => SpendPolicy::decode_wo_prefix(decoder),
+            version => Err(DecodingError::InvalidValue(format!(
+                "unsupported spend policy version {}",
+                version
+            ))),
+        }
+    }
+}
+
 impl SpendPolicy {
     pub fn to_u8(&self) -> u8 {
         match self {
@@ -136,9 +151,54 @@ impl SpendPolicy {
         }
     }
 
+    pub fn decode_wo_prefix(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        SpendPolicy::decode_at_depth(decoder, 0)
+    }
+
+    /// Decode a policy nested in `depth` thresholds, failing past `MAX_POLICY_DEPTH` so untrusted input can not
+    /// exhaust the stack
+    fn decode_at_depth(decoder: &mut Decoder, depth: usize) -> Result<Self, DecodingError> {
+        if depth > MAX_POLICY_DEPTH {
+            return Err(DecodingError::InvalidValue(format!(
+                "spend policy nested deeper than {} levels",
+                MAX_POLICY_DEPTH
+            )));
+        }
+        let policy = match decoder.read_u8()? {
+            1 => SpendPolicy::Above(decoder.read_u64()?),
+            2 => SpendPolicy::After(decoder.read_u64()?),
+            3 => SpendPolicy::PublicKey(PublicKey::decode(decoder)?),
+            4 => SpendPolicy::Hash(H256::decode(decoder)?),
+            5 => {
+                let n = decoder.read_u8()?;
+                let len = decoder.read_u8()?;
+                let of = (0..len)
+                    .map(|_| SpendPolicy::decode_at_depth(decoder, depth + 1))
+                    .collect::<Result<_, _>>()?;
+                SpendPolicy::Threshold { n, of }
+            },
+            6 => SpendPolicy::Opaque(Address::decode(decoder)?),
+            7 => SpendPolicy::UnlockConditions(UnlockCondition::decode(decoder)?),
+            opcode => {
+                return Err(DecodingError::InvalidValue(format!(
+                    "unknown spend policy opcode {}",
+                    opcode
+                )))
+            },
+        };
+        Ok(policy)
+    }
+
+    /// The address committing to the policy, computed as `blake2b("sia/address|" | encoding)`.
+    ///
+    /// Unlock conditions keep their v1 unlock hash and an opaque policy is its address. Sub-policies of a threshold
+    /// are replaced by their addresses before hashing, so revealing only some branches of a threshold, see
+    /// `opacify`, does not change its address.
     pub fn address(&self) -> Address {
-        if let SpendPolicy::UnlockConditions(unlock_condition) = self {
-            return unlock_condition.address();
+        match self {
+            SpendPolicy::UnlockConditions(unlock_condition) => return unlock_condition.address(),
+            SpendPolicy::Opaque(address) => return address.clone(),
+            _ => (),
         }
         let mut encoder = Encoder::default();
         encoder.write_distinguisher("address");
@@ -172,48 +232,60 @@ impl SpendPolicy {
 
     pub fn opacify(&self) -> Self { SpendPolicy::Opaque(self.address()) }
 
-    pub fn satisfy<T: SatisfyPolicy>(&self, data: T) -> Result<SatisfiedPolicy, String> { data.satisfy(self) }
+    pub fn satisfy<T: SatisfyPolicy>(&self, data: T) -> Result<SatisfiedPolicy, SatisfyPolicyError> {
+        data.satisfy(self)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SatisfyPolicyError {
+    #[error("Failed to satisfy. Policy is not {expected}")]
+    UnexpectedPolicy { expected: &'static str },
 }
 
 pub trait SatisfyPolicy {
-    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, String>;
+    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, SatisfyPolicyError>;
 }
 
 impl SatisfyPolicy for Signature {
-    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, String> {
+    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, SatisfyPolicyError> {
         match policy {
             SpendPolicy::PublicKey(_) | SpendPolicy::UnlockConditions(_) => Ok(SatisfiedPolicy {
                 policy: policy.clone(),
                 signatures: vec![self],
                 preimages: vec![],
             }),
-            _ => Err("Failed to satisfy. Policy is not PublicKey or UnlockConditions".to_string()),
+            _ => Err(SatisfyPolicyError::UnexpectedPolicy {
+                expected: "PublicKey or UnlockConditions",
+            }),
         }
     }
 }
 
 impl SatisfyPolicy for Preimage {
-    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, String> {
+    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, SatisfyPolicyError> {
         match policy {
             SpendPolicy::Hash(_) => Ok(SatisfiedPolicy {
                 policy: policy.clone(),
                 signatures: vec![],
                 preimages: vec![self],
             }),
-            _ => Err("Failed to satisfy. Policy is not Hash".to_string()),
+            _ => Err(SatisfyPolicyError::UnexpectedPolicy { expected: "Hash" }),
         }
     }
 }
 
 impl SatisfyPolicy for () {
-    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, String> {
+    fn satisfy(self, policy: &SpendPolicy) -> Result<SatisfiedPolicy, SatisfyPolicyError> {
         match policy {
             SpendPolicy::Above(_) | SpendPolicy::After(_) | SpendPolicy::Opaque(_) => Ok(SatisfiedPolicy {
                 policy: policy.clone(),
                 signatures: vec![],
                 preimages: vec![],
             }),
-            _ => Err("Failed to satisfy. Policy is not Above, After or Opaque".to_string()),
+            _ => Err(SatisfyPolicyError::UnexpectedPolicy {
+                expected: "Above, After or Opaque",
+            }),
         }
     }
 }
@@ -306,8 +378,7 @@ impl Serialize for UnlockKey {
 }
 
 fn parse_specifier(input: &str) -> IResult<&str, Specifier> {
-    let (input, prefix_str) = take_until(":")(input)?;
-    let specifier = Specifier::from_str_expect(prefix_str);
+    let (input, specifier) = map_res(take_until(":"), Specifier::from_str)(input)?;
     let (input, _) = char(':')(input)?;
     Ok((input, specifier))
 }
@@ -367,6 +438,13 @@ impl Encodable for PublicKey {
     fn encode(&self, encoder: &mut Encoder) { encoder.write_slice(&self.to_bytes()); }
 }
 
+impl Decodable for PublicKey {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        let bytes: [u8; 32] = decoder.read_array()?;
+        PublicKey::from_bytes(&bytes).map_err(|e| DecodingError::InvalidValue(e.to_string()))
+    }
+}
+
 impl Encodable for UnlockKey {
     fn encode(&self, encoder: &mut Encoder) {
         match self {
@@ -383,6 +461,19 @@ impl Encodable for UnlockKey {
         }
     }
 }
+
+impl Decodable for UnlockKey {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        let algorithm = Specifier::decode(decoder)?;
+        let public_key = decoder.read_len_prefixed_bytes()?;
+        match algorithm {
+            Specifier::Ed25519 => PublicKey::from_bytes(&public_key)
+                .map(UnlockKey::Ed25519)
+                .map_err(|e| DecodingError::InvalidValue(e.to_string())),
+            algorithm => Ok(UnlockKey::NonStandard { algorithm, public_key }),
+        }
+    }
+}
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnlockCondition {
@@ -403,6 +494,16 @@ impl Encodable for UnlockCondition {
     }
 }
 
+impl Decodable for UnlockCondition {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(UnlockCondition {
+            timelock: decoder.read_u64()?,
+            unlock_keys: decoder.read_len_prefixed_vec()?,
+            signatures_required: decoder.read_u64()?,
+        })
+    }
+}
+
 impl UnlockCondition {
     pub fn new(pubkeys: Vec<PublicKey>, timelock: u64, signatures_required: u64) -> Self {
         let unlock_keys = pubkeys.into_iter().map(UnlockKey::Ed25519).collect();
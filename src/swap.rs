@@ -0,0 +1,133 @@
+//! Hash and time locked outputs for cross-chain atomic swaps
+use crate::spend_policy::{spend_policy_atomic_swap, spend_policy_atomic_swap_refund, spend_policy_atomic_swap_success,
+                          SpendPolicy};
+use crate::transaction::{Preimage, SatisfiedPolicy, SiacoinElement, V2TransactionBuilder};
+use crate::types::{Address, H256};
+use crate::{Keypair, PublicKey};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Keys are identified by their hex encoding
+#[derive(Debug, Error, PartialEq)]
+pub enum AtomicSwapError {
+    #[error("AtomicSwapError error: keypair {got} does not match the {path} key {expected}")]
+    WrongKeypair {
+        path: &'static str,
+        expected: String,
+        got: String,
+    },
+    #[error("AtomicSwapError error: preimage does not hash to the secret hash {0}")]
+    WrongPreimage(H256),
+}
+
+/// The terms of a swap output, spendable either by `success_key` revealing the preimage of `secret_hash`, or by
+/// `refund_key` once `lock_time` has passed.
+///
+/// Funding the swap is a regular payment to `address()`. Spending it takes two steps since the signature commits
+/// to the whole transaction:
+/// ```ignore
+/// let builder = swap
+///     .add_success_input(V2TransactionBuilder::new(), swap_output)
+///     .add_siacoin_output(SiacoinOutput { value, address: my_address })
+///     .miner_fee(fee);
+/// let tx = swap.sign_success(builder, &keypair, secret)?.build();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtomicSwap {
+    pub success_key: PublicKey,
+    pub refund_key: PublicKey,
+    /// Unix timestamp in seconds after which the refund path becomes valid
+    pub lock_time: u64,
+    /// SHA-256 hash of the secret revealed by the success path
+    pub secret_hash: H256,
+}
+
+impl AtomicSwap {
+    pub fn new(success_key: PublicKey, refund_key: PublicKey, lock_time: u64, secret_hash: H256) -> Self {
+        AtomicSwap {
+            success_key,
+            refund_key,
+            lock_time,
+            secret_hash,
+        }
+    }
+
+    /// The full policy, `1 of [2 of [success_key, secret_hash], 2 of [refund_key, after lock_time]]`
+    pub fn policy(&self) -> SpendPolicy {
+        spend_policy_atomic_swap(self.success_key, self.refund_key, self.lock_time, self.secret_hash)
+    }
+
+    /// The address the swap is funded to
+    pub fn address(&self) -> Address { self.policy().address() }
+
+    /// The policy revealing only the success path, with the refund path opaque
+    pub fn success_policy(&self) -> SpendPolicy {
+        spend_policy_atomic_swap_success(self.success_key, self.refund_key, self.lock_time, self.secret_hash)
+    }
+
+    /// The policy revealing only the refund path, with the success path opaque
+    pub fn refund_policy(&self) -> SpendPolicy {
+        spend_policy_atomic_swap_refund(self.success_key, self.refund_key, self.lock_time, self.secret_hash)
+    }
+
+    /// Whether the refund path is valid in the block after a tip with the given timestamp
+    pub fn is_refundable_at(&self, timestamp: u64) -> bool { timestamp > self.lock_time }
+
+    pub fn add_success_input(&self, builder: V2TransactionBuilder, parent: SiacoinElement) -> V2TransactionBuilder {
+        builder.add_siacoin_input(parent, self.success_policy())
+    }
+
+    pub fn add_refund_input(&self, builder: V2TransactionBuilder, parent: SiacoinElement) -> V2TransactionBuilder {
+        builder.add_siacoin_input(parent, self.refund_policy())
+    }
+
+    /// Satisfy every input spending the swap through the success path by signing with `keypair` and revealing
+    /// `preimage`, which must hash to `secret_hash`. Must be called after all inputs and outputs are added.
+    pub fn sign_success(
+        &self,
+        builder: V2TransactionBuilder,
+        keypair: &Keypair,
+        preimage: Preimage,
+    ) -> Result<V2TransactionBuilder, AtomicSwapError> {
+        check_keypair("success", &self.success_key, keypair)?;
+        if H256(Sha256::digest(&preimage).into()) != self.secret_hash {
+            return Err(AtomicSwapError::WrongPreimage(self.secret_hash));
+        }
+        let signature = keypair.sign(&builder.input_sig_hash().0);
+        let satisfied_policy = SatisfiedPolicy {
+            policy: self.success_policy(),
+            signatures: vec![signature],
+            preimages: vec![preimage],
+        };
+        Ok(builder.satisfy_siacoin_inputs(&self.address(), satisfied_policy))
+    }
+
+    /// Satisfy every input spending the swap through the refund path by signing with `keypair`. Must be called
+    /// after all inputs and outputs are added, and is only valid once `lock_time` has passed.
+    pub fn sign_refund(
+        &self,
+        builder: V2TransactionBuilder,
+        keypair: &Keypair,
+    ) -> Result<V2TransactionBuilder, AtomicSwapError> {
+        check_keypair("refund", &self.refund_key, keypair)?;
+        let signature = keypair.sign(&builder.input_sig_hash().0);
+        let satisfied_policy = SatisfiedPolicy {
+            policy: self.refund_policy(),
+            signatures: vec![signature],
+            preimages: Vec::new(),
+        };
+        Ok(builder.satisfy_siacoin_inputs(&self.address(), satisfied_policy))
+    }
+}
+
+fn check_keypair(path: &'static str, expected: &PublicKey, keypair: &Keypair) -> Result<(), AtomicSwapError> {
+    let got = keypair.public();
+    if got != *expected {
+        return Err(AtomicSwapError::WrongKeypair {
+            path,
+            expected: expected.to_string(),
+            got: got.to_string(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::backup::{BackupAddress, BackupError, HistoryCursor, KdfParams, WalletBackup, BACKUP_VERSION};
+use crate::keypair::Seed;
+use crate::types::Address;
+use std::str::FromStr;
+
+// cheap parameters to keep the tests fast
+const TEST_KDF: KdfParams = KdfParams {
+    memory_cost: 64,
+    time_cost: 1,
+    parallelism: 1,
+};
+
+fn wallet_backup() -> WalletBackup {
+    let address =
+        Address::from_str("addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f").unwrap();
+    let mut backup = WalletBackup {
+        seed: Some(Seed::from_bytes([7; 32])),
+        next_derivation_index: 1,
+        ..Default::default()
+    };
+    backup.addresses.push(BackupAddress {
+        address: address.clone(),
+        label: Some("savings".into()),
+        derivation_index: Some(0),
+    });
+    backup.history_cursors.insert(address, HistoryCursor {
+        height: 1000,
+        offset: 25,
+    });
+    backup
+}
+
+#[test]
+fn test_backup_round_trip() {
+    let backup = wallet_backup();
+    let encrypted = backup.encrypt_with_params(b"password", TEST_KDF).unwrap();
+    assert_eq!(encrypted.version, BACKUP_VERSION);
+
+    let artifact = serde_json::to_string(&encrypted).unwrap();
+    assert!(!artifact.contains("savings"));
+    assert_eq!(WalletBackup::restore(&artifact, b"password").unwrap(), backup);
+}
+
+#[test]
+fn test_backup_wrong_password() {
+    let encrypted = wallet_backup().encrypt_with_params(b"password", TEST_KDF).unwrap();
+    assert!(matches!(encrypted.decrypt(b"wrong"), Err(BackupError::Decryption)));
+}
+
+#[test]
+fn test_backup_header_authenticated() {
+    let mut encrypted = wallet_backup().encrypt_with_params(b"password", TEST_KDF).unwrap();
+    encrypted.kdf.time_cost = 2;
+    assert!(matches!(encrypted.decrypt(b"password"), Err(BackupError::Decryption)));
+}
+
+#[test]
+fn test_backup_kdf_params_capped() {
+    let mut encrypted = wallet_backup().encrypt_with_params(b"password", TEST_KDF).unwrap();
+    encrypted.kdf.memory_cost = u32::MAX;
+    assert!(matches!(
+        encrypted.decrypt(b"password"),
+        Err(BackupError::KdfParamsTooHigh(_))
+    ));
+
+    encrypted.kdf = TEST_KDF;
+    encrypted.kdf.time_cost = 1000;
+    assert!(matches!(
+        encrypted.decrypt(b"password"),
+        Err(BackupError::KdfParamsTooHigh(_))
+    ));
+}
+
+#[test]
+fn test_backup_unsupported_version() {
+    let mut encrypted = wallet_backup().encrypt_with_params(b"password", TEST_KDF).unwrap();
+    encrypted.version = BACKUP_VERSION + 1;
+    assert!(matches!(
+        encrypted.decrypt(b"password"),
+        Err(BackupError::UnsupportedVersion(v)) if v == BACKUP_VERSION + 1
+    ));
+
+    encrypted.magic = "something else".into();
+    assert!(matches!(encrypted.decrypt(b"password"), Err(BackupError::InvalidMagic)));
+}
+
+#[test]
+fn test_backup_forward_compatible() {
+    // a backup written by a later version with fields unknown to this one
+    let json = r#"{
+        "addresses": [],
+        "nextDerivationIndex": 3,
+        "hardwareDevice": {"model": "x"}
+    }"#;
+    let backup: WalletBackup = serde_json::from_str(json).unwrap();
+    assert_eq!(backup.next_derivation_index, 3);
+    assert!(backup.history_cursors.is_empty());
+    assert_eq!(backup.extra["hardwareDevice"], json!({"model": "x"}));
+
+    let encrypted = backup.encrypt_with_params(b"password", TEST_KDF).unwrap();
+    let restored = encrypted.decrypt(b"password").unwrap();
+    assert_eq!(restored.extra, backup.extra);
+}
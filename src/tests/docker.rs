@@ -0,0 +1,189 @@
+//! End-to-end tests against walletd running in docker, run with `cargo test --features docker-tests`
+//!
+//! Each test starts its own container from `$WALLETD_IMAGE` (default `ghcr.io/siafoundation/walletd:latest`) on
+//! `$WALLETD_NETWORK` (default `zen`). The node does not connect to peers and funds addresses by mining on its own
+//! chain through the debug miner. Spending needs v2 transactions, so the network must allow them at the heights
+//! mined here.
+use crate::http::client::native::{Conf, NativeClient};
+use crate::http::client::{sleep, ApiClient, ApiClientHelpers};
+use crate::http::endpoints::{DebugMineRequest, TxpoolTransactionsRequest};
+use crate::http::events::{subscribe_confirmed_transaction, ConfirmationEvent};
+use crate::spend_policy::SpendPolicy;
+use crate::transaction::{Currency, SiacoinOutput, Siacoins, V2TransactionBuilder};
+use crate::types::Address;
+use crate::utxo::SelectionStrategy;
+use crate::Keypair;
+use core::time::Duration;
+use futures::StreamExt;
+use instant::Instant;
+use std::net::{Ipv4Addr, TcpListener};
+use std::process::Command;
+use url::Url;
+
+const DEFAULT_IMAGE: &str = "ghcr.io/siafoundation/walletd:latest";
+const DEFAULT_NETWORK: &str = "zen";
+const API_PASSWORD: &str = "password";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Blocks until a miner payout can be spent
+const MATURITY_DELAY: u64 = 144;
+
+/// A walletd container, removed when dropped
+struct DockerNode {
+    container_id: String,
+    client: NativeClient,
+}
+
+impl DockerNode {
+    async fn start() -> Self {
+        let image = std::env::var("WALLETD_IMAGE").unwrap_or_else(|_| DEFAULT_IMAGE.to_owned());
+        let network = std::env::var("WALLETD_NETWORK").unwrap_or_else(|_| DEFAULT_NETWORK.to_owned());
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .expect("free localhost port")
+            .port();
+
+        let output = Command::new("docker")
+            .args(["run", "--detach", "--rm"])
+            .arg("--publish")
+            .arg(format!("127.0.0.1:{}:9980", port))
+            .arg("--env")
+            .arg(format!("WALLETD_API_PASSWORD={}", API_PASSWORD))
+            .arg(&image)
+            .args(["-network", network.as_str(), "-bootstrap=false", "-debug"])
+            .output()
+            .expect("docker is installed");
+        assert!(
+            output.status.success(),
+            "docker run failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let container_id = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+        let conf = Conf {
+            server_url: Url::parse(&format!("http://127.0.0.1:{}/", port)).expect("valid url"),
+            base_path: None,
+            fallback_urls: Vec::new(),
+            password: Some(API_PASSWORD.to_owned()),
+            timeout: Some(10),
+            deadline: None,
+            circuit_breaker: None,
+            throttle: None,
+            ip_family: Default::default(),
+            etag_cache: false,
+            response_cache: None,
+            slow_request_threshold: None,
+            retry: None,
+            pool: Default::default(),
+            user_agent: None,
+            headers: Default::default(),
+            proxy: None,
+            tls: Default::default(),
+            skip_health_check: false,
+        };
+        let started = Instant::now();
+        // `NativeClient::new` pings the node, so it only succeeds once the API is up
+        let client = loop {
+            match NativeClient::new(conf.clone()).await {
+                Ok(client) => break client,
+                Err(e) if started.elapsed() >= STARTUP_TIMEOUT => {
+                    remove_container(&container_id);
+                    panic!("walletd not ready after {:?}: {}", STARTUP_TIMEOUT, e);
+                },
+                Err(_) => sleep(POLL_INTERVAL).await,
+            }
+        };
+        DockerNode { container_id, client }
+    }
+
+    fn client(&self) -> &NativeClient { &self.client }
+
+    async fn mine(&self, blocks: u64, address: &Address) {
+        self.client
+            .dispatcher(DebugMineRequest {
+                blocks,
+                address: address.clone(),
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Mine until the payout of the first block mined to `address` is spendable
+    async fn fund(&self, address: &Address) { self.mine(MATURITY_DELAY + 1, address).await }
+}
+
+impl Drop for DockerNode {
+    fn drop(&mut self) { remove_container(&self.container_id) }
+}
+
+fn remove_container(container_id: &str) {
+    Command::new("docker")
+        .args(["rm", "--force", container_id])
+        .output()
+        .ok();
+}
+
+fn keypair(seed: u8) -> Keypair { Keypair::from_private_bytes(&[seed; 32]).unwrap() }
+
+#[tokio::test]
+async fn test_docker_balance_and_utxos() {
+    let node = DockerNode::start().await;
+    let address = keypair(1).address();
+    node.fund(&address).await;
+
+    let balance = node.client().address_balance(address.clone()).await.unwrap();
+    assert!(*balance.siacoins > 0);
+
+    let utxos = node.client().spendable_utxos(&address, 1).await.unwrap();
+    assert!(!utxos.is_empty());
+    assert!(utxos.iter().all(|utxo| utxo.siacoin_output.address == address));
+}
+
+#[tokio::test]
+async fn test_docker_send_and_confirm() {
+    let node = DockerNode::start().await;
+    let sender = keypair(1);
+    let recipient = keypair(2).address();
+    node.fund(&sender.address()).await;
+
+    let amount = Siacoins::new(1).unwrap().to_hastings();
+    let fee = Currency(10_000_000_000_000_000_000);
+    let selection = node
+        .client()
+        .select_utxos(&sender.address(), amount + fee, SelectionStrategy::LargestFirst, 1)
+        .await
+        .unwrap();
+    let builder = selection
+        .selected
+        .into_iter()
+        .fold(V2TransactionBuilder::new(), |builder, utxo| {
+            builder.add_siacoin_input(utxo, SpendPolicy::UnlockConditions(sender.unlock_condition()))
+        })
+        .add_siacoin_output(SiacoinOutput {
+            value: amount,
+            address: recipient.clone(),
+        })
+        .miner_fee(fee)
+        .add_change_output(sender.address())
+        .unwrap();
+    let tx = builder.sign_simple(vec![&sender]).unwrap().build();
+    let txid = tx.txid();
+
+    node.client().broadcast_transaction(&tx).await.unwrap();
+    let txpool = node.client().dispatcher(TxpoolTransactionsRequest).await.unwrap();
+    assert!(txpool.v2transactions.iter().any(|pooled| pooled.txid() == txid));
+
+    node.mine(1, &keypair(3).address()).await;
+    let mut confirmations = Box::pin(subscribe_confirmed_transaction(
+        node.client().clone(),
+        txid,
+        POLL_INTERVAL,
+    ));
+    match confirmations.next().await.unwrap().unwrap() {
+        ConfirmationEvent::Confirmed { confirmations, .. } => assert_eq!(confirmations, 1),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let balance = node.client().address_balance(recipient).await.unwrap();
+    assert_eq!(balance.siacoins, amount);
+}
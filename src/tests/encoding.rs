@@ -1,7 +1,8 @@
 use crate::blake2b_internal::standard_unlock_hash;
-use crate::encoding::Encoder;
+use crate::encoding::{Decodable, Decoder, DecodingError, Encodable, Encoder};
 use crate::spend_policy::{SpendPolicy, UnlockCondition};
-use crate::types::{Address, H256};
+use crate::transaction::SiacoinInputV2;
+use crate::types::{Address, BlockID, ChainIndex, H256};
 use crate::PublicKey;
 use std::str::FromStr;
 
@@ -180,3 +181,174 @@ fn test_unlock_condition_unlock_hash_standard() {
     let hash = standard_unlock_hash(&pubkey);
     assert_eq!(hash, expected);
 }
+
+/// Decode the encoding of `item` and check it matches and re-encodes to the same bytes
+fn assert_round_trip<T: Encodable + Decodable + PartialEq + std::fmt::Debug>(item: &T) {
+    let mut encoder = Encoder::default();
+    item.encode(&mut encoder);
+    let decoded: T = Decoder::decode_all(&encoder.buffer).unwrap();
+    assert_eq!(&decoded, item);
+
+    let mut reencoded = Encoder::default();
+    decoded.encode(&mut reencoded);
+    assert_eq!(reencoded.buffer, encoder.buffer);
+}
+
+#[test]
+fn test_spend_policy_decode() {
+    let pubkey = PublicKey::from_bytes(
+        &hex::decode("0102030000000000000000000000000000000000000000000000000000000000").unwrap(),
+    )
+    .unwrap();
+    let unlock_condition = UnlockCondition::new(vec![pubkey], 0, 1);
+    assert_round_trip(&pubkey);
+    assert_round_trip(&unlock_condition);
+
+    let policies = vec![
+        SpendPolicy::above(1),
+        SpendPolicy::after(1),
+        SpendPolicy::PublicKey(pubkey),
+        SpendPolicy::Hash(H256::from(
+            "0102030000000000000000000000000000000000000000000000000000000000",
+        )),
+        SpendPolicy::Opaque(SpendPolicy::above(1).address()),
+        SpendPolicy::UnlockConditions(unlock_condition.clone()),
+    ];
+    for policy in &policies {
+        assert_round_trip(policy);
+    }
+    let threshold = SpendPolicy::Threshold { n: 2, of: policies };
+    assert_round_trip(&threshold);
+
+    // known encoding from the Go implementation, see test_spend_policy_encode_threshold
+    let policy = SpendPolicy::Threshold {
+        n: 1,
+        of: vec![SpendPolicy::above(1), SpendPolicy::after(1)],
+    };
+    let mut encoder = Encoder::default();
+    policy.encode(&mut encoder);
+    let decoded: SpendPolicy = Decoder::decode_all(&encoder.buffer).unwrap();
+    assert_eq!(
+        Encoder::encode_and_hash(&decoded),
+        H256::from("7d792df6cd0b5e0f795287b3bf4087bbcc4c1bd0c52880a552cdda3e5e33d802")
+    );
+}
+
+#[test]
+fn test_spend_policy_decode_depth_limit() {
+    let nested = |depth: usize| {
+        (0..depth).fold(SpendPolicy::above(1), |policy, _| SpendPolicy::Threshold {
+            n: 1,
+            of: vec![policy],
+        })
+    };
+    assert_round_trip(&nested(64));
+
+    let mut encoder = Encoder::default();
+    nested(65).encode(&mut encoder);
+    assert!(Decoder::decode_all::<SpendPolicy>(&encoder.buffer).is_err());
+
+    // far deeper than the stack could handle if the depth was not limited
+    let mut buffer = vec![1u8];
+    for _ in 0..1_000_000 {
+        buffer.extend_from_slice(&[5, 1, 1]);
+    }
+    assert!(Decoder::decode_all::<SpendPolicy>(&buffer).is_err());
+}
+
+#[test]
+fn test_siacoin_input_v2_decode() {
+    let j = json!({
+        "parent": {
+            "id": "h:f59e395dc5cbe3217ee80eff60585ffc9802e7ca580d55297782d4a9b4e08589",
+            "leafIndex": 3,
+            "merkleProof": [
+                "h:ab0e1726444c50e2c0f7325eb65e5bd262a97aad2647d2816c39d97958d9588a",
+                "h:467e2be4d8482eca1f99440b6efd531ab556d10a8371a98a05b00cb284620cf0"
+            ],
+            "siacoinOutput": {
+                "value": "288594172736732570239334030000",
+                "address": "addr:2757c80b7ec2e493a138fed45b906f9f5735a992b68dcbd2069fbdf418c8b25158f3ac7a816b"
+            },
+            "maturityHeight": 0
+        },
+        "satisfiedPolicy": {
+            "policy": {
+                "type": "pk",
+                "policy": "ed25519:968e286ef5df3954b7189c53a0b4b3d827664357ebc85d590299b199af46abad"
+            },
+            "signatures": [
+                "sig:7a2c332fef3958a0486ef5e55b70d2a68514ff46d9307a85c3c0e40b76a19eebf4371ab3dd38a668cefe94dbedff2c50cc67856fbf42dce2194b380e536c1500"
+            ]
+        }
+    });
+    let input: SiacoinInputV2 = serde_json::from_value(j).unwrap();
+    assert_round_trip(&input);
+    assert_round_trip(&input.parent.state_element);
+    assert_round_trip(&input.parent);
+    assert_round_trip(&input.satisfied_policy);
+
+    let index = ChainIndex {
+        height: 3,
+        id: BlockID(input.parent.state_element.id),
+    };
+    assert_round_trip(&index);
+}
+
+#[test]
+fn test_decode_errors() {
+    let mut encoder = Encoder::default();
+    SpendPolicy::above(1).encode(&mut encoder);
+    let bytes = encoder.buffer;
+
+    assert_eq!(
+        Decoder::decode_all::<SpendPolicy>(&bytes[..bytes.len() - 1]),
+        Err(DecodingError::UnexpectedEnd {
+            needed: 8,
+            remaining: 7
+        })
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        Decoder::decode_all::<SpendPolicy>(&trailing),
+        Err(DecodingError::TrailingBytes(1))
+    );
+    let mut unknown = bytes;
+    unknown[1] = 8;
+    assert!(matches!(
+        Decoder::decode_all::<SpendPolicy>(&unknown),
+        Err(DecodingError::InvalidValue(_))
+    ));
+
+    // a length prefix beyond the input is rejected without allocating for it
+    let mut encoder = Encoder::default();
+    encoder.write_u64(u64::MAX);
+    assert!(matches!(
+        Decoder::new(&encoder.buffer).read_len_prefixed_vec::<H256>(),
+        Err(DecodingError::UnexpectedEnd { .. })
+    ));
+}
+
+#[test]
+fn test_specifier_unknown_round_trip() {
+    use crate::specifier::Specifier;
+    use crate::spend_policy::UnlockKey;
+
+    let mut bytes = [0u8; 16];
+    bytes[..5].copy_from_slice(b"sr25\x01");
+    let specifier = Decoder::decode_all::<Specifier>(&bytes).unwrap();
+    assert_eq!(specifier, Specifier::Unknown(bytes));
+    assert_round_trip(&specifier);
+
+    let specifier = Specifier::from_str("secp256k1").unwrap();
+    assert_eq!(specifier.to_string(), "secp256k1");
+    assert_eq!(&specifier.as_bytes()[..10], b"secp256k1\0");
+    assert_eq!(Specifier::from_str("ed25519").unwrap(), Specifier::Ed25519);
+    assert!(Specifier::from_str("longer than sixteen").is_err());
+
+    let unlock_key = UnlockKey::from_str("secp256k1:0102").unwrap();
+    assert_eq!(unlock_key.to_string(), "secp256k1:0102");
+    assert_round_trip(&unlock_key);
+    assert_eq!(serde_json::to_string(&specifier).unwrap(), "\"secp256k1\"");
+}
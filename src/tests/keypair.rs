@@ -0,0 +1,90 @@
+use crate::blake2b_internal::hash_blake2b_single;
+use crate::keypair::{Keypair, KeypairError, Seed, DICTIONARY_SIZE};
+use crate::types::v1_standard_address_from_pubkey;
+
+/// A stand-in for siad's dictionary with the same size and 3 character unique prefixes
+fn dictionary() -> Vec<String> {
+    (0..DICTIONARY_SIZE)
+        .map(|i| {
+            let letter = |n: usize| (b'a' + (n % 26) as u8) as char;
+            format!("{}{}{}word", letter(i / 676), letter(i / 26), letter(i))
+        })
+        .collect()
+}
+
+fn seed() -> Seed { Seed::from_hex("8bc7de1fd7f8fc8f3d4f35a5bd2d6b80c1d4c69b6c5aeab1a06d6e8e60c49e01").unwrap() }
+
+#[test]
+fn test_keypair_address() {
+    let keypair = Keypair::from_hex("0100000000000000000000000000000000000000000000000000000000000000").unwrap();
+    assert_eq!(keypair.address(), v1_standard_address_from_pubkey(&keypair.public()));
+    assert_eq!(keypair.unlock_condition().address(), keypair.address());
+
+    assert!(matches!(Keypair::from_hex("0100"), Err(KeypairError::InvalidLength(2))));
+    assert!(matches!(Keypair::from_hex("zz"), Err(KeypairError::InvalidHex(_))));
+}
+
+#[test]
+fn test_seed_keypair_derivation() {
+    let seed = seed();
+    let mut preimage = seed.as_bytes().to_vec();
+    preimage.extend_from_slice(&1u64.to_le_bytes());
+    let secret = hash_blake2b_single(&preimage).0;
+
+    assert_eq!(
+        seed.keypair(1).public(),
+        Keypair::from_private_bytes(&secret).unwrap().public()
+    );
+    assert_eq!(seed.keypair(0).public(), seed.keypair(0).public());
+    assert_ne!(seed.keypair(0).public(), seed.keypair(1).public());
+}
+
+#[test]
+fn test_seed_phrase_round_trip() {
+    let dictionary = dictionary();
+    let dictionary: Vec<&str> = dictionary.iter().map(String::as_str).collect();
+
+    for seed in [seed(), Seed::from_bytes([0; 32]), Seed::from_bytes([0xff; 32])].iter() {
+        let phrase = seed.to_phrase(&dictionary).unwrap();
+        let words = phrase.split(' ').count();
+        assert!(words == 28 || words == 29, "{} words", words);
+        assert_eq!(&Seed::from_phrase(&phrase, &dictionary).unwrap(), seed);
+
+        // only the prefix of each word is significant
+        let abbreviated: Vec<&str> = phrase.split(' ').map(|word| &word[..3]).collect();
+        assert_eq!(&Seed::from_phrase(&abbreviated.join(" "), &dictionary).unwrap(), seed);
+    }
+}
+
+#[test]
+fn test_seed_phrase_invalid() {
+    let dictionary = dictionary();
+    let dictionary: Vec<&str> = dictionary.iter().map(String::as_str).collect();
+    let phrase = seed().to_phrase(&dictionary).unwrap();
+
+    let mut words: Vec<&str> = phrase.split(' ').collect();
+    words[0] = if words[0] == dictionary[0] {
+        dictionary[1]
+    } else {
+        dictionary[0]
+    };
+    assert!(matches!(
+        Seed::from_phrase(&words.join(" "), &dictionary),
+        Err(KeypairError::InvalidChecksum)
+    ));
+
+    words.pop();
+    assert!(matches!(
+        Seed::from_phrase(&words.join(" "), &dictionary),
+        Err(KeypairError::InvalidPhraseLength(_))
+    ));
+
+    assert!(matches!(
+        Seed::from_phrase("abcword 123", &dictionary),
+        Err(KeypairError::UnknownWord(word)) if word == "123"
+    ));
+    assert!(matches!(
+        Seed::from_phrase(&phrase, &dictionary[1..]),
+        Err(KeypairError::InvalidDictionary(1625))
+    ));
+}
@@ -0,0 +1,68 @@
+//! Integration tests against a walletd spawned by `LocalNode`, run with `cargo test --features local-node`. Platforms
+//! without a pinned release archive need `$WALLETD_BIN` or the SHA-256 of the archive in `$WALLETD_SHA256`.
+use crate::http::client::{ApiClient, ApiClientHelpers};
+use crate::http::endpoints::{ConsensusTipRequest, TxpoolTransactionsRequest};
+use crate::local_node::{pinned_sha256, release_asset, verify_sha256, LocalNode, LocalNodeConf, LocalNodeError};
+use crate::types::Address;
+use std::str::FromStr;
+
+async fn spawn_node() -> LocalNode { LocalNode::spawn(LocalNodeConf::default()).await.unwrap() }
+
+#[test]
+fn test_release_asset() {
+    let asset = release_asset().unwrap();
+    assert!(asset.starts_with("walletd_") && asset.ends_with(".zip"));
+}
+
+#[test]
+fn test_verify_sha256() {
+    let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    verify_sha256("abc.zip", b"abc", sha256).unwrap();
+    verify_sha256("abc.zip", b"abc", &sha256.to_uppercase()).unwrap();
+    assert!(matches!(
+        verify_sha256("abc.zip", b"abd", sha256),
+        Err(LocalNodeError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_pinned_sha256() {
+    let asset = release_asset().unwrap();
+    assert_eq!(pinned_sha256("v0.0.0", &asset), None);
+    if let Some(sha256) = pinned_sha256(crate::local_node::WALLETD_VERSION, &asset) {
+        assert_eq!(hex::decode(sha256).unwrap().len(), 32);
+    }
+}
+
+#[tokio::test]
+async fn test_local_node_requires_checksum() {
+    let conf = LocalNodeConf {
+        version: "v0.0.0".to_owned(),
+        bin: None,
+        sha256: None,
+        ..Default::default()
+    };
+    assert!(matches!(
+        LocalNode::spawn(conf).await,
+        Err(LocalNodeError::ChecksumRequired(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_local_node_consensus_tip() {
+    let node = spawn_node().await;
+    let tip = node.client().dispatcher(ConsensusTipRequest).await.unwrap();
+    assert_eq!(node.client().current_height().await.unwrap(), tip.height);
+}
+
+#[tokio::test]
+async fn test_local_node_empty_txpool_and_balance() {
+    let node = spawn_node().await;
+    let txpool = node.client().dispatcher(TxpoolTransactionsRequest).await.unwrap();
+    assert!(txpool.v2transactions.is_empty());
+
+    let address =
+        Address::from_str("addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f").unwrap();
+    let balance = node.client().address_balance(address).await.unwrap();
+    assert_eq!(*balance.siacoins, 0);
+}
@@ -1,4 +1,13 @@
+#[cfg(feature = "backup")] mod backup;
+#[cfg(feature = "docker-tests")] mod docker;
 mod encoding;
+mod keypair;
+#[cfg(feature = "local-node")] mod local_node;
+mod payment_uri;
 mod serde;
+#[cfg(feature = "wallet")] mod signing_session;
+#[cfg(feature = "snapshot")] mod snapshot;
 mod spend_policy;
+mod swap;
 mod transaction;
+#[cfg(feature = "wallet")] mod utxo;
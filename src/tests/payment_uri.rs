@@ -0,0 +1,84 @@
+use crate::payment_uri::{PaymentUri, PaymentUriError};
+use crate::types::Address;
+use std::str::FromStr;
+
+const ADDRESS: &str = "addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f";
+
+#[test]
+fn test_payment_uri_display() {
+    let address = Address::from_str(ADDRESS).unwrap();
+    let uri = PaymentUri::new(address.clone());
+    assert_eq!(uri.to_string(), format!("sia:{}", address.str_without_prefix()));
+
+    let uri = uri.amount(1_000_000.into()).label("Coffee & cake").message("order #12");
+    assert_eq!(
+        uri.to_string(),
+        format!(
+            "sia:{}?amount=1000000&label=Coffee+%26+cake&message=order+%2312",
+            address.str_without_prefix()
+        )
+    );
+}
+
+#[test]
+fn test_payment_uri_round_trip() {
+    let address = Address::from_str(ADDRESS).unwrap();
+    let uri = PaymentUri::new(address)
+        .amount(u128::MAX.into())
+        .label("ünïcode label")
+        .message("a=b&c");
+    assert_eq!(PaymentUri::from_str(&uri.to_string()).unwrap(), uri);
+}
+
+#[test]
+fn test_payment_uri_from_scanned_address() {
+    let expected = PaymentUri::new(Address::from_str(ADDRESS).unwrap());
+    let without_prefix = &ADDRESS["addr:".len()..];
+
+    assert_eq!(PaymentUri::from_str(ADDRESS).unwrap(), expected);
+    assert_eq!(PaymentUri::from_str(without_prefix).unwrap(), expected);
+    assert_eq!(
+        PaymentUri::from_str(&format!(" SIA:{}\n", without_prefix)).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_payment_uri_from_str_errors() {
+    let without_prefix = &ADDRESS["addr:".len()..];
+
+    let err = PaymentUri::from_str(&format!("sia:{}?amount=1.5", without_prefix)).unwrap_err();
+    assert!(matches!(err, PaymentUriError::InvalidAmount(_)));
+
+    let err = PaymentUri::from_str(&format!("sia:{}?amount=1&amount=2", without_prefix)).unwrap_err();
+    assert!(matches!(err, PaymentUriError::DuplicateParameter(_)));
+
+    let err = PaymentUri::from_str("sia:deadbeef").unwrap_err();
+    assert!(matches!(err, PaymentUriError::InvalidAddress(_)));
+
+    // unknown parameters are ignored
+    assert!(PaymentUri::from_str(&format!("sia:{}?foo=bar", without_prefix)).is_ok());
+}
+
+#[cfg(feature = "qr")]
+#[test]
+fn test_payment_uri_qr() {
+    use crate::qr::{QrMatrix, ToQr};
+
+    let uri = PaymentUri::new(Address::from_str(ADDRESS).unwrap()).amount(1.into());
+    let matrix = uri.to_qr_matrix().unwrap();
+    assert_eq!(matrix, QrMatrix::encode(&uri.to_string()).unwrap());
+    assert_eq!(matrix.modules.len(), matrix.width * matrix.width);
+    // finder pattern corners are dark
+    assert!(matrix.is_dark(0, 0));
+    assert!(matrix.is_dark(matrix.width - 1, 0));
+    assert!(matrix.is_dark(0, matrix.width - 1));
+
+    let png = uri.to_qr_png(2).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let decoder = png::Decoder::new(png.as_slice());
+    let reader = decoder.read_info().unwrap();
+    let expected_size = ((matrix.width + 8) * 2) as u32;
+    assert_eq!(reader.info().width, expected_size);
+    assert_eq!(reader.info().height, expected_size);
+}
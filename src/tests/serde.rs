@@ -1,7 +1,10 @@
 use crate::encoding::PrefixedH256;
 use crate::spend_policy::UnlockKey;
-use crate::transaction::{SiacoinElement, SiacoinOutput, StateElement, V2Transaction};
-use crate::types::{Address, BlockID, Event};
+use crate::transaction::{Currency, SiacoinElement, SiacoinOutput, StateElement, V2Transaction};
+use crate::types::{Address, BlockID, ChainIndex, ConsensusState, Event, EventID, FileContractID, ParseAddressError,
+                   ParseBlockIDError, ParseChainIndexError, ParseHashIDError, TransactionID, H256};
+use std::convert::TryFrom;
+use std::str::FromStr;
 
 // Ensure the original value matches the value after round-trip (serialize -> deserialize -> serialize)
 macro_rules! test_serde {
@@ -36,6 +39,51 @@ fn test_serde_address() {
     );
 }
 
+#[test]
+fn test_address_from_str() {
+    let hash = "591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a884";
+    let s = format!("addr:{}0be0653e411f", hash);
+    let address = Address::from_str(&s).unwrap();
+    assert!(Address::is_valid_checksum(&s));
+    let bytes = hex::decode(hash).unwrap();
+    assert_eq!(Address::try_from(bytes.as_slice()).unwrap(), address);
+    assert_eq!(
+        Address::try_from(&bytes[1..]),
+        Err(ParseAddressError::InvalidLength {
+            expected: 32,
+            found: 31
+        })
+    );
+
+    assert_eq!(
+        Address::from_str(&format!("{}0be0653e411f", hash)),
+        Err(ParseAddressError::MissingPrefix)
+    );
+    assert_eq!(
+        Address::from_str(&format!("h:{}0be0653e411f", hash)),
+        Err(ParseAddressError::WrongPrefix("h".to_owned()))
+    );
+    assert_eq!(
+        Address::from_str(&format!("addr:{}", hash)),
+        Err(ParseAddressError::InvalidLength {
+            expected: 76,
+            found: 64
+        })
+    );
+    assert!(matches!(
+        Address::from_str(&format!("addr:{}0be0653e411g", hash)),
+        Err(ParseAddressError::InvalidHexEncoding(_))
+    ));
+    assert_eq!(
+        Address::from_str(&format!("addr:{}0be0653e4120", hash)),
+        Err(ParseAddressError::InvalidChecksum)
+    );
+    assert!(!Address::is_valid_checksum(&format!("addr:{}0be0653e4120", hash)));
+
+    let err = serde_json::from_value::<Address>(json!(format!("addr:{}0be0653e4120", hash))).unwrap_err();
+    assert!(err.to_string().contains("invalid checksum"));
+}
+
 #[test]
 fn test_serde_unlock_key() {
     test_serde!(
@@ -446,6 +494,62 @@ fn test_serde_event_v2_contract_resolution_finalization() {
     // FIXME this should deserialize from a JSON object generated from walletd and recalcuate the txid to check encoding/serde
 }
 
+#[test]
+fn test_serde_event_v1_contract_resolution() {
+    let j = json!(
+      {
+        "id": "h:2c1bc6aa1d2a2b3e1a5c1bba56f1bd7bcb2cb1e4a0eaf7d8f5ad0bb8b37e2a01",
+        "index": {
+          "height": 190,
+          "id": "bid:730f554f8cd5e6bd855b21b8c53f59808f3aa7351093f44da7761181283e3c6b"
+        },
+        "timestamp": "2024-07-18T19:04:16Z",
+        "maturityHeight": 334,
+        "type": "v1ContractResolution",
+        "data": {
+          "parent": {
+            "id": "h:34f6bb9b9ed58dedebce2f39d29a526ea3012e9ae005cfca6a5257761c5412f6",
+            "leafIndex": 351,
+            "merkleProof": null,
+            "fileContract": {
+              "filesize": 0,
+              "fileMerkleRoot": "h:0000000000000000000000000000000000000000000000000000000000000000",
+              "windowStart": 179,
+              "windowEnd": 189,
+              "payout": "10400000000000000000000000000",
+              "validProofOutputs": [
+                {
+                  "value": "10000000000000000000000000000",
+                  "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+                }
+              ],
+              "missedProofOutputs": [
+                {
+                  "value": "10000000000000000000000000000",
+                  "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+                }
+              ],
+              "unlockHash": "addr:000000000000000000000000000000000000000000000000000000000000000089eb0d6a8a69",
+              "revisionNumber": 0
+            }
+          },
+          "siacoinElement": {
+            "id": "h:4c0170b9e82eacc2d14a13b974ce0c03560358276f135403bd060b53ce53be1c",
+            "leafIndex": 391,
+            "merkleProof": null,
+            "siacoinOutput": {
+              "value": "10000000000000000000000000000",
+              "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+            },
+            "maturityHeight": 334
+          },
+          "missed": true
+        }
+      }
+    );
+    test_serde!(Event, j);
+}
+
 #[test]
 fn test_serde_event_v2_transaction() {
     let j = json!(
@@ -570,3 +674,138 @@ fn test_v2_transaction_serde_basic_send() {
     let tx2 = serde_json::from_str::<V2Transaction>(&j2).unwrap();
     assert_eq!(tx, tx2);
 }
+
+#[test]
+fn test_serde_currency_boundaries() {
+    test_serde!(Currency, json!("0"));
+    test_serde!(Currency, json!("340282366920938463463374607431768211455"));
+    assert!(serde_json::from_str::<Currency>(r#""340282366920938463463374607431768211456""#).is_err());
+    assert!(serde_json::from_str::<Currency>(r#""-1""#).is_err());
+
+    // integers are accepted while they are exact
+    let currency: Currency = serde_json::from_str("18446744073709551615").unwrap();
+    assert_eq!(currency, Currency(u64::MAX as u128));
+    // larger JSON numbers are parsed as floats and must not be rounded into a balance
+    assert!(serde_json::from_str::<Currency>("18446744073709551616").is_err());
+    assert!(serde_json::from_str::<Currency>("1.5").is_err());
+    assert!(serde_json::from_str::<Currency>("-1").is_err());
+
+    // the same holds when deserializing from an intermediate `serde_json::Value`
+    let currency: Currency = serde_json::from_value(json!("340282366920938463463374607431768211455")).unwrap();
+    assert_eq!(currency, Currency(u128::MAX));
+    assert!(serde_json::from_value::<Currency>(json!(1e30)).is_err());
+}
+
+#[test]
+fn test_serde_height_boundaries() {
+    let json = json!({
+        "height": u64::MAX,
+        "id": "bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"
+    });
+    test_serde!(ChainIndex, json);
+    let index: ChainIndex = serde_json::from_value(json).unwrap();
+    assert_eq!(index.height, u64::MAX);
+
+    let overflow = r#"{"height":18446744073709551616,"id":"bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"}"#;
+    assert!(serde_json::from_str::<ChainIndex>(overflow).is_err());
+}
+
+#[test]
+fn test_block_id_from_str() {
+    let id = BlockID::from_str("bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667").unwrap();
+    assert_eq!(
+        id.to_string(),
+        "bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"
+    );
+
+    assert_eq!(
+        BlockID::from_str("c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"),
+        Err(ParseBlockIDError::MissingPrefix)
+    );
+    assert!(matches!(
+        BlockID::from_str("bid:c67c3b2e"),
+        Err(ParseBlockIDError::InvalidHexEncoding(_))
+    ));
+}
+
+#[test]
+fn test_serde_prefixed_ids() {
+    let hex = "c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667";
+    test_serde!(TransactionID, json!(format!("txid:{}", hex)));
+    test_serde!(EventID, json!(format!("h:{}", hex)));
+    test_serde!(FileContractID, json!(format!("fcid:{}", hex)));
+
+    let txid = TransactionID::from_str(&format!("txid:{}", hex)).unwrap();
+    assert_eq!(H256::from(txid), H256::from_str(hex).unwrap());
+    assert_eq!(EventID::from(txid).to_string(), format!("h:{}", hex));
+
+    // IDs of another kind are rejected rather than silently reinterpreted
+    assert_eq!(
+        TransactionID::from_str(&format!("fcid:{}", hex)),
+        Err(ParseHashIDError::MissingPrefix("txid"))
+    );
+    assert!(serde_json::from_value::<EventID>(json!(hex)).is_err());
+    assert!(matches!(
+        FileContractID::from_str("fcid:c67c3b2e"),
+        Err(ParseHashIDError::InvalidHexEncoding(_))
+    ));
+}
+
+#[test]
+fn test_chain_index_display_from_str() {
+    // Go formats a ChainIndex as "%d::%x", without the bid: prefix
+    let s = "42::c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667";
+    let index = ChainIndex::from_str(s).unwrap();
+    assert_eq!(index.height, 42);
+    assert_eq!(
+        index.id,
+        BlockID::from_str("bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667").unwrap()
+    );
+    assert_eq!(index.to_string(), s);
+
+    assert_eq!(ChainIndex::from_str("42"), Err(ParseChainIndexError::MissingSeparator));
+    assert!(matches!(
+        ChainIndex::from_str("-1::c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"),
+        Err(ParseChainIndexError::InvalidHeight(_))
+    ));
+    assert!(matches!(
+        ChainIndex::from_str("42::bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"),
+        Err(ParseChainIndexError::InvalidBlockID(
+            ParseBlockIDError::InvalidHexEncoding(_)
+        ))
+    ));
+}
+
+#[test]
+fn test_serde_consensus_state() {
+    let json = json!({
+        "index": {
+            "height": 1000,
+            "id": "bid:c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667"
+        },
+        "prevTimestamps": ["2024-06-01T12:00:00Z", "2024-06-01T11:50:00Z"],
+        "depth": "bid:00000000000000ffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "childTarget": "bid:0000000000000040000000000000000000000000000000000000000000000000",
+        "siafundPool": "0",
+        "oakTime": 600000000000u64,
+        "oakTarget": "bid:0000000000000040000000000000000000000000000000000000000000000000",
+        "foundationPrimaryAddress": "addr:591fcf237f8854b5653d1ac84ae4c107b37f148c3c7b413f292d48db0c25a8840be0653e411f",
+        "foundationFailsafeAddress": "addr:c899f7795bb20c94e57c764f06699e09e6ad071ad95539eef4fb505e79ab22e8be4d64067ccc",
+        "totalWork": "4294967296000",
+        "difficulty": "4294967296",
+        "oakWork": "4294967296",
+        "elements": {
+            "numLeaves": 3,
+            "trees": [
+                "h:0000000000000000000000000000000000000000000000000000000000000001",
+                "h:0000000000000000000000000000000000000000000000000000000000000002"
+            ]
+        },
+        "attestations": 0
+    });
+    test_serde!(ConsensusState, json);
+
+    let state: ConsensusState = serde_json::from_value(json).unwrap();
+    assert_eq!(state.index.height, 1000);
+    assert_eq!(state.elements.trees.len(), 2);
+}
@@ -0,0 +1,277 @@
+use crate::encoding::Encoder;
+use crate::signing_session::{SigningRequest, SigningSession, SigningSessionError};
+use crate::spend_policy::{SpendPolicy, UnlockCondition};
+use crate::transaction::{SiacoinElement, SiacoinOutput, StateElement, V2Transaction, V2TransactionBuilder};
+use crate::types::{Address, H256};
+use crate::Keypair;
+use std::str::FromStr;
+
+fn keypair(seed: u8) -> Keypair { Keypair::from_private_bytes(&[seed; 32]).unwrap() }
+
+fn utxo(id: u8, policy: &SpendPolicy) -> SiacoinElement {
+    let mut id_bytes = [0u8; 32];
+    id_bytes[0] = id;
+    SiacoinElement {
+        state_element: StateElement {
+            id: H256(id_bytes),
+            leaf_index: id as u64,
+            merkle_proof: None,
+        },
+        siacoin_output: SiacoinOutput {
+            value: 1_000_000.into(),
+            address: policy.address(),
+        },
+        maturity_height: 0,
+    }
+}
+
+fn unsigned_tx(policy: SpendPolicy) -> V2Transaction {
+    V2TransactionBuilder::new()
+        .add_siacoin_input(utxo(1, &policy), policy.clone())
+        .add_siacoin_output(SiacoinOutput {
+            value: 900_000.into(),
+            address: policy.address(),
+        })
+        .miner_fee(100_000.into())
+        .build()
+}
+
+/// 2-of-3 threshold of public keys 1, 2 and 3
+fn threshold_policy() -> SpendPolicy {
+    SpendPolicy::Threshold {
+        n: 2,
+        of: (1..=3).map(|i| SpendPolicy::PublicKey(keypair(i).public())).collect(),
+    }
+}
+
+fn request_for(session: &SigningSession, seed: u8) -> SigningRequest {
+    session
+        .requests()
+        .into_iter()
+        .find(|request| request.signer == keypair(seed).public())
+        .unwrap()
+}
+
+#[test]
+fn test_signing_session_threshold() {
+    let mut session = SigningSession::new(unsigned_tx(threshold_policy()));
+    assert_eq!(session.signers().len(), 3);
+    assert!(!session.is_complete());
+
+    // signatures are merged out of order and serialized over the wire
+    for seed in [3, 1, 2] {
+        let request: SigningRequest =
+            serde_json::from_str(&serde_json::to_string(&request_for(&session, seed)).unwrap()).unwrap();
+        let partial = request.sign(&keypair(seed)).unwrap();
+        session
+            .add_signature(serde_json::from_value(serde_json::to_value(&partial).unwrap()).unwrap())
+            .unwrap();
+    }
+    assert!(session.is_complete());
+    assert!(session.pending_signers().is_empty());
+
+    let sig_hash = session.sig_hash();
+    let tx = session.finalize().unwrap();
+    assert_eq!(tx.input_sig_hash(), sig_hash);
+    // only the first two satisfied keys are included, in policy order
+    assert_eq!(tx.siacoin_inputs[0].satisfied_policy.signatures, vec![
+        keypair(1).sign(&sig_hash.0),
+        keypair(2).sign(&sig_hash.0)
+    ]);
+}
+
+#[test]
+fn test_signing_session_skips_missing_signer() {
+    let mut session = SigningSession::new(unsigned_tx(threshold_policy()));
+    for seed in [1, 3] {
+        let partial = request_for(&session, seed).sign(&keypair(seed)).unwrap();
+        session.add_signature(partial).unwrap();
+    }
+    assert_eq!(session.pending_signers(), vec![keypair(2).public()]);
+
+    let sig_hash = session.sig_hash();
+    assert_eq!(
+        sig_hash,
+        H256::from("0cecc8e58b228cc4cfce7c1337bcfc3d8d5c74bf9523bbb04aa3f5110780f516")
+    );
+    let tx = session.finalize().unwrap();
+    let satisfied_policy = &tx.siacoin_inputs[0].satisfied_policy;
+    // the key that did not sign is revealed only by its address
+    assert_eq!(satisfied_policy.policy, SpendPolicy::Threshold {
+        n: 2,
+        of: vec![
+            SpendPolicy::PublicKey(keypair(1).public()),
+            SpendPolicy::PublicKey(keypair(2).public()).opacify(),
+            SpendPolicy::PublicKey(keypair(3).public()),
+        ],
+    });
+    assert_eq!(satisfied_policy.signatures, vec![
+        keypair(1).sign(&sig_hash.0),
+        keypair(3).sign(&sig_hash.0)
+    ]);
+
+    let expected =
+        Address::from_str("addr:3ae1e6de973700d9e4752749c31ae65f90e85a795e87af26da544ac50f2c51ad5820446794cf").unwrap();
+    assert_eq!(satisfied_policy.policy.address(), expected);
+    assert_eq!(threshold_policy().address(), expected);
+
+    let hash = Encoder::encode_and_hash(&tx.siacoin_inputs[0]);
+    let expected = H256::from("cd507a4da1d5d76afe4675a84c44d1242003b52c3a7b20fe9553e7e67592ed85");
+    assert_eq!(hash, expected);
+}
+
+#[test]
+fn test_signing_session_unlock_conditions() {
+    let keys = (1..=3).map(|i| keypair(i).public()).collect();
+    let policy = SpendPolicy::UnlockConditions(UnlockCondition::new(keys, 0, 2));
+    let mut session = SigningSession::new(unsigned_tx(policy));
+
+    let partial = request_for(&session, 2).sign(&keypair(2)).unwrap();
+    session.add_signature(partial).unwrap();
+    assert!(matches!(
+        session.clone().finalize(),
+        Err(SigningSessionError::Unsatisfied(0))
+    ));
+
+    let partial = request_for(&session, 3).sign(&keypair(3)).unwrap();
+    session.add_signature(partial).unwrap();
+    assert_eq!(
+        session.finalize().unwrap().siacoin_inputs[0]
+            .satisfied_policy
+            .signatures
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_signing_session_rejects_invalid_signatures() {
+    let mut session = SigningSession::new(unsigned_tx(threshold_policy()));
+    let partial = request_for(&session, 1).sign(&keypair(1)).unwrap();
+
+    let mut forged = partial.clone();
+    forged.signature = keypair(2).sign(&session.sig_hash().0);
+    assert_eq!(
+        session.add_signature(forged),
+        Err(SigningSessionError::InvalidSignature(keypair(1).public().to_string()))
+    );
+
+    let mut outsider = partial.clone();
+    outsider.signer = keypair(4).public();
+    outsider.signature = keypair(4).sign(&session.sig_hash().0);
+    assert_eq!(
+        session.add_signature(outsider),
+        Err(SigningSessionError::UnknownSigner(keypair(4).public().to_string()))
+    );
+
+    let mut stale = partial.clone();
+    stale.sig_hash = H256::default();
+    assert!(matches!(
+        session.add_signature(stale),
+        Err(SigningSessionError::SigHashMismatch { .. })
+    ));
+
+    session.add_signature(partial.clone()).unwrap();
+    session.add_signature(partial).unwrap();
+    assert_eq!(session.pending_signers().len(), 2);
+}
+
+#[test]
+fn test_signing_session_deserialize_checks_sig_hash() {
+    let mut session = SigningSession::new(unsigned_tx(threshold_policy()));
+    session.sign(&keypair(1)).unwrap();
+    let json = serde_json::to_value(&session).unwrap();
+    let restored: SigningSession = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(restored.pending_signers().len(), 2);
+
+    // a session whose transaction was swapped after signing no longer hashes to its sig hash
+    let other = SigningSession::new(unsigned_tx(SpendPolicy::PublicKey(keypair(1).public())));
+    let mut swapped = json.clone();
+    swapped["transaction"] = serde_json::to_value(other.transaction()).unwrap();
+    assert!(serde_json::from_value::<SigningSession>(swapped).is_err());
+
+    // and signatures are validated against the recomputed sig hash
+    let mut forged = json;
+    forged["signatures"][0]["signature"] = serde_json::to_value(crate::encoding::PrefixedSignature(
+        keypair(2).sign(&session.sig_hash().0),
+    ))
+    .unwrap();
+    assert!(serde_json::from_value::<SigningSession>(forged).is_err());
+}
+
+#[test]
+fn test_signing_request_checks_transaction() {
+    let session = SigningSession::new(unsigned_tx(threshold_policy()));
+    let mut request = request_for(&session, 1);
+    assert_eq!(
+        request.sign(&keypair(2)),
+        Err(SigningSessionError::WrongKeypair(keypair(1).public().to_string()))
+    );
+
+    // a coordinator can not get a different transaction signed than the one shown to the signer
+    request.transaction.miner_fee = 1.into();
+    assert!(matches!(
+        request.sign(&keypair(1)),
+        Err(SigningSessionError::SigHashMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_partially_signed_transaction_between_signers() {
+    use crate::signing_session::{MissingSignatures, PartiallySignedTransaction};
+
+    let psbt = PartiallySignedTransaction::new(unsigned_tx(threshold_policy()));
+    assert_eq!(psbt.missing_signatures(), vec![MissingSignatures {
+        input: 0,
+        required: Some(2),
+        signers: (1..=3).map(|i| keypair(i).public()).collect(),
+    }]);
+
+    // signers 1 and 3 sign their own copies in parallel, the coordinator merges them
+    let mut copies: Vec<PartiallySignedTransaction> = [1, 3]
+        .iter()
+        .map(|seed| {
+            let mut copy: PartiallySignedTransaction =
+                serde_json::from_str(&serde_json::to_string(&psbt).unwrap()).unwrap();
+            copy.sign(&keypair(*seed)).unwrap();
+            copy
+        })
+        .collect();
+    let mut merged = copies.remove(0);
+    assert_eq!(merged.missing_signatures()[0].required, Some(1));
+    assert!(matches!(
+        merged.sign(&keypair(4)),
+        Err(SigningSessionError::UnknownSigner(_))
+    ));
+    merged.merge(copies.remove(0)).unwrap();
+    assert!(merged.missing_signatures().is_empty());
+    assert!(merged.is_complete());
+
+    let other = SigningSession::new(unsigned_tx(SpendPolicy::PublicKey(keypair(1).public())));
+    assert!(matches!(
+        merged.merge(other),
+        Err(SigningSessionError::SigHashMismatch { .. })
+    ));
+    assert_eq!(
+        merged.finalize().unwrap().siacoin_inputs[0]
+            .satisfied_policy
+            .signatures
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_missing_signatures_unlock_conditions() {
+    let keys = (1..=3).map(|i| keypair(i).public()).collect();
+    let policy = SpendPolicy::UnlockConditions(UnlockCondition::new(keys, 0, 2));
+    let mut session = SigningSession::new(unsigned_tx(policy));
+    session.sign(&keypair(2)).unwrap();
+
+    let missing = session.missing_signatures();
+    assert_eq!(missing[0].required, Some(1));
+    assert_eq!(missing[0].signers, vec![keypair(1).public(), keypair(3).public()]);
+
+    let session = SigningSession::new(unsigned_tx(SpendPolicy::Opaque(keypair(1).address())));
+    assert_eq!(session.missing_signatures()[0].required, None);
+}
@@ -0,0 +1,103 @@
+use crate::snapshot::{merge_events, AddressSnapshot, SnapshotError, WalletSnapshot, SNAPSHOT_VERSION};
+use crate::types::{Address, BlockID, ChainIndex, Event, H256};
+use std::str::FromStr;
+
+fn event(id: u8, height: u64, maturity_height: u64) -> Event {
+    let j = json!({
+        "id": format!("h:{:064x}", id),
+        "index": {
+            "height": height,
+            "id": "bid:bd04c08bb96203c7f24adf2d405cb1069c7da8573573011379a986be62fc2a29"
+        },
+        "timestamp": "2024-07-18T19:04:16Z",
+        "maturityHeight": maturity_height,
+        "type": "v2Transaction",
+        "data": {
+            "siacoinOutputs": [
+                {
+                    "value": "10400000000000000000000000000",
+                    "address": "addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f"
+                }
+            ],
+            "minerFee": "0"
+        }
+    });
+    serde_json::from_value(j).unwrap()
+}
+
+fn ids(events: &[Event]) -> Vec<u8> { events.iter().map(|event| H256::from(event.id).0[31]).collect() }
+
+fn snapshot() -> WalletSnapshot {
+    let tip = ChainIndex {
+        height: 100,
+        id: BlockID(H256::from(
+            "c67c3b2e57490617a25a9fcb9fd54ab6acbe72fc1e4f1f432cb9334177917667",
+        )),
+    };
+    WalletSnapshot::new(tip, vec![AddressSnapshot {
+        address: Address::from_str("addr:f7843ac265b037658b304468013da4fd0f304a1b73df0dc68c4273c867bfa38d01a7661a187f")
+            .unwrap(),
+        events: vec![event(2, 99, 99), event(1, 50, 50)],
+        utxos: vec![],
+    }])
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let snapshot = snapshot();
+    let mut file = Vec::new();
+    snapshot.write_to(&mut file).unwrap();
+
+    let read = WalletSnapshot::read_from(file.as_slice()).unwrap();
+    assert_eq!(read.version, SNAPSHOT_VERSION);
+    assert_eq!(read.tip, snapshot.tip);
+    assert_eq!(read.addresses[0].address, snapshot.addresses[0].address);
+    assert_eq!(ids(&read.addresses[0].events), vec![2, 1]);
+}
+
+#[test]
+fn test_snapshot_invalid_header() {
+    assert!(matches!(
+        WalletSnapshot::read_from(&b"not gzip"[..]),
+        Err(SnapshotError::Serde(_))
+    ));
+
+    let mut snapshot = snapshot();
+    snapshot.magic = "sia-rust-backup".into();
+    let mut file = Vec::new();
+    snapshot.write_to(&mut file).unwrap();
+    assert!(matches!(
+        WalletSnapshot::read_from(file.as_slice()),
+        Err(SnapshotError::InvalidMagic)
+    ));
+
+    let mut snapshot = self::snapshot();
+    snapshot.version = SNAPSHOT_VERSION + 1;
+    let mut file = Vec::new();
+    snapshot.write_to(&mut file).unwrap();
+    assert!(matches!(
+        WalletSnapshot::read_from(file.as_slice()),
+        Err(SnapshotError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn test_merge_events() {
+    let mut events = vec![event(3, 99, 99), event(2, 96, 240), event(1, 50, 50)];
+    // event 3 was reorged out, event 2 is returned again since it matures above the cutoff
+    let fetched = vec![
+        event(2, 96, 240),
+        event(5, 102, 102),
+        event(6, 102, 102),
+        event(4, 98, 98),
+    ];
+
+    // ordered by block height rather than maturity height, ties by ID
+    assert_eq!(merge_events(&mut events, fetched, Some(96)), 3);
+    assert_eq!(ids(&events), vec![6, 5, 4, 2, 1]);
+
+    // a full rescan after a reorg deeper than the cutoff drops every event not returned again
+    let fetched = vec![event(7, 101, 101), event(1, 50, 50)];
+    assert_eq!(merge_events(&mut events, fetched, None), 1);
+    assert_eq!(ids(&events), vec![7, 1]);
+}
@@ -1,4 +1,5 @@
-use crate::spend_policy::{spend_policy_atomic_swap_success, SpendPolicy, SpendPolicyHelper, UnlockCondition, UnlockKey};
+use crate::spend_policy::{spend_policy_atomic_swap, spend_policy_atomic_swap_refund, spend_policy_atomic_swap_success,
+                          SpendPolicy, SpendPolicyHelper, UnlockCondition, UnlockKey};
 use crate::types::{Address, H256};
 use crate::PublicKey;
 use std::str::FromStr;
@@ -163,3 +164,36 @@ fn test_serde_spend_policy_unlock_conditions_standard() {
 
     assert_eq!(spend_policy, spend_policy_deser);
 }
+
+#[test]
+fn test_spend_policy_address_opaque() {
+    let policy = SpendPolicy::above(100);
+    let opaque = policy.opacify();
+    assert_eq!(opaque, SpendPolicy::Opaque(policy.address()));
+    assert_eq!(opaque.address(), policy.address());
+    assert_eq!(opaque.opacify(), opaque);
+}
+
+#[test]
+fn test_spend_policy_address_threshold_opacified() {
+    let alice_pubkey = PublicKey::from_bytes(
+        &hex::decode("0102030000000000000000000000000000000000000000000000000000000000").unwrap(),
+    )
+    .unwrap();
+    let bob_pubkey = PublicKey::from_bytes(
+        &hex::decode("06C87838297B7BB16AB23946C99DFDF77FF834E35DB07D71E9B1D2B01A11E96D").unwrap(),
+    )
+    .unwrap();
+    let secret_hash = H256::from("0100000000000000000000000000000000000000000000000000000000000000");
+
+    let swap = spend_policy_atomic_swap(alice_pubkey, bob_pubkey, 77777777, secret_hash);
+    let success = spend_policy_atomic_swap_success(alice_pubkey, bob_pubkey, 77777777, secret_hash);
+    let refund = spend_policy_atomic_swap_refund(alice_pubkey, bob_pubkey, 77777777, secret_hash);
+    assert_ne!(success, swap);
+    assert_eq!(success.address(), swap.address());
+    assert_eq!(refund.address(), swap.address());
+
+    let nested = SpendPolicy::threshold(1, vec![SpendPolicy::threshold(1, vec![swap.clone()])]);
+    let nested_opaque = SpendPolicy::threshold(1, vec![SpendPolicy::threshold(1, vec![swap.opacify()])]);
+    assert_eq!(nested.address(), nested_opaque.address());
+}
@@ -0,0 +1,116 @@
+use crate::swap::{AtomicSwap, AtomicSwapError};
+use crate::transaction::{SiacoinElement, SiacoinOutput, StateElement, V2TransactionBuilder};
+use crate::types::{Address, H256};
+use crate::Keypair;
+use ed25519_dalek::Verifier;
+use std::str::FromStr;
+
+fn keypair(seed: u8) -> Keypair { Keypair::from_private_bytes(&[seed; 32]).unwrap() }
+
+fn swap() -> AtomicSwap {
+    AtomicSwap::new(
+        keypair(1).public(),
+        keypair(2).public(),
+        1_700_000_000,
+        // sha256 of the preimage `[7; 32]`
+        H256::from("4bb06f8e4e3a7715d201d573d0aa423762e55dabd61a2c02278fa56cc6d294e0"),
+    )
+}
+
+fn swap_output(swap: &AtomicSwap) -> SiacoinElement {
+    SiacoinElement {
+        state_element: StateElement {
+            id: H256::from("0200000000000000000000000000000000000000000000000000000000000000"),
+            leaf_index: 0,
+            merkle_proof: None,
+        },
+        siacoin_output: SiacoinOutput {
+            value: 1_000.into(),
+            address: swap.address(),
+        },
+        maturity_height: 0,
+    }
+}
+
+fn spend(swap: &AtomicSwap, success: bool) -> V2TransactionBuilder {
+    let builder = V2TransactionBuilder::new();
+    let builder = if success {
+        swap.add_success_input(builder, swap_output(swap))
+    } else {
+        swap.add_refund_input(builder, swap_output(swap))
+    };
+    builder
+        .add_siacoin_output(SiacoinOutput {
+            value: 990.into(),
+            address: Address::from_str(
+                "addr:72b0762b382d4c251af5ae25b6777d908726d75962e5224f98d7f619bb39515dd64b9a56043a",
+            )
+            .unwrap(),
+        })
+        .miner_fee(10.into())
+}
+
+#[test]
+fn test_atomic_swap_address() {
+    let swap = swap();
+    assert_eq!(swap.success_policy().address(), swap.address());
+    assert_eq!(swap.refund_policy().address(), swap.address());
+    assert!(!swap.is_refundable_at(1_700_000_000));
+    assert!(swap.is_refundable_at(1_700_000_001));
+}
+
+#[test]
+fn test_atomic_swap_sign_success() {
+    let swap = swap();
+    let builder = spend(&swap, true);
+    let sig_hash = builder.input_sig_hash();
+    let tx = swap.sign_success(builder, &keypair(1), vec![7u8; 32]).unwrap().build();
+
+    let satisfied = &tx.siacoin_inputs[0].satisfied_policy;
+    assert_eq!(satisfied.policy, swap.success_policy());
+    assert_eq!(satisfied.preimages, vec![vec![7u8; 32]]);
+    assert_eq!(satisfied.signatures.len(), 1);
+    keypair(1)
+        .public()
+        .verify(&sig_hash.0, &satisfied.signatures[0])
+        .unwrap();
+}
+
+#[test]
+fn test_atomic_swap_sign_refund() {
+    let swap = swap();
+    let builder = spend(&swap, false);
+    let sig_hash = builder.input_sig_hash();
+    let tx = swap.sign_refund(builder, &keypair(2)).unwrap().build();
+
+    let satisfied = &tx.siacoin_inputs[0].satisfied_policy;
+    assert_eq!(satisfied.policy, swap.refund_policy());
+    assert!(satisfied.preimages.is_empty());
+    keypair(2)
+        .public()
+        .verify(&sig_hash.0, &satisfied.signatures[0])
+        .unwrap();
+}
+
+#[test]
+fn test_atomic_swap_wrong_keypair() {
+    let swap = swap();
+    assert_eq!(
+        swap.sign_refund(spend(&swap, false), &keypair(1)).err(),
+        Some(AtomicSwapError::WrongKeypair {
+            path: "refund",
+            expected: keypair(2).public().to_string(),
+            got: keypair(1).public().to_string(),
+        })
+    );
+    assert!(swap.sign_success(spend(&swap, true), &keypair(2), vec![]).is_err());
+}
+
+#[test]
+fn test_atomic_swap_wrong_preimage() {
+    let swap = swap();
+    assert_eq!(
+        swap.sign_success(spend(&swap, true), &keypair(1), vec![8u8; 32]).err(),
+        Some(AtomicSwapError::WrongPreimage(swap.secret_hash))
+    );
+}
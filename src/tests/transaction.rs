@@ -1,10 +1,11 @@
-use crate::encoding::Encoder;
+use crate::encoding::{Encodable, Encoder};
 use crate::spend_policy::{spend_policy_atomic_swap_refund, spend_policy_atomic_swap_success, SpendPolicy,
                           UnlockCondition};
-use crate::transaction::{Attestation, Currency, CurrencyVersion, FileContractRevisionV2, SatisfiedPolicy,
-                         SiacoinElement, SiacoinInputV1, SiacoinInputV2, SiacoinOutput, SiacoinOutputVersion,
-                         StateElement, V2FileContract, V2FileContractElement, V2Transaction};
-use crate::types::{v1_standard_address_from_pubkey, Address, H256};
+use crate::transaction::{Attestation, CoveredFields, Currency, CurrencyError, CurrencyVersion, FileContractRevisionV2,
+                         ReplayPrefix, SatisfiedPolicy, SiacoinElement, SiacoinInputV1, SiacoinInputV2, SiacoinOutput,
+                         SiacoinOutputVersion, Siacoins, SigHashError, StateElement, TransactionSignature,
+                         V1Transaction, V2FileContract, V2FileContractElement, V2Transaction, HASTINGS_PER_SIACOIN};
+use crate::types::{v1_standard_address_from_pubkey, Address, BlockID, ChainIndex, Network, TransactionID, H256};
 use crate::{PublicKey, Signature};
 use std::str::FromStr;
 
@@ -736,3 +737,493 @@ fn test_v2_transaction_signing() {
     let sig: Signature = keypair.sign(&sig_hash.0);
     assert_eq!(tx.siacoin_inputs[0].satisfied_policy.signatures[0], sig);
 }
+
+#[test]
+fn test_v2_transaction_sign_simple_parallel() {
+    use crate::transaction::V2TransactionBuilder;
+    use crate::Keypair;
+
+    let keypairs: Vec<Keypair> = (1u8..=7)
+        .map(|i| {
+            let mut secret = [0u8; 32];
+            secret[0] = i;
+            Keypair::from_private_bytes(&secret).unwrap()
+        })
+        .collect();
+
+    let builder = || {
+        let mut builder = V2TransactionBuilder::new();
+        for (i, keypair) in keypairs.iter().enumerate() {
+            let policy = SpendPolicy::PublicKey(keypair.public());
+            let mut id = [0u8; 32];
+            id[0] = i as u8;
+            let parent = SiacoinElement {
+                state_element: StateElement {
+                    id: H256(id),
+                    leaf_index: i as u64,
+                    merkle_proof: None,
+                },
+                siacoin_output: SiacoinOutput {
+                    value: 1.into(),
+                    address: policy.address(),
+                },
+                maturity_height: 0,
+            };
+            builder = builder.add_siacoin_input(parent, policy);
+        }
+        builder
+    };
+
+    let expected = builder().sign_simple(keypairs.iter().collect()).unwrap().build();
+    for threads in &[0, 1, 3, 7, 16] {
+        let tx = builder()
+            .sign_simple_parallel(keypairs.iter().collect(), *threads)
+            .unwrap()
+            .build();
+        assert_eq!(tx, expected);
+    }
+}
+
+#[test]
+fn test_v2_transaction_builder_canonical_sort() {
+    use crate::transaction::V2TransactionBuilder;
+
+    let policy = SpendPolicy::Above(0);
+    let parents: Vec<SiacoinElement> = (0u8..4)
+        .map(|i| {
+            let mut id = [0u8; 32];
+            id[31] = 4 - i;
+            SiacoinElement {
+                state_element: StateElement {
+                    id: H256(id),
+                    leaf_index: i as u64,
+                    merkle_proof: None,
+                },
+                siacoin_output: SiacoinOutput {
+                    value: 10.into(),
+                    address: policy.address(),
+                },
+                maturity_height: 0,
+            }
+        })
+        .collect();
+    let outputs: Vec<SiacoinOutput> = (1u64..4)
+        .map(|i| SiacoinOutput {
+            value: i.into(),
+            address: policy.address(),
+        })
+        .collect();
+
+    let build = |parents: Vec<SiacoinElement>, outputs: Vec<SiacoinOutput>| {
+        let builder = parents
+            .into_iter()
+            .fold(V2TransactionBuilder::new(), |builder, parent| {
+                builder.add_siacoin_input(parent, policy.clone())
+            });
+        builder
+            .siacoin_outputs(outputs)
+            .miner_fee(1.into())
+            .canonical_sort()
+            .build()
+    };
+
+    let tx = build(parents.clone(), outputs.clone());
+    let mut rev_parents = parents;
+    rev_parents.reverse();
+    let mut rev_outputs = outputs;
+    rev_outputs.reverse();
+    let permuted = build(rev_parents, rev_outputs);
+
+    assert_eq!(tx, permuted);
+    assert_eq!(tx.txid(), permuted.txid());
+    let ids: Vec<H256> = tx.siacoin_inputs.iter().map(|si| si.parent.state_element.id).collect();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+}
+
+#[test]
+fn test_siacoins_conversion() {
+    let one = Siacoins::new(1).unwrap();
+    assert_eq!(Currency::from(one), Currency(HASTINGS_PER_SIACOIN));
+    assert_eq!(Siacoins::from(Currency(1)).to_string(), "0.000000000000000000000001");
+    assert_eq!(Siacoins::from(Currency(u128::MAX)).to_hastings(), Currency(u128::MAX));
+    assert_eq!(Siacoins::new(u64::MAX), Err(CurrencyError::Overflow));
+
+    let half = Siacoins::from_str("0.5").unwrap();
+    assert_eq!(half.to_string(), "0.5");
+    assert_eq!((one + half).to_string(), "1.5");
+    assert_eq!(Siacoins::from_str("1.5").unwrap(), one + half);
+    assert_eq!(Siacoins::from_str("42").unwrap().to_string(), "42");
+    assert_eq!(Siacoins::ZERO.to_string(), "0");
+}
+
+#[test]
+fn test_siacoins_from_str_invalid() {
+    for s in ["", ".5", "1.", "1.2.3", "-1", "1e3", " 1"] {
+        assert_eq!(
+            Siacoins::from_str(s),
+            Err(CurrencyError::InvalidAmount(s.to_owned())),
+            "{}",
+            s
+        );
+    }
+    assert!(matches!(
+        Siacoins::from_str("0.0000000000000000000000001"),
+        Err(CurrencyError::TooPrecise(_))
+    ));
+    assert_eq!(Siacoins::from_str("340282366920939"), Err(CurrencyError::Overflow));
+}
+
+#[test]
+fn test_currency_arithmetic() {
+    let total: Currency = vec![Currency(1), Currency(2), Currency(3)].into_iter().sum();
+    assert_eq!(total, Currency(6));
+    assert_eq!(total - Currency(6), Currency(0));
+    assert_eq!(Currency(u128::MAX).checked_add(Currency(1)), None);
+    assert_eq!(Currency(0).checked_sub(Currency(1)), None);
+
+    let mut sc = Siacoins::new(2).unwrap();
+    sc -= Siacoins::new(1).unwrap();
+    assert_eq!(sc.checked_sub(Siacoins::new(2).unwrap()), None);
+    assert_eq!(sc.checked_add(sc), Some(Siacoins::new(2).unwrap()));
+}
+
+#[test]
+#[should_panic(expected = "Currency overflow")]
+fn test_currency_add_overflow() { let _ = Currency(u128::MAX) + Currency(1); }
+
+#[test]
+#[should_panic(expected = "Currency underflow")]
+fn test_currency_sub_underflow() {
+    let mut amount = Currency(1);
+    amount -= Currency(2);
+}
+
+#[test]
+#[should_panic(expected = "Siacoins underflow")]
+fn test_siacoins_sub_underflow() { let _ = Siacoins::new(1).unwrap() - Siacoins::new(2).unwrap(); }
+
+#[test]
+fn test_currency_display_and_parse() {
+    assert_eq!(Currency(1_500_000_000_000_000_000_000_000).to_string(), "1.5 SC");
+    assert_eq!(Currency(1).to_string(), "0.000000000000000000000001 SC");
+    assert_eq!(Currency::ZERO.to_string(), "0 SC");
+
+    assert_eq!(
+        Currency::from_str("1.5 SC"),
+        Ok(Currency(1_500_000_000_000_000_000_000_000))
+    );
+    assert_eq!(
+        Currency::from_str("2SC"),
+        Ok(Currency(2_000_000_000_000_000_000_000_000))
+    );
+    assert_eq!(Currency::from_str("100 H"), Ok(Currency(100)));
+    assert_eq!(Currency::from_str("100"), Ok(Currency(100)));
+    assert!(matches!(
+        Currency::from_str("1.5"),
+        Err(CurrencyError::InvalidAmount(_))
+    ));
+    assert!(matches!(Currency::from_str("SC"), Err(CurrencyError::InvalidAmount(_))));
+    assert_eq!(
+        Currency::from_str("340282366920938463463374607431768211456"),
+        Err(CurrencyError::Overflow)
+    );
+    assert_eq!(Currency(u128::MAX).checked_mul(2), None);
+    assert_eq!(Currency(3).checked_mul(2), Some(Currency(6)));
+
+    // serialized as hastings, not as the display form
+    assert_eq!(serde_json::to_value(Currency(100)).unwrap(), json!("100"));
+}
+
+#[test]
+fn test_output_ids() {
+    let txid = TransactionID(H256::from(
+        "5900e475aace932c94bcc94cf296596ccff1d77d9aba52a079e9f429605671cd",
+    ));
+    assert_eq!(
+        SiacoinOutput::id(txid, 0),
+        H256::from("68b21e1d62ab455f6f25dd54bc531687940d4de21e971fac329e25832f48f38a")
+    );
+    assert_eq!(
+        SiacoinOutput::id(txid, 1),
+        H256::from("df482385aea3eac63bdb42b163b91921b45b7e6d3777ae281836252fa331d8ce")
+    );
+
+    let tx = V2Transaction::default();
+    assert_eq!(tx.siacoin_output_id(1), SiacoinOutput::id(tx.txid(), 1));
+    assert_ne!(tx.siafund_output_id(1), tx.siacoin_output_id(1));
+
+    let tx = V1Transaction::default();
+    assert_eq!(
+        tx.siacoin_output_id(2),
+        H256::from("7226e2edcff58d36c760d2065987fde4aea1643f37fd44b30b6188bb8894ccc7")
+    );
+    assert_eq!(
+        tx.siafund_output_id(2),
+        H256::from("c88d014f85273fba17051e41fdab920c4dfabd1ded1afbd26899de5cbe9de266")
+    );
+}
+
+#[test]
+fn test_v2_transaction_builder_change_output() {
+    use crate::transaction::{V2TransactionBuilder, V2TransactionBuilderError};
+    use crate::Keypair;
+    use ed25519_dalek::Verifier;
+
+    let keypair = Keypair::from_private_bytes(&[1u8; 32]).unwrap();
+    let policy = SpendPolicy::PublicKey(keypair.public());
+    let change_address = policy.address();
+    let recipient =
+        Address::from_str("addr:1d9a926b1e14b54242375c7899a60de883c8cad0a45a49a7ca2fdb6eb52f0f01dfe678918204").unwrap();
+    let builder = |input_value: u64| {
+        let parent = SiacoinElement {
+            state_element: StateElement {
+                id: H256([7u8; 32]),
+                leaf_index: 0,
+                merkle_proof: None,
+            },
+            siacoin_output: SiacoinOutput {
+                value: input_value.into(),
+                address: change_address.clone(),
+            },
+            maturity_height: 0,
+        };
+        V2TransactionBuilder::new()
+            .add_siacoin_input(parent, policy.clone())
+            .add_siacoin_output(SiacoinOutput {
+                value: 600.into(),
+                address: recipient.clone(),
+            })
+            .miner_fee(100.into())
+    };
+
+    let tx = builder(1000)
+        .add_change_output(change_address.clone())
+        .unwrap()
+        .sign_simple(vec![&keypair])
+        .unwrap()
+        .build();
+    assert_eq!(tx.siacoin_outputs[1], SiacoinOutput {
+        value: 300.into(),
+        address: change_address.clone(),
+    });
+    let sig_hash = tx.input_sig_hash();
+    assert!(keypair
+        .public()
+        .verify(&sig_hash.0, &tx.siacoin_inputs[0].satisfied_policy.signatures[0])
+        .is_ok());
+
+    // spent exactly, no change output
+    let tx = builder(700).add_change_output(change_address.clone()).unwrap().build();
+    assert_eq!(tx.siacoin_outputs.len(), 1);
+
+    match builder(699).add_change_output(change_address) {
+        Err(V2TransactionBuilderError::InsufficientFunds { available, required }) => {
+            assert_eq!((available, required), (Currency(699), Currency(700)))
+        },
+        other => panic!("expected InsufficientFunds, got {:?}", other.map(|b| b.build())),
+    }
+}
+
+#[test]
+fn test_v2_transaction_builder_siafunds() {
+    use crate::transaction::{SiafundElement, SiafundOutput, V2TransactionBuilder, V2TransactionBuilderError};
+    use crate::Keypair;
+    use ed25519_dalek::Verifier;
+
+    let keypair = Keypair::from_private_bytes(&[1u8; 32]).unwrap();
+    let policy = SpendPolicy::PublicKey(keypair.public());
+    let owner = policy.address();
+    let recipient =
+        Address::from_str("addr:1d9a926b1e14b54242375c7899a60de883c8cad0a45a49a7ca2fdb6eb52f0f01dfe678918204").unwrap();
+    let parent = SiafundElement {
+        state_element: StateElement {
+            id: H256([7u8; 32]),
+            leaf_index: 0,
+            merkle_proof: None,
+        },
+        siafund_output: SiafundOutput {
+            value: 2000,
+            address: owner.clone(),
+        },
+        claim_start: Currency(1_000_000),
+    };
+
+    // 2000 of 10000 siafunds receive a fifth of the pool accrued since `claim_start`
+    assert_eq!(parent.claim_value(Currency(6_000_000)), Currency(1_000_000));
+    assert_eq!(parent.claim_value(Currency(1_000_000)), Currency::ZERO);
+    assert_eq!(
+        parent.v2_claim_output_id(),
+        H256::from("d5aa0e15283cefd0f0051f76b15ede4e47003b599bba50a498b3926f4f2de1a2")
+    );
+
+    let builder = |sent: u64| {
+        V2TransactionBuilder::new()
+            .add_siafund_input(parent.clone(), policy.clone(), owner.clone())
+            .add_siafund_output(SiafundOutput {
+                value: sent,
+                address: recipient.clone(),
+            })
+    };
+    let tx = builder(500)
+        .add_siafund_change_output(owner.clone())
+        .unwrap()
+        .sign_simple(vec![&keypair])
+        .unwrap()
+        .build();
+    assert_eq!(tx.siafund_inputs[0].claim_address, owner);
+    assert_eq!(tx.siafund_outputs[1], SiafundOutput {
+        value: 1500,
+        address: owner.clone(),
+    });
+    assert!(keypair
+        .public()
+        .verify(
+            &tx.input_sig_hash().0,
+            &tx.siafund_inputs[0].satisfied_policy.signatures[0]
+        )
+        .is_ok());
+
+    let tx = builder(2000).add_siafund_change_output(owner.clone()).unwrap().build();
+    assert_eq!(tx.siafund_outputs.len(), 1);
+
+    match builder(2001).add_siafund_change_output(owner) {
+        Err(V2TransactionBuilderError::InsufficientSiafunds { available, required }) => {
+            assert_eq!((available, required), (2000, 2001))
+        },
+        other => panic!("expected InsufficientSiafunds, got {:?}", other.map(|b| b.build())),
+    }
+}
+
+fn v1_transaction() -> V1Transaction {
+    let public_key = PublicKey::from_bytes(
+        &hex::decode("0102030000000000000000000000000000000000000000000000000000000000").unwrap(),
+    )
+    .unwrap();
+    V1Transaction {
+        siacoin_inputs: vec![SiacoinInputV1 {
+            parent_id: H256::from("0405060000000000000000000000000000000000000000000000000000000000"),
+            unlock_condition: UnlockCondition::new(vec![public_key], 0, 1),
+        }],
+        siacoin_outputs: vec![SiacoinOutput {
+            value: Currency(1),
+            address: Address::from_str(
+                "addr:000000000000000000000000000000000000000000000000000000000000000089eb0d6a8a69",
+            )
+            .unwrap(),
+        }],
+        miner_fees: vec![Currency(2)],
+        ..Default::default()
+    }
+}
+
+fn v1_signature(covered_fields: serde_json::Value) -> TransactionSignature {
+    serde_json::from_value(json!({
+        "parentID": "h:0405060000000000000000000000000000000000000000000000000000000000",
+        "publicKeyIndex": 0,
+        "timelock": 0,
+        "coveredFields": covered_fields,
+        "signature": ""
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_v1_whole_sig_hash() {
+    let tx = v1_transaction();
+    let signature = v1_signature(json!({ "wholeTransaction": true }));
+
+    // the preimage layout of `State.WholeSigHash`
+    let mut encoder = Encoder::default();
+    encoder.write_u64(1);
+    encoder.write_u8(2);
+    tx.siacoin_inputs[0].encode(&mut encoder);
+    encoder.write_u64(1);
+    SiacoinOutputVersion::V1(&tx.siacoin_outputs[0]).encode(&mut encoder);
+    // file contracts, revisions, storage proofs, siafund inputs and outputs
+    (0..5).for_each(|_| encoder.write_u64(0));
+    encoder.write_u64(1);
+    CurrencyVersion::V1(&tx.miner_fees[0]).encode(&mut encoder);
+    // arbitrary data
+    encoder.write_u64(0);
+    signature.parent_id.encode(&mut encoder);
+    encoder.write_u64(0);
+    encoder.write_u64(0);
+
+    let hash = tx.whole_sig_hash(&signature, ReplayPrefix::V2).unwrap();
+    assert_eq!(hash, encoder.hash());
+    assert_eq!(tx.sig_hash(&signature, ReplayPrefix::V2).unwrap(), hash);
+    assert_ne!(tx.whole_sig_hash(&signature, ReplayPrefix::Asic).unwrap(), hash);
+    assert_ne!(tx.whole_sig_hash(&signature, ReplayPrefix::None).unwrap(), hash);
+
+    let signature = v1_signature(json!({ "wholeTransaction": true, "signatures": [0] }));
+    assert_eq!(
+        tx.whole_sig_hash(&signature, ReplayPrefix::V2),
+        Err(SigHashError::IndexOutOfRange {
+            field: "signatures",
+            index: 0
+        })
+    );
+}
+
+#[test]
+fn test_v1_partial_sig_hash() {
+    let tx = v1_transaction();
+    let covered_fields = CoveredFields {
+        siacoin_inputs: vec![0],
+        miner_fees: vec![0],
+        ..Default::default()
+    };
+
+    // only the covered elements, without length prefixes
+    let mut encoder = Encoder::default();
+    encoder.write_u8(1);
+    tx.siacoin_inputs[0].encode(&mut encoder);
+    CurrencyVersion::V1(&tx.miner_fees[0]).encode(&mut encoder);
+
+    let hash = tx.partial_sig_hash(&covered_fields, ReplayPrefix::Asic).unwrap();
+    assert_eq!(hash, encoder.hash());
+    let signature = v1_signature(serde_json::to_value(&covered_fields).unwrap());
+    assert_eq!(tx.sig_hash(&signature, ReplayPrefix::Asic).unwrap(), hash);
+
+    let covered_fields = CoveredFields {
+        siacoin_outputs: vec![1],
+        ..Default::default()
+    };
+    assert_eq!(
+        tx.partial_sig_hash(&covered_fields, ReplayPrefix::Asic),
+        Err(SigHashError::IndexOutOfRange {
+            field: "siacoin_outputs",
+            index: 1
+        })
+    );
+}
+
+#[test]
+fn test_replay_prefix_at_height() {
+    let network: Network = serde_json::from_str(include_str!("../../fixtures/walletd/consensus_network.json")).unwrap();
+
+    assert_eq!(ReplayPrefix::at_height(&network, 19), ReplayPrefix::None);
+    assert_eq!(ReplayPrefix::at_height(&network, 20), ReplayPrefix::Asic);
+    assert_eq!(ReplayPrefix::at_height(&network, 99), ReplayPrefix::Asic);
+    assert_eq!(ReplayPrefix::at_height(&network, 100), ReplayPrefix::V2);
+
+    let allow_height = network.hardfork_v2.allow_height;
+    assert_eq!(ReplayPrefix::at_height(&network, allow_height - 1), ReplayPrefix::Asic);
+    assert_eq!(ReplayPrefix::at_height(&network, allow_height), ReplayPrefix::V2);
+
+    // the block after a tip at `allow_height - 1` is the first to use the v2 prefix
+    let tip = |height| ChainIndex {
+        height,
+        id: BlockID::default(),
+    };
+    assert_eq!(
+        ReplayPrefix::after_tip(&network, &tip(allow_height - 2)),
+        ReplayPrefix::Asic
+    );
+    assert_eq!(
+        ReplayPrefix::after_tip(&network, &tip(allow_height - 1)),
+        ReplayPrefix::V2
+    );
+}
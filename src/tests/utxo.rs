@@ -0,0 +1,269 @@
+use crate::spend_policy::SpendPolicy;
+use crate::transaction::{Currency, SatisfiedPolicy, SiacoinElement, SiacoinInputV2, SiacoinOutput, StateElement,
+                         V2Transaction};
+use crate::types::{Address, BlockID, ChainIndex, Event, EventDataWrapper, EventID, EventPayout, EventType,
+                   TxpoolTransactions, H256};
+use crate::utxo::{confirmation_heights, find_txpool_conflicts, select_utxos, txpool_spent_ids, CoinSelectionError,
+                  SelectionStrategy, SpendableFilter, TxpoolConflict};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn utxo(id: u8, maturity_height: u64) -> SiacoinElement {
+    let mut id_bytes = [0u8; 32];
+    id_bytes[0] = id;
+    SiacoinElement {
+        state_element: StateElement {
+            id: H256(id_bytes),
+            leaf_index: id as u64,
+            merkle_proof: None,
+        },
+        siacoin_output: SiacoinOutput {
+            value: 1.into(),
+            address: Address::from_str(
+                "addr:72b0762b382d4c251af5ae25b6777d908726d75962e5224f98d7f619bb39515dd64b9a56043a",
+            )
+            .unwrap(),
+        },
+        maturity_height,
+    }
+}
+
+fn tx_spending(parents: Vec<SiacoinElement>, miner_fee: u64) -> V2Transaction {
+    V2Transaction {
+        siacoin_inputs: parents
+            .into_iter()
+            .map(|parent| SiacoinInputV2 {
+                parent,
+                satisfied_policy: SatisfiedPolicy {
+                    policy: SpendPolicy::Above(0),
+                    signatures: vec![],
+                    preimages: vec![],
+                },
+            })
+            .collect(),
+        miner_fee: miner_fee.into(),
+        ..Default::default()
+    }
+}
+
+fn txpool_spending(parent: SiacoinElement) -> TxpoolTransactions {
+    let tx = tx_spending(vec![parent], 0);
+    TxpoolTransactions {
+        transactions: vec![],
+        v2transactions: vec![tx],
+    }
+}
+
+#[test]
+fn test_spendable_filter_maturity() {
+    let filter = SpendableFilter::new(100);
+
+    assert!(filter.is_spendable(&utxo(1, 0)));
+    // spendable in the next block
+    assert!(filter.is_spendable(&utxo(2, 101)));
+    // coinbase outputs that are still immature
+    assert!(!filter.is_spendable(&utxo(3, 102)));
+    assert!(!filter.is_spendable(&utxo(4, 244)));
+}
+
+#[test]
+fn test_spendable_filter_min_confirmations() {
+    let confirmed = utxo(1, 0);
+    let recent = utxo(2, 0);
+    let unknown = utxo(3, 0);
+
+    let mut heights = HashMap::new();
+    heights.insert(confirmed.state_element.id, 90);
+    heights.insert(recent.state_element.id, 100);
+    let filter = SpendableFilter::new(100)
+        .min_confirmations(6)
+        .confirmation_heights(heights);
+
+    assert_eq!(filter.confirmations(&confirmed), 11);
+    assert_eq!(filter.confirmations(&recent), 1);
+    assert_eq!(filter.confirmations(&unknown), 1);
+
+    let spendable = filter.filter(vec![confirmed.clone(), recent, unknown]);
+    assert_eq!(spendable, vec![confirmed]);
+}
+
+fn event(height: u64, event_type: EventType, data: EventDataWrapper) -> Event {
+    Event {
+        id: EventID::default(),
+        index: ChainIndex {
+            height,
+            id: BlockID(H256::default()),
+        },
+        timestamp: DateTime::<Utc>::from(std::time::UNIX_EPOCH),
+        maturity_height: height,
+        event_type,
+        data,
+        relevant: None,
+    }
+}
+
+#[test]
+fn test_confirmation_heights_of_transactions() {
+    let mut tx = tx_spending(vec![utxo(1, 0)], 1);
+    tx.siacoin_outputs = vec![utxo(2, 0).siacoin_output, utxo(3, 0).siacoin_output];
+    let payout = utxo(4, 200);
+    let events = vec![
+        event(
+            150,
+            EventType::V2Transaction,
+            EventDataWrapper::V2Transaction(tx.clone()),
+        ),
+        event(
+            56,
+            EventType::Miner,
+            EventDataWrapper::MinerPayout(EventPayout {
+                siacoin_element: payout.clone(),
+            }),
+        ),
+    ];
+
+    let heights = confirmation_heights(&events);
+    assert_eq!(heights.len(), 3);
+    assert_eq!(heights[&tx.siacoin_output_id(0)], 150);
+    assert_eq!(heights[&tx.siacoin_output_id(1)], 150);
+    assert_eq!(heights[&payout.state_element.id], 56);
+
+    // an ordinary output of a transaction is held to the confirmation depth like a payout
+    let mut received = utxo(2, 0);
+    received.state_element.id = tx.siacoin_output_id(0);
+    let filter = SpendableFilter::new(152)
+        .min_confirmations(6)
+        .confirmation_heights(heights);
+    assert_eq!(filter.confirmations(&received), 3);
+    assert!(!filter.is_spendable(&received));
+}
+
+#[test]
+fn test_spendable_filter_excludes_txpool_spends() {
+    let pending = utxo(1, 0);
+    let free = utxo(2, 0);
+    let txpool = txpool_spending(pending.clone());
+
+    assert!(txpool_spent_ids(&txpool).contains(&pending.state_element.id));
+
+    let filter = SpendableFilter::new(100).exclude_txpool(&txpool);
+    assert_eq!(filter.filter(vec![pending, free.clone()]), vec![free]);
+}
+
+#[test]
+fn test_serde_txpool_transactions_response_null() {
+    let json = r#"{"transactions":null,"v2transactions":null}"#;
+    let txpool: TxpoolTransactions = serde_json::from_str(json).unwrap();
+    assert!(txpool.transactions.is_empty());
+    assert!(txpool.v2transactions.is_empty());
+}
+
+#[test]
+fn test_find_txpool_conflicts() {
+    let shared = utxo(1, 0);
+    let pool_tx = tx_spending(vec![shared.clone(), utxo(2, 0)], 1);
+    let txpool = TxpoolTransactions {
+        transactions: vec![],
+        v2transactions: vec![pool_tx.clone()],
+    };
+
+    let tx = tx_spending(vec![shared.clone(), utxo(3, 0)], 2);
+    let expected = TxpoolConflict {
+        txid: pool_tx.txid(),
+        output_ids: vec![shared.state_element.id],
+    };
+    assert_eq!(find_txpool_conflicts(&tx, &txpool), vec![expected]);
+
+    let tx = tx_spending(vec![utxo(3, 0)], 2);
+    assert!(find_txpool_conflicts(&tx, &txpool).is_empty());
+}
+
+#[test]
+fn test_find_txpool_conflicts_ignores_self() {
+    let tx = tx_spending(vec![utxo(1, 0)], 1);
+    let txpool = TxpoolTransactions {
+        transactions: vec![],
+        v2transactions: vec![tx.clone()],
+    };
+    assert!(find_txpool_conflicts(&tx, &txpool).is_empty());
+}
+
+#[test]
+fn test_spendable_filter_max_height() {
+    let utxo = utxo(1, u64::MAX);
+    let mut heights = HashMap::new();
+    heights.insert(utxo.state_element.id, u64::MAX);
+    let filter = SpendableFilter::new(u64::MAX).confirmation_heights(heights);
+
+    assert!(filter.is_mature(&utxo));
+    assert_eq!(filter.confirmations(&utxo), 1);
+}
+
+fn utxos_valued(values: &[u64]) -> Vec<SiacoinElement> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let mut utxo = utxo(i as u8, 0);
+            utxo.siacoin_output.value = (*value).into();
+            utxo
+        })
+        .collect()
+}
+
+fn selected_values(selected: &[SiacoinElement]) -> Vec<u128> {
+    selected.iter().map(|utxo| utxo.siacoin_output.value.0).collect()
+}
+
+#[test]
+fn test_select_utxos_largest_first() {
+    let utxos = utxos_valued(&[5, 20, 1, 10]);
+    let selection = select_utxos(utxos.clone(), 25.into(), SelectionStrategy::LargestFirst).unwrap();
+    assert_eq!(selected_values(&selection.selected), vec![20, 10]);
+    assert_eq!(selection.change, Currency(5));
+    assert_eq!(selection.total(), Currency(30));
+
+    let selection = select_utxos(utxos.clone(), 36.into(), SelectionStrategy::default()).unwrap();
+    assert_eq!(selected_values(&selection.selected), vec![20, 10, 5, 1]);
+    assert_eq!(selection.change, Currency(0));
+
+    match select_utxos(utxos, 37.into(), SelectionStrategy::LargestFirst) {
+        Err(CoinSelectionError::InsufficientFunds { available, required }) => {
+            assert_eq!((available, required), (Currency(36), Currency(37)))
+        },
+        other => panic!("expected InsufficientFunds, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_select_utxos_branch_and_bound() {
+    let utxos = utxos_valued(&[5, 20, 1, 10, 3]);
+
+    // 20 + 5 + 1 matches exactly, largest first would pick 20 + 10
+    let exact = SelectionStrategy::BranchAndBound { tolerance: 0.into() };
+    let selection = select_utxos(utxos.clone(), 26.into(), exact).unwrap();
+    assert_eq!(selected_values(&selection.selected), vec![20, 5, 1]);
+    assert_eq!(selection.change, Currency(0));
+
+    // no exact match for 17, 10 + 5 + 3 exceeds it the least
+    let tolerant = SelectionStrategy::BranchAndBound { tolerance: 3.into() };
+    let selection = select_utxos(utxos.clone(), 17.into(), tolerant).unwrap();
+    assert_eq!(selected_values(&selection.selected), vec![10, 5, 3]);
+    assert_eq!(selection.change, Currency(1));
+
+    // nothing within tolerance, falls back to largest first
+    let selection = select_utxos(utxos_valued(&[20, 10]), 15.into(), exact).unwrap();
+    assert_eq!(selected_values(&selection.selected), vec![20]);
+    assert_eq!(selection.change, Currency(5));
+}
+
+#[test]
+fn test_select_utxos_branch_and_bound_many_outputs() {
+    // deep enough to overflow the stack if the search recursed once per output
+    let utxos = utxos_valued(&[1; 120_000]);
+    let exact = SelectionStrategy::BranchAndBound { tolerance: 0.into() };
+    let selection = select_utxos(utxos, 90_000.into(), exact).unwrap();
+    assert_eq!(selection.selected.len(), 90_000);
+    assert_eq!(selection.change, Currency(0));
+}
@@ -1,18 +1,25 @@
-use crate::encoding::{Encodable, Encoder, HexArray64, PrefixedH256, PrefixedPublicKey, PrefixedSignature, ScoidH256};
+use crate::encoding::{Decodable, Decoder, DecodingError, Encodable, Encoder, HexArray64, PrefixedH256,
+                      PrefixedPublicKey, PrefixedSignature, ScoidH256};
+use crate::specifier::Specifier;
 use crate::spend_policy::{SpendPolicy, SpendPolicyHelper, UnlockCondition, UnlockKey};
-use crate::types::{Address, ChainIndex, H256};
+use crate::types::{Address, ChainIndex, FileContractID, Network, TransactionID, H256};
 use crate::{Keypair, PublicKey, Signature};
 use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use serde_with::{serde_as, FromInto};
+use std::convert::TryFrom;
 use std::fmt;
-use std::ops::Deref;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Deref, Sub, SubAssign};
 use std::str::FromStr;
+use thiserror::Error;
 
+const ASIC_REPLAY_PREFIX: u8 = 1;
 const V2_REPLAY_PREFIX: u8 = 2;
 
-#[derive(Copy, Clone, Debug, Default, PartialEq)]
+/// An amount of hastings, the smallest unit of siacoin. See `Siacoins` for whole SC amounts.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Currency(pub u128);
 
 impl Deref for Currency {
@@ -22,11 +29,11 @@ impl Deref for Currency {
 }
 
 impl Currency {
-    const ZERO: Currency = Currency(0);
+    pub const ZERO: Currency = Currency(0);
 }
 
-// TODO does this also need to be able to deserialize from an integer?
-// walletd API returns this as a string
+// walletd API returns this as a string, integers are accepted as long as they were parsed without loss.
+// JSON numbers that do not fit a u64 are parsed as floats by serde_json and are rejected rather than rounded.
 impl<'de> Deserialize<'de> for Currency {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -47,9 +54,39 @@ impl<'de> Deserialize<'de> for Currency {
             {
                 Ok(Currency(u128::from_str(value).map_err(E::custom)?))
             }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Currency, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Currency(value.into()))
+            }
+
+            fn visit_u128<E>(self, value: u128) -> Result<Currency, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Currency(value))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Currency, E>
+            where
+                E: serde::de::Error,
+            {
+                u64::try_from(value)
+                    .map(|value| Currency(value.into()))
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Currency, E>
+            where
+                E: serde::de::Error,
+            {
+                Err(E::invalid_type(serde::de::Unexpected::Float(value), &self))
+            }
         }
 
-        deserializer.deserialize_str(CurrencyVisitor)
+        deserializer.deserialize_any(CurrencyVisitor)
     }
 }
 
@@ -58,7 +95,7 @@ impl Serialize for Currency {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        serializer.serialize_str(&self.0.to_string())
     }
 }
 
@@ -74,6 +111,216 @@ impl From<u128> for Currency {
     fn from(value: u128) -> Self { Currency(value) }
 }
 
+/// Hastings per siacoin, 10^24
+pub const HASTINGS_PER_SIACOIN: u128 = 1_000_000_000_000_000_000_000_000;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CurrencyError {
+    #[error("CurrencyError error: amount overflows u128 hastings")]
+    Overflow,
+    #[error("CurrencyError error: invalid siacoin amount {0:?}")]
+    InvalidAmount(String),
+    #[error("CurrencyError error: {0:?} has more than 24 decimal places")]
+    TooPrecise(String),
+}
+
+impl Currency {
+    pub fn checked_add(self, other: Currency) -> Option<Currency> { self.0.checked_add(other.0).map(Currency) }
+
+    pub fn checked_sub(self, other: Currency) -> Option<Currency> { self.0.checked_sub(other.0).map(Currency) }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Currency> { self.0.checked_mul(factor.into()).map(Currency) }
+}
+
+/// The amount in SC, eg. `1.5 SC`
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{} SC", Siacoins::from(*self)) }
+}
+
+/// Parses SC with the `SC` suffix, eg. `1.5 SC`, or hastings either bare or with the `H` suffix, eg. `100 H`
+impl FromStr for Currency {
+    type Err = CurrencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(sc) = s.strip_suffix("SC") {
+            return Siacoins::from_str(sc.trim_end()).map(Currency::from);
+        }
+        let hastings = s.strip_suffix('H').map(str::trim_end).unwrap_or(s);
+        if hastings.is_empty() || !hastings.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(CurrencyError::InvalidAmount(s.to_owned()));
+        }
+        u128::from_str(hastings)
+            .map(Currency)
+            .map_err(|_| CurrencyError::Overflow)
+    }
+}
+
+/// Panics on overflow whatever the build profile, use `checked_add` where the amounts are not trusted
+impl Add for Currency {
+    type Output = Currency;
+
+    fn add(self, other: Currency) -> Currency {
+        self.checked_add(other)
+            .unwrap_or_else(|| panic!("Currency overflow: {:?} + {:?}", self, other))
+    }
+}
+
+/// Panics on underflow whatever the build profile, use `checked_sub` where the amounts are not trusted
+impl Sub for Currency {
+    type Output = Currency;
+
+    fn sub(self, other: Currency) -> Currency {
+        self.checked_sub(other)
+            .unwrap_or_else(|| panic!("Currency underflow: {:?} - {:?}", self, other))
+    }
+}
+
+impl AddAssign for Currency {
+    fn add_assign(&mut self, other: Currency) { *self = *self + other }
+}
+
+impl SubAssign for Currency {
+    fn sub_assign(&mut self, other: Currency) { *self = *self - other }
+}
+
+impl Sum for Currency {
+    fn sum<I: Iterator<Item = Currency>>(iter: I) -> Currency { iter.fold(Currency::ZERO, Add::add) }
+}
+
+/// An amount of siacoins.
+///
+/// Amounts in SC and in hastings are distinct types that only combine with their own unit, so mixing them up,
+/// eg. passing a whole SC amount where hastings are expected, does not compile. Converting between them goes
+/// through the lossless `From` impls, which are the only place the 10^24 scaling happens.
+/// ```compile_fail
+/// use sia_rust::transaction::{Currency, Siacoins};
+///
+/// let fee = Currency(10_000_000_000_000_000_000);
+/// let total = Siacoins::new(1).unwrap() + fee;
+/// ```
+/// ```
+/// use sia_rust::transaction::{Currency, Siacoins};
+///
+/// let fee = Currency(10_000_000_000_000_000_000);
+/// let total = Currency::from(Siacoins::new(1).unwrap()) + fee;
+/// assert_eq!(Siacoins::from(total).to_string(), "1.00001");
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Siacoins {
+    // stored as hastings so fractional amounts round-trip exactly
+    hastings: u128,
+}
+
+impl Siacoins {
+    pub const ZERO: Siacoins = Siacoins { hastings: 0 };
+
+    /// `sc` whole siacoins, fails if the amount does not fit in u128 hastings
+    pub fn new(sc: u64) -> Result<Self, CurrencyError> {
+        (sc as u128)
+            .checked_mul(HASTINGS_PER_SIACOIN)
+            .map(|hastings| Siacoins { hastings })
+            .ok_or(CurrencyError::Overflow)
+    }
+
+    pub fn to_hastings(self) -> Currency { Currency(self.hastings) }
+
+    pub fn checked_add(self, other: Siacoins) -> Option<Siacoins> {
+        self.hastings
+            .checked_add(other.hastings)
+            .map(|hastings| Siacoins { hastings })
+    }
+
+    pub fn checked_sub(self, other: Siacoins) -> Option<Siacoins> {
+        self.hastings
+            .checked_sub(other.hastings)
+            .map(|hastings| Siacoins { hastings })
+    }
+}
+
+impl From<Siacoins> for Currency {
+    fn from(value: Siacoins) -> Self { value.to_hastings() }
+}
+
+impl From<Currency> for Siacoins {
+    fn from(value: Currency) -> Self { Siacoins { hastings: value.0 } }
+}
+
+/// Panics on overflow whatever the build profile, use `checked_add` where the amounts are not trusted
+impl Add for Siacoins {
+    type Output = Siacoins;
+
+    fn add(self, other: Siacoins) -> Siacoins {
+        self.checked_add(other)
+            .unwrap_or_else(|| panic!("Siacoins overflow: {} + {}", self, other))
+    }
+}
+
+/// Panics on underflow whatever the build profile, use `checked_sub` where the amounts are not trusted
+impl Sub for Siacoins {
+    type Output = Siacoins;
+
+    fn sub(self, other: Siacoins) -> Siacoins {
+        self.checked_sub(other)
+            .unwrap_or_else(|| panic!("Siacoins underflow: {} - {}", self, other))
+    }
+}
+
+impl AddAssign for Siacoins {
+    fn add_assign(&mut self, other: Siacoins) { *self = *self + other }
+}
+
+impl SubAssign for Siacoins {
+    fn sub_assign(&mut self, other: Siacoins) { *self = *self - other }
+}
+
+impl Sum for Siacoins {
+    fn sum<I: Iterator<Item = Siacoins>>(iter: I) -> Siacoins { iter.fold(Siacoins::ZERO, Add::add) }
+}
+
+/// Decimal SC with trailing zeros trimmed, eg. `1.5`
+impl fmt::Display for Siacoins {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let whole = self.hastings / HASTINGS_PER_SIACOIN;
+        let fraction = self.hastings % HASTINGS_PER_SIACOIN;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let fraction = format!("{:024}", fraction);
+        write!(f, "{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+}
+
+/// Parses decimal SC such as `1.5` or `0.000001`, rejecting amounts finer than one hasting
+impl FromStr for Siacoins {
+    type Err = CurrencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CurrencyError::InvalidAmount(s.to_owned());
+        let (whole, fraction) = match s.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (s, ""),
+        };
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) || (s.contains('.') && fraction.is_empty()) {
+            return Err(invalid());
+        }
+        if fraction.len() > 24 {
+            return Err(CurrencyError::TooPrecise(s.to_owned()));
+        }
+        let whole = u128::from_str(whole).map_err(|_| CurrencyError::Overflow)?;
+        let fraction = match fraction {
+            "" => 0,
+            fraction => u128::from_str(&format!("{:0<24}", fraction)).map_err(|_| invalid())?,
+        };
+        whole
+            .checked_mul(HASTINGS_PER_SIACOIN)
+            .and_then(|hastings| hastings.checked_add(fraction))
+            .map(|hastings| Siacoins { hastings })
+            .ok_or(CurrencyError::Overflow)
+    }
+}
+
 // Currency remains the same data structure between V1 and V2 however the encoding changes
 #[derive(Clone, Debug)]
 pub enum CurrencyVersion<'a> {
@@ -124,6 +371,18 @@ impl Encodable for Signature {
     fn encode(&self, encoder: &mut Encoder) { encoder.write_slice(&self.to_bytes()); }
 }
 
+impl Decodable for Signature {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        let bytes: [u8; 64] = decoder.read_array()?;
+        Signature::from_bytes(&bytes).map_err(|e| DecodingError::InvalidValue(e.to_string()))
+    }
+}
+
+// only the v2 encoding is self-delimiting, so that is the one decoded, see `CurrencyVersion`
+impl Decodable for Currency {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> { Ok(Currency(decoder.read_u128()?)) }
+}
+
 impl Encodable for SatisfiedPolicy {
     fn encode(&self, encoder: &mut Encoder) {
         self.policy.encode(encoder);
@@ -173,6 +432,41 @@ impl Encodable for SatisfiedPolicy {
     }
 }
 
+// the number of signatures and preimages is implied by the policy, mirroring `SatisfiedPolicy::encode`
+impl Decodable for SatisfiedPolicy {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        fn rec(policy: &SpendPolicy, decoder: &mut Decoder, sp: &mut SatisfiedPolicy) -> Result<(), DecodingError> {
+            match policy {
+                SpendPolicy::PublicKey(_) => sp.signatures.push(Signature::decode(decoder)?),
+                SpendPolicy::Hash(_) => sp.preimages.push(decoder.read_len_prefixed_bytes()?),
+                SpendPolicy::Threshold { n: _, of } => {
+                    for p in of {
+                        rec(p, decoder, sp)?;
+                    }
+                },
+                SpendPolicy::UnlockConditions(uc) => {
+                    for unlock_key in &uc.unlock_keys {
+                        if let UnlockKey::Ed25519(public_key) = unlock_key {
+                            rec(&SpendPolicy::PublicKey(*public_key), decoder, sp)?;
+                        }
+                    }
+                },
+                _ => {},
+            }
+            Ok(())
+        }
+
+        let policy = SpendPolicy::decode(decoder)?;
+        let mut satisfied_policy = SatisfiedPolicy {
+            policy: policy.clone(),
+            signatures: Vec::new(),
+            preimages: Vec::new(),
+        };
+        rec(&policy, decoder, &mut satisfied_policy)?;
+        Ok(satisfied_policy)
+    }
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -204,6 +498,20 @@ impl Encodable for StateElement {
     }
 }
 
+// an empty proof is encoded the same as a missing one and decodes as `None`
+impl Decodable for StateElement {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        let id = H256::decode(decoder)?;
+        let leaf_index = decoder.read_u64()?;
+        let merkle_proof: Vec<H256> = decoder.read_len_prefixed_vec()?;
+        Ok(StateElement {
+            id,
+            leaf_index,
+            merkle_proof: (!merkle_proof.is_empty()).then_some(merkle_proof),
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SiafundElement {
@@ -213,6 +521,28 @@ pub struct SiafundElement {
     pub claim_start: Currency,
 }
 
+/// Number of siafunds in existence, the siafund pool is shared out in these units
+pub const SIAFUND_COUNT: u64 = 10_000;
+
+impl SiafundElement {
+    /// Siacoins claimed when spending this output, given the `siafund_pool` of the consensus state the spending
+    /// transaction is included in, see `ConsensusState::siafund_pool`.
+    ///
+    /// The claim is paid to the `claim_address` of the input as a siacoin output that matures like a miner payout.
+    pub fn claim_value(&self, siafund_pool: Currency) -> Currency {
+        let per_siafund = siafund_pool.0.saturating_sub(self.claim_start.0) / SIAFUND_COUNT as u128;
+        Currency(per_siafund.saturating_mul(self.siafund_output.value as u128))
+    }
+
+    /// ID of the siacoin output created for the claim when this output is spent by a v2 transaction
+    pub fn v2_claim_output_id(&self) -> H256 {
+        let mut encoder = Encoder::default();
+        encoder.write_distinguisher("id/v2siafundclaimoutput");
+        self.state_element.id.encode(&mut encoder);
+        encoder.hash()
+    }
+}
+
 impl Encodable for SiafundElement {
     fn encode(&self, encoder: &mut Encoder) {
         self.state_element.encode(encoder);
@@ -221,6 +551,16 @@ impl Encodable for SiafundElement {
     }
 }
 
+impl Decodable for SiafundElement {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiafundElement {
+            state_element: StateElement::decode(decoder)?,
+            siafund_output: SiafundOutput::decode(decoder)?,
+            claim_start: Currency::decode(decoder)?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SiacoinElement {
@@ -238,6 +578,16 @@ impl Encodable for SiacoinElement {
     }
 }
 
+impl Decodable for SiacoinElement {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiacoinElement {
+            state_element: StateElement::decode(decoder)?,
+            siacoin_output: SiacoinOutput::decode(decoder)?,
+            maturity_height: decoder.read_u64()?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SiafundInputV2 {
@@ -254,6 +604,16 @@ impl Encodable for SiafundInputV2 {
     }
 }
 
+impl Decodable for SiafundInputV2 {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiafundInputV2 {
+            parent: SiafundElement::decode(decoder)?,
+            claim_address: Address::decode(decoder)?,
+            satisfied_policy: SatisfiedPolicy::decode(decoder)?,
+        })
+    }
+}
+
 // https://github.com/SiaFoundation/core/blob/6c19657baf738c6b730625288e9b5413f77aa659/types/types.go#L197-L198
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -286,6 +646,15 @@ impl Encodable for SiacoinInputV2 {
     }
 }
 
+impl Decodable for SiacoinInputV2 {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiacoinInputV2 {
+            parent: SiacoinElement::decode(decoder)?,
+            satisfied_policy: SatisfiedPolicy::decode(decoder)?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct SiafundOutput {
     pub value: u64,
@@ -314,6 +683,16 @@ impl<'a> Encodable for SiafundOutputVersion<'a> {
     }
 }
 
+/// Decodes the v2 encoding, see `SiafundOutputVersion::V2`
+impl Decodable for SiafundOutput {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiafundOutput {
+            value: decoder.read_u64()?,
+            address: Address::decode(decoder)?,
+        })
+    }
+}
+
 // SiacoinOutput remains the same data structure between V1 and V2 however the encoding changes
 #[derive(Clone, Debug)]
 pub enum SiacoinOutputVersion<'a> {
@@ -336,12 +715,44 @@ impl<'a> Encodable for SiacoinOutputVersion<'a> {
     }
 }
 
+/// Decodes the v2 encoding, see `SiacoinOutputVersion::V2`
+impl Decodable for SiacoinOutput {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(SiacoinOutput {
+            value: Currency::decode(decoder)?,
+            address: Address::decode(decoder)?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct SiacoinOutput {
     pub value: Currency,
     pub address: Address,
 }
 
+impl SiacoinOutput {
+    /// ID of the `index`th siacoin output created by the v2 transaction `txid`.
+    /// For v1 transactions see `V1Transaction::siacoin_output_id`.
+    pub fn id(txid: TransactionID, index: u64) -> H256 { v2_output_id("id/siacoinoutput", txid, index) }
+}
+
+fn v2_output_id(distinguisher: &str, txid: TransactionID, index: u64) -> H256 {
+    let mut encoder = Encoder::default();
+    encoder.write_distinguisher(distinguisher);
+    txid.encode(&mut encoder);
+    encoder.write_u64(index);
+    encoder.hash()
+}
+
+fn v1_output_id(specifier: Specifier, tx: &V1Transaction, index: u64) -> H256 {
+    let mut encoder = Encoder::default();
+    specifier.encode(&mut encoder);
+    V1TransactionSansSigs(tx.clone()).encode(&mut encoder);
+    encoder.write_u64(index);
+    encoder.hash()
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
@@ -405,6 +816,39 @@ impl<'de> Deserialize<'de> for V1Signature {
     }
 }
 
+impl Encodable for CoveredFields {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_bool(self.whole_transaction);
+        for indices in [
+            &self.siacoin_inputs,
+            &self.siacoin_outputs,
+            &self.file_contracts,
+            &self.file_contract_revisions,
+            &self.storage_proofs,
+            &self.siafund_inputs,
+            &self.siafund_outputs,
+            &self.miner_fees,
+            &self.arbitrary_data,
+            &self.signatures,
+        ]
+        .iter()
+        {
+            encoder.write_u64(indices.len() as u64);
+            indices.iter().for_each(|index| encoder.write_u64(*index));
+        }
+    }
+}
+
+impl Encodable for TransactionSignature {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.parent_id.encode(encoder);
+        encoder.write_u64(self.public_key_index);
+        encoder.write_u64(self.timelock);
+        self.covered_fields.encode(encoder);
+        encoder.write_len_prefixed_bytes(&self.signature.0);
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct FileContract {
     pub filesize: u64,
@@ -563,7 +1007,6 @@ impl Encodable for StorageProof {
 }
 
 type SiafundOutputID = H256;
-type FileContractID = H256;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct FileContractRevision {
@@ -815,22 +1258,26 @@ impl Encodable for ChainIndexElement {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileContractElementV1 {
     #[serde(flatten)]
     pub state_element: StateElement,
     pub file_contract: FileContractV1,
 }
 
+#[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FileContractV1 {
     pub filesize: u64,
+    #[serde_as(as = "FromInto<PrefixedH256>")]
     pub file_merkle_root: H256,
     pub window_start: u64,
     pub window_end: u64,
     pub payout: Currency,
     pub valid_proof_outputs: Vec<SiacoinOutput>,
     pub missed_proof_outputs: Vec<SiacoinOutput>,
-    pub unlock_hash: H256,
+    pub unlock_hash: Address,
     pub revision_number: u64,
 }
 
@@ -869,8 +1316,197 @@ pub struct V1Transaction {
     pub signatures: Vec<TransactionSignature>,
 }
 
+/// Written before each signed input of a v1 sig hash, so a signature is only valid on the side of the hardforks it
+/// was made for. `State.replayPrefix` in Go.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReplayPrefix {
+    /// Before the ASIC hardfork, nothing is written
+    None,
+    Asic,
+    V2,
+}
+
+impl ReplayPrefix {
+    /// The prefix of transactions in the block at `child_height` of `network`. Like Go, this is the height of the
+    /// block the transaction is included in, ie. the parent state's `childHeight`, not the height of the current tip.
+    pub fn at_height(network: &Network, child_height: u64) -> Self {
+        if child_height >= network.hardfork_v2.allow_height {
+            ReplayPrefix::V2
+        } else if child_height >= network.hardfork_asic.height {
+            ReplayPrefix::Asic
+        } else {
+            ReplayPrefix::None
+        }
+    }
+
+    /// The prefix of transactions in the block after `tip`, ie. the next block to be mined
+    pub fn after_tip(network: &Network, tip: &ChainIndex) -> Self {
+        ReplayPrefix::at_height(network, tip.height.saturating_add(1))
+    }
+}
+
+impl Encodable for ReplayPrefix {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            ReplayPrefix::None => (),
+            ReplayPrefix::Asic => encoder.write_u8(ASIC_REPLAY_PREFIX),
+            ReplayPrefix::V2 => encoder.write_u8(V2_REPLAY_PREFIX),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SigHashError {
+    #[error("SigHashError: covered {field} index {index} is out of range")]
+    IndexOutOfRange { field: &'static str, index: u64 },
+}
+
+/// The elements of `items` at `indices`, in the order of `indices`
+fn covered<'a, T>(items: &'a [T], indices: &[u64], field: &'static str) -> Result<Vec<&'a T>, SigHashError> {
+    indices
+        .iter()
+        .map(|index| {
+            usize::try_from(*index)
+                .ok()
+                .and_then(|i| items.get(i))
+                .ok_or(SigHashError::IndexOutOfRange { field, index: *index })
+        })
+        .collect()
+}
+
 impl V1Transaction {
-    pub fn txid(&self) -> H256 { Encoder::encode_and_hash(&V1TransactionSansSigs(self.clone())) }
+    pub fn txid(&self) -> TransactionID {
+        TransactionID(Encoder::encode_and_hash(&V1TransactionSansSigs(self.clone())))
+    }
+
+    /// The hash `signature` signs, depending on whether it covers the whole transaction.
+    /// `replay_prefix` is that of the block the transaction is included in, see `ReplayPrefix::after_tip`.
+    pub fn sig_hash(
+        &self,
+        signature: &TransactionSignature,
+        replay_prefix: ReplayPrefix,
+    ) -> Result<H256, SigHashError> {
+        if signature.covered_fields.whole_transaction {
+            self.whole_sig_hash(signature, replay_prefix)
+        } else {
+            self.partial_sig_hash(&signature.covered_fields, replay_prefix)
+        }
+    }
+
+    /// The hash signed by a signature with `CoveredFields::whole_transaction` set, `State.WholeSigHash` in Go.
+    ///
+    /// Covers every field but the signatures, then the parent ID, public key index and timelock of `signature`
+    /// and the other signatures listed in its `CoveredFields::signatures`. The signature bytes of `signature`
+    /// itself are not covered, so it can be passed before it is signed.
+    pub fn whole_sig_hash(
+        &self,
+        signature: &TransactionSignature,
+        replay_prefix: ReplayPrefix,
+    ) -> Result<H256, SigHashError> {
+        let covered_signatures = covered(&self.signatures, &signature.covered_fields.signatures, "signatures")?;
+
+        let mut encoder = Encoder::default();
+        encoder.write_u64(self.siacoin_inputs.len() as u64);
+        for input in &self.siacoin_inputs {
+            replay_prefix.encode(&mut encoder);
+            input.encode(&mut encoder);
+        }
+        encoder.write_u64(self.siacoin_outputs.len() as u64);
+        for output in &self.siacoin_outputs {
+            SiacoinOutputVersion::V1(output).encode(&mut encoder);
+        }
+        encoder.write_len_prefixed_vec(&self.file_contracts);
+        encoder.write_len_prefixed_vec(&self.file_contract_revisions);
+        encoder.write_len_prefixed_vec(&self.storage_proofs);
+        encoder.write_u64(self.siafund_inputs.len() as u64);
+        for input in &self.siafund_inputs {
+            replay_prefix.encode(&mut encoder);
+            input.encode(&mut encoder);
+        }
+        encoder.write_u64(self.siafund_outputs.len() as u64);
+        for output in &self.siafund_outputs {
+            SiafundOutputVersion::V1(output).encode(&mut encoder);
+        }
+        encoder.write_u64(self.miner_fees.len() as u64);
+        for fee in &self.miner_fees {
+            CurrencyVersion::V1(fee).encode(&mut encoder);
+        }
+        let arbitrary_data = self
+            .arbitrary_data
+            .as_ref()
+            .map_or(&[][..], |data| data.data.as_slice());
+        encoder.write_u64(arbitrary_data.len() as u64);
+        for data in arbitrary_data {
+            encoder.write_len_prefixed_bytes(data);
+        }
+
+        signature.parent_id.encode(&mut encoder);
+        encoder.write_u64(signature.public_key_index);
+        encoder.write_u64(signature.timelock);
+        for covered_signature in covered_signatures {
+            covered_signature.encode(&mut encoder);
+        }
+        Ok(encoder.hash())
+    }
+
+    /// The hash signed by a signature covering only `covered_fields`, `State.PartialSigHash` in Go.
+    ///
+    /// Covers the elements at the listed indices of each field, in the order they are listed, without any length
+    /// prefixes.
+    pub fn partial_sig_hash(
+        &self,
+        covered_fields: &CoveredFields,
+        replay_prefix: ReplayPrefix,
+    ) -> Result<H256, SigHashError> {
+        let cf = covered_fields;
+        let mut encoder = Encoder::default();
+        for input in covered(&self.siacoin_inputs, &cf.siacoin_inputs, "siacoin_inputs")? {
+            replay_prefix.encode(&mut encoder);
+            input.encode(&mut encoder);
+        }
+        for output in covered(&self.siacoin_outputs, &cf.siacoin_outputs, "siacoin_outputs")? {
+            SiacoinOutputVersion::V1(output).encode(&mut encoder);
+        }
+        for contract in covered(&self.file_contracts, &cf.file_contracts, "file_contracts")? {
+            contract.encode(&mut encoder);
+        }
+        for revision in covered(
+            &self.file_contract_revisions,
+            &cf.file_contract_revisions,
+            "file_contract_revisions",
+        )? {
+            revision.encode(&mut encoder);
+        }
+        for proof in covered(&self.storage_proofs, &cf.storage_proofs, "storage_proofs")? {
+            proof.encode(&mut encoder);
+        }
+        for input in covered(&self.siafund_inputs, &cf.siafund_inputs, "siafund_inputs")? {
+            replay_prefix.encode(&mut encoder);
+            input.encode(&mut encoder);
+        }
+        for output in covered(&self.siafund_outputs, &cf.siafund_outputs, "siafund_outputs")? {
+            SiafundOutputVersion::V1(output).encode(&mut encoder);
+        }
+        for fee in covered(&self.miner_fees, &cf.miner_fees, "miner_fees")? {
+            CurrencyVersion::V1(fee).encode(&mut encoder);
+        }
+        let arbitrary_data = self
+            .arbitrary_data
+            .as_ref()
+            .map_or(&[][..], |data| data.data.as_slice());
+        for data in covered(arbitrary_data, &cf.arbitrary_data, "arbitrary_data")? {
+            encoder.write_len_prefixed_bytes(data);
+        }
+        for signature in covered(&self.signatures, &cf.signatures, "signatures")? {
+            signature.encode(&mut encoder);
+        }
+        Ok(encoder.hash())
+    }
+
+    /// ID of the `index`th siacoin output, known before the transaction is broadcast
+    pub fn siacoin_output_id(&self, index: u64) -> H256 { v1_output_id(Specifier::SiacoinOutput, self, index) }
+
+    pub fn siafund_output_id(&self, index: u64) -> H256 { v1_output_id(Specifier::SiafundOutput, self, index) }
 }
 
 impl Encodable for SiafundInputV1 {
@@ -964,12 +1600,18 @@ impl V2Transaction {
         encoder.hash()
     }
 
-    pub fn txid(&self) -> H256 {
+    pub fn txid(&self) -> TransactionID {
         let mut encoder = Encoder::default();
         encoder.write_distinguisher("id/transaction");
         self.encode(&mut encoder);
-        encoder.hash()
+        TransactionID(encoder.hash())
     }
+
+    /// ID of the `index`th siacoin output, known before the transaction is broadcast. Signatures are not
+    /// covered, so the ID is stable from the moment the outputs and inputs are final.
+    pub fn siacoin_output_id(&self, index: u64) -> H256 { SiacoinOutput::id(self.txid(), index) }
+
+    pub fn siafund_output_id(&self, index: u64) -> H256 { v2_output_id("id/siafundoutput", self.txid(), index) }
 }
 
 // this encoding corresponds to the Go implementation's "V2TransactionSemantics" rather than "V2Transaction"
@@ -1029,6 +1671,26 @@ impl Encodable for V2Transaction {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum V2TransactionBuilderError {
+    #[error("V2TransactionBuilderError error: signing thread panicked")]
+    SigningThreadPanicked,
+    #[error(
+        "V2TransactionBuilderError error: inputs total {} hastings, outputs and miner fee require {}",
+        .available.0,
+        .required.0
+    )]
+    InsufficientFunds { available: Currency, required: Currency },
+    #[error("V2TransactionBuilderError error: total amount overflows u128 hastings")]
+    AmountOverflow,
+    #[error(
+        "V2TransactionBuilderError error: siafund inputs total {} siafunds, outputs require {}",
+        .available,
+        .required
+    )]
+    InsufficientSiafunds { available: u64, required: u64 },
+}
+
 pub struct V2TransactionBuilder {
     siacoin_inputs: Vec<SiacoinInputV2>,
     siacoin_outputs: Vec<SiacoinOutput>,
@@ -1198,11 +1860,121 @@ impl V2TransactionBuilder {
         self
     }
 
+    /// Attach `satisfied_policy` to every siacoin input spending an output of `address`.
+    ///
+    /// For policies `sign_simple` can not satisfy, eg. thresholds requiring a preimage. The signatures must be
+    /// made over `input_sig_hash` once all inputs and outputs are added.
+    pub fn satisfy_siacoin_inputs(mut self, address: &Address, satisfied_policy: SatisfiedPolicy) -> Self {
+        for si in &mut self.siacoin_inputs {
+            if si.parent.siacoin_output.address == *address {
+                si.satisfied_policy = satisfied_policy.clone();
+            }
+        }
+        self
+    }
+
+    /// Spend the siafund output `parent`. The siacoins it accrued since `parent.claim_start` are paid out to
+    /// `claim_address`, see `SiafundElement::claim_value`.
+    pub fn add_siafund_input(mut self, parent: SiafundElement, policy: SpendPolicy, claim_address: Address) -> Self {
+        self.siafund_inputs.push(SiafundInputV2 {
+            parent,
+            claim_address,
+            satisfied_policy: SatisfiedPolicy {
+                policy,
+                signatures: Vec::new(),
+                preimages: Vec::new(),
+            },
+        });
+        self
+    }
+
+    /// Siafund counterpart of `satisfy_siacoin_inputs`
+    pub fn satisfy_siafund_inputs(mut self, address: &Address, satisfied_policy: SatisfiedPolicy) -> Self {
+        for si in &mut self.siafund_inputs {
+            if si.parent.siafund_output.address == *address {
+                si.satisfied_policy = satisfied_policy.clone();
+            }
+        }
+        self
+    }
+
     pub fn add_siacoin_output(mut self, output: SiacoinOutput) -> Self {
         self.siacoin_outputs.push(output);
         self
     }
 
+    /// Send whatever the siacoin inputs hold beyond the outputs and miner fee back to `address`.
+    ///
+    /// Must be called after all inputs, outputs and the miner fee are added. No output is added if the inputs are
+    /// spent exactly, and the builder is rejected if they do not cover the outputs and fee.
+    pub fn add_change_output(self, address: Address) -> Result<Self, V2TransactionBuilderError> {
+        let available = self
+            .siacoin_inputs
+            .iter()
+            .map(|si| si.parent.siacoin_output.value)
+            .try_fold(Currency::ZERO, Currency::checked_add);
+        let required = self
+            .siacoin_outputs
+            .iter()
+            .map(|so| so.value)
+            .try_fold(self.miner_fee, Currency::checked_add);
+        let (available, required) = available
+            .zip(required)
+            .ok_or(V2TransactionBuilderError::AmountOverflow)?;
+        match available.checked_sub(required) {
+            Some(change) if change.0 == 0 => Ok(self),
+            Some(change) => Ok(self.add_siacoin_output(SiacoinOutput { value: change, address })),
+            None => Err(V2TransactionBuilderError::InsufficientFunds { available, required }),
+        }
+    }
+
+    pub fn add_siafund_output(mut self, output: SiafundOutput) -> Self {
+        self.siafund_outputs.push(output);
+        self
+    }
+
+    /// Send whatever the siafund inputs hold beyond the siafund outputs back to `address`.
+    ///
+    /// Siafunds must be spent exactly, so this must be called after all siafund inputs and outputs are added. The
+    /// claims of the inputs are siacoins and do not count towards the siafunds.
+    pub fn add_siafund_change_output(self, address: Address) -> Result<Self, V2TransactionBuilderError> {
+        let available = self
+            .siafund_inputs
+            .iter()
+            .try_fold(0u64, |total, si| total.checked_add(si.parent.siafund_output.value));
+        let required = self
+            .siafund_outputs
+            .iter()
+            .try_fold(0u64, |total, so| total.checked_add(so.value));
+        let (available, required) = available
+            .zip(required)
+            .ok_or(V2TransactionBuilderError::AmountOverflow)?;
+        match available.checked_sub(required) {
+            Some(0) => Ok(self),
+            Some(change) => Ok(self.add_siafund_output(SiafundOutput { value: change, address })),
+            None => Err(V2TransactionBuilderError::InsufficientSiafunds { available, required }),
+        }
+    }
+
+    /// Sort inputs by parent ID and outputs by their encoding so that builders fed the same inputs and
+    /// outputs in any order produce the same transaction and txid. This changes the signature hash,
+    /// so it must be called before signing. Output indices, and therefore output IDs, change as well.
+    pub fn canonical_sort(mut self) -> Self {
+        fn encoded<T: Encodable>(item: &T) -> Vec<u8> {
+            let mut encoder = Encoder::default();
+            item.encode(&mut encoder);
+            encoder.buffer
+        }
+
+        self.siacoin_inputs.sort_by_key(|si| si.parent.state_element.id);
+        self.siacoin_outputs
+            .sort_by_cached_key(|so| encoded(&SiacoinOutputVersion::V2(so)));
+        self.siafund_inputs.sort_by_key(|si| si.parent.state_element.id);
+        self.siafund_outputs
+            .sort_by_cached_key(|so| encoded(&SiafundOutputVersion::V2(so)));
+        self
+    }
+
     pub fn input_sig_hash(&self) -> H256 {
         let mut encoder = Encoder::default();
         encoder.write_distinguisher("sig/input");
@@ -1213,28 +1985,72 @@ impl V2TransactionBuilder {
 
     // Sign all PublicKey or UnlockConditions policies with the provided keypairs
     // Incapable of handling threshold policies
-    pub fn sign_simple(mut self, keypairs: Vec<&Keypair>) -> Result<Self, String> {
+    pub fn sign_simple(mut self, keypairs: Vec<&Keypair>) -> Result<Self, V2TransactionBuilderError> {
         let sig_hash = self.input_sig_hash();
         for keypair in keypairs {
             let sig = keypair.sign(&sig_hash.0);
-            for si in &mut self.siacoin_inputs {
-                match &si.satisfied_policy.policy {
-                    SpendPolicy::PublicKey(pk) if pk == &keypair.public() => si.satisfied_policy.signatures.push(sig),
-                    SpendPolicy::UnlockConditions(uc) => {
-                        for p in &uc.unlock_keys {
-                            match p {
-                                UnlockKey::Ed25519(pk) if pk == &keypair.public() => {
-                                    si.satisfied_policy.signatures.push(sig)
-                                },
-                                _ => (),
-                            }
+            self.add_signature(&keypair.public(), sig);
+        }
+        Ok(self)
+    }
+
+    /// Equivalent to `sign_simple`, but the signatures are produced on up to `max_threads` threads.
+    ///
+    /// All inputs of a v2 transaction share a single signature hash, so the work is split by keypair. This is
+    /// worthwhile when spending the outputs of many distinct keys, eg. when sweeping or consolidating an HD wallet.
+    /// Signatures are applied in the order of `keypairs`, so the result is identical to `sign_simple`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sign_simple_parallel(
+        mut self,
+        keypairs: Vec<&Keypair>,
+        max_threads: usize,
+    ) -> Result<Self, V2TransactionBuilderError> {
+        let sig_hash = self.input_sig_hash();
+        let threads = max_threads.max(1);
+        let chunk_size = keypairs.len().saturating_sub(1) / threads + 1;
+        let sigs: Vec<Signature> = std::thread::scope(|scope| {
+            let handles: Vec<_> = keypairs
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk.iter().map(|kp| kp.sign(&sig_hash.0)).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .map_err(|_| V2TransactionBuilderError::SigningThreadPanicked)
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|chunks| chunks.into_iter().flatten().collect())
+        })?;
+        for (keypair, sig) in keypairs.iter().zip(sigs) {
+            self.add_signature(&keypair.public(), sig);
+        }
+        Ok(self)
+    }
+
+    // Attach `sig` to every siacoin and siafund input with a PublicKey or UnlockConditions policy that includes
+    // `public_key`
+    fn add_signature(&mut self, public_key: &PublicKey, sig: Signature) {
+        let satisfied_policies = self
+            .siacoin_inputs
+            .iter_mut()
+            .map(|si| &mut si.satisfied_policy)
+            .chain(self.siafund_inputs.iter_mut().map(|si| &mut si.satisfied_policy));
+        for satisfied_policy in satisfied_policies {
+            match &satisfied_policy.policy {
+                SpendPolicy::PublicKey(pk) if pk == public_key => satisfied_policy.signatures.push(sig),
+                SpendPolicy::UnlockConditions(uc) => {
+                    for p in &uc.unlock_keys {
+                        match p {
+                            UnlockKey::Ed25519(pk) if pk == public_key => satisfied_policy.signatures.push(sig),
+                            _ => (),
                         }
-                    },
-                    _ => (),
-                }
+                    }
+                },
+                _ => (),
             }
         }
-        Ok(self)
     }
 
     pub fn build(self) -> V2Transaction {
@@ -1,27 +1,30 @@
 use crate::blake2b_internal::standard_unlock_hash;
-use crate::encoding::{Encodable, Encoder, PrefixedH256};
+use crate::encoding::{Decodable, Decoder, DecodingError, Encodable, Encoder, PrefixedH256};
 pub use crate::hash::H256;
-pub use crate::transaction::Currency;
-use crate::transaction::{FileContractElementV1, SiacoinElement, SiafundElement, StateElement, V1Transaction,
-                         V2FileContractResolution, V2Transaction};
+pub use crate::spend_policy::{SpendPolicy, UnlockCondition};
+pub use crate::transaction::{Currency, Siacoins};
+use crate::transaction::{FileContractElementV1, SiacoinElement, SiacoinOutput, SiafundElement, StateElement,
+                         V1Transaction, V2FileContractResolution, V2Transaction};
 use crate::PublicKey;
 use blake2b_simd::Params;
 use chrono::{DateTime, Utc};
 use hex::FromHexError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use serde_with::{serde_as, FromInto};
+use serde_with::{serde_as, DefaultOnNull, FromInto};
 use std::convert::From;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::str::FromStr;
+use thiserror::Error;
 
+const ADDRESS_PREFIX: &str = "addr:";
 const ADDRESS_HASH_LENGTH: usize = 32;
 const ADDRESS_CHECKSUM_LENGTH: usize = 6;
 
 // TODO this could probably include the checksum within the data type
 // generating the checksum on the fly is how Sia Go does this however
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct Address(pub H256);
 
 impl Serialize for Address {
@@ -52,7 +55,7 @@ impl<'de> Deserialize<'de> for Address {
             where
                 E: serde::de::Error,
             {
-                Address::from_str(value).map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
+                Address::from_str(value).map_err(|e| E::custom(format!("invalid address {:?}: {}", value, e)))
             }
         }
 
@@ -61,6 +64,14 @@ impl<'de> Deserialize<'de> for Address {
 }
 
 impl Address {
+    /// The address of an unlock hash, eg. one computed by a v1 wallet. Sia addresses do not encode the network, so
+    /// the same address is valid on mainnet and every testnet.
+    pub fn from_unlock_hash(hash: [u8; ADDRESS_HASH_LENGTH]) -> Self { Address(H256::from(hash)) }
+
+    /// Whether `s` is a well-formed address whose checksum matches, eg. to validate user input before sending it
+    /// to a node. `Address::from_str` tells what is wrong with it.
+    pub fn is_valid_checksum(s: &str) -> bool { Address::from_str(s).is_ok() }
+
     pub fn str_without_prefix(&self) -> String {
         let bytes = self.0 .0.as_ref();
         let checksum = blake2b_checksum(bytes);
@@ -72,21 +83,28 @@ impl Encodable for Address {
     fn encode(&self, encoder: &mut Encoder) { self.0.encode(encoder) }
 }
 
-impl fmt::Display for Address {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "addr:{}", self.str_without_prefix()) }
+impl Decodable for Address {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> { Ok(Address(H256::decode(decoder)?)) }
 }
 
-impl fmt::Display for ParseAddressError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "Failed to parse Address: {:?}", self) }
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "addr:{}", self.str_without_prefix()) }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum ParseAddressError {
     #[serde(rename = "Address must begin with addr: prefix")]
+    #[error("Failed to parse Address: must begin with addr: prefix")]
     MissingPrefix,
+    /// Prefixed like another type, eg. `h:` of a hash or `txid:` of a transaction ID
+    #[error("Failed to parse Address: must begin with addr: prefix, found {0}:")]
+    WrongPrefix(String),
+    #[error("Failed to parse Address: invalid hex encoding: {0}")]
     InvalidHexEncoding(String),
+    #[error("Failed to parse Address: invalid checksum")]
     InvalidChecksum,
-    InvalidLength,
+    #[error("Failed to parse Address: invalid length {found}, expected {expected}")]
+    InvalidLength { expected: usize, found: usize },
 }
 
 impl From<FromHexError> for ParseAddressError {
@@ -97,31 +115,46 @@ impl FromStr for Address {
     type Err = ParseAddressError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !s.starts_with("addr:") {
-            return Err(ParseAddressError::MissingPrefix);
-        }
-
-        let without_prefix = &s[ADDRESS_CHECKSUM_LENGTH - 1..];
-        if without_prefix.len() != (ADDRESS_HASH_LENGTH + ADDRESS_CHECKSUM_LENGTH) * 2 {
-            return Err(ParseAddressError::InvalidLength);
+        let without_prefix = match s.strip_prefix(ADDRESS_PREFIX) {
+            Some(without_prefix) => without_prefix,
+            None => {
+                return Err(match s.split_once(':') {
+                    Some((prefix, _)) => ParseAddressError::WrongPrefix(prefix.to_owned()),
+                    None => ParseAddressError::MissingPrefix,
+                })
+            },
+        };
+
+        let expected = (ADDRESS_HASH_LENGTH + ADDRESS_CHECKSUM_LENGTH) * 2;
+        if without_prefix.len() != expected {
+            return Err(ParseAddressError::InvalidLength {
+                expected,
+                found: without_prefix.len(),
+            });
         }
 
-        let (address_hex, checksum_hex) = without_prefix.split_at(ADDRESS_HASH_LENGTH * 2);
+        let mut bytes = [0u8; ADDRESS_HASH_LENGTH + ADDRESS_CHECKSUM_LENGTH];
+        hex::decode_to_slice(without_prefix, &mut bytes)?;
+        let (address_bytes, checksum_bytes) = bytes.split_at(ADDRESS_HASH_LENGTH);
 
-        let address_bytes: [u8; ADDRESS_HASH_LENGTH] = hex::decode(address_hex)
-            .map_err(ParseAddressError::from)?
-            .try_into()
-            .map_err(|_| ParseAddressError::InvalidLength)?;
-
-        let checksum = hex::decode(checksum_hex).map_err(ParseAddressError::from)?;
-        let checksum_bytes: [u8; ADDRESS_CHECKSUM_LENGTH] =
-            checksum.try_into().map_err(|_| ParseAddressError::InvalidLength)?;
-
-        if checksum_bytes != blake2b_checksum(&address_bytes) {
+        if checksum_bytes != blake2b_checksum(address_bytes) {
             return Err(ParseAddressError::InvalidChecksum);
         }
 
-        Ok(Address(H256::from(address_bytes)))
+        Ok(Address::try_from(address_bytes)?)
+    }
+}
+
+/// An address from the bytes of its unlock hash, see `Address::from_unlock_hash`
+impl TryFrom<&[u8]> for Address {
+    type Error = ParseAddressError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let hash: [u8; ADDRESS_HASH_LENGTH] = bytes.try_into().map_err(|_| ParseAddressError::InvalidLength {
+            expected: ADDRESS_HASH_LENGTH,
+            found: bytes.len(),
+        })?;
+        Ok(Address::from_unlock_hash(hash))
     }
 }
 
@@ -137,7 +170,7 @@ pub fn v1_standard_address_from_pubkey(pubkey: &PublicKey) -> Address {
     Address(hash)
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct BlockID(pub H256);
 
 impl From<BlockID> for H256 {
@@ -148,6 +181,25 @@ impl From<H256> for BlockID {
     fn from(h256: H256) -> Self { BlockID(h256) }
 }
 
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseBlockIDError {
+    #[error("Failed to parse BlockID: must begin with bid: prefix")]
+    MissingPrefix,
+    #[error("Failed to parse BlockID: invalid hex encoding: {0}")]
+    InvalidHexEncoding(String),
+}
+
+impl FromStr for BlockID {
+    type Err = ParseBlockIDError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex_str = s.strip_prefix("bid:").ok_or(ParseBlockIDError::MissingPrefix)?;
+        H256::from_str(hex_str)
+            .map(BlockID)
+            .map_err(|e| ParseBlockIDError::InvalidHexEncoding(e.to_string()))
+    }
+}
+
 impl<'de> Deserialize<'de> for BlockID {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -166,13 +218,7 @@ impl<'de> Deserialize<'de> for BlockID {
             where
                 E: serde::de::Error,
             {
-                if let Some(hex_str) = value.strip_prefix("bid:") {
-                    H256::from_str(hex_str)
-                        .map(BlockID)
-                        .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
-                } else {
-                    Err(E::invalid_value(serde::de::Unexpected::Str(value), &self))
-                }
+                BlockID::from_str(value).map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
             }
         }
 
@@ -193,21 +239,329 @@ impl fmt::Display for BlockID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "bid:{}", self.0) }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseHashIDError {
+    #[error("Failed to parse ID: must begin with {0}: prefix")]
+    MissingPrefix(&'static str),
+    #[error("Failed to parse ID: invalid hex encoding: {0}")]
+    InvalidHexEncoding(String),
+}
+
+/// Defines a newtype of `H256` for IDs that Sia prefixes with `$prefix:` in JSON and text, like `BlockID`.
+/// The encoding is that of the hash itself.
+macro_rules! impl_prefixed_id {
+    ($(#[$meta:meta])* $name:ident, $prefix:literal) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub struct $name(pub H256);
+
+        impl $name {
+            pub const PREFIX: &'static str = $prefix;
+        }
+
+        impl From<$name> for H256 {
+            fn from(id: $name) -> Self { id.0 }
+        }
+
+        impl From<H256> for $name {
+            fn from(h256: H256) -> Self { $name(h256) }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseHashIDError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let hex_str = s
+                    .strip_prefix(concat!($prefix, ":"))
+                    .ok_or(ParseHashIDError::MissingPrefix($prefix))?;
+                H256::from_str(hex_str)
+                    .map($name)
+                    .map_err(|e| ParseHashIDError::InvalidHexEncoding(e.to_string()))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}:{}", $prefix, self.0) }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let s = String::deserialize(deserializer)?;
+                $name::from_str(&s).map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+
+        impl Encodable for $name {
+            fn encode(&self, encoder: &mut Encoder) { self.0.encode(encoder) }
+        }
+
+        impl Decodable for $name {
+            fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> { H256::decode(decoder).map($name) }
+        }
+    };
+}
+
+impl_prefixed_id!(
+    /// ID of a transaction, `types.TransactionID` in Go, see `V2Transaction::txid`
+    TransactionID,
+    "txid"
+);
+impl_prefixed_id!(
+    /// ID of an event, `types.Hash256` in Go. The event of a transaction shares its ID.
+    EventID,
+    "h"
+);
+impl_prefixed_id!(
+    /// ID of a file contract, `types.FileContractID` in Go
+    FileContractID,
+    "fcid"
+);
+
+impl From<TransactionID> for EventID {
+    fn from(txid: TransactionID) -> Self { EventID(txid.0) }
+}
+
+/// A block identified by its height and ID, as returned by `ConsensusTipRequest`
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct ChainIndex {
     pub height: u64,
     pub id: BlockID,
 }
 
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseChainIndexError {
+    #[error("Failed to parse ChainIndex: expected <height>::<hex id>")]
+    MissingSeparator,
+    #[error("Failed to parse ChainIndex: invalid height: {0}")]
+    InvalidHeight(String),
+    #[error("Failed to parse ChainIndex: {0}")]
+    InvalidBlockID(#[from] ParseBlockIDError),
+}
+
+/// Formats as `<height>::<hex id>` without the `bid:` prefix, eg. `42::0123..cdef`, like Go's `"%d::%x"`
+impl fmt::Display for ChainIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}::{}", self.height, self.id.0) }
+}
+
+impl FromStr for ChainIndex {
+    type Err = ParseChainIndexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (height, id) = s.split_once("::").ok_or(ParseChainIndexError::MissingSeparator)?;
+        Ok(ChainIndex {
+            height: height
+                .parse()
+                .map_err(|e: std::num::ParseIntError| ParseChainIndexError::InvalidHeight(e.to_string()))?,
+            id: H256::from_str(id)
+                .map(BlockID)
+                .map_err(|e| ParseBlockIDError::InvalidHexEncoding(e.to_string()))?,
+        })
+    }
+}
+
 // TODO unit test
 impl Encodable for ChainIndex {
     fn encode(&self, encoder: &mut Encoder) {
         encoder.write_u64(self.height);
-        let block_id: H256 = self.id.clone().into();
-        block_id.encode(encoder);
+        self.id.0.encode(encoder);
+    }
+}
+
+impl Decodable for ChainIndex {
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodingError> {
+        Ok(ChainIndex {
+            height: decoder.read_u64()?,
+            id: BlockID(H256::decode(decoder)?),
+        })
     }
 }
 
+/// The Merkle accumulator committing to every state element, see `StateElement`
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementAccumulator {
+    pub num_leaves: u64,
+    /// Roots of the perfect subtrees, one for each bit set in `num_leaves`
+    #[serde_as(as = "Vec<FromInto<PrefixedH256>>")]
+    pub trees: Vec<H256>,
+}
+
+/// The consensus state as of a block, as returned by `ConsensusTipStateRequest`.
+///
+/// This corresponds to the `consensus.State` type in Go. The work and difficulty values are 256-bit integers and
+/// kept as the decimal strings walletd returns.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsensusState {
+    pub index: ChainIndex,
+    /// Timestamps of the last 11 blocks, newest first
+    pub prev_timestamps: Vec<DateTime<Utc>>,
+    pub depth: BlockID,
+    pub child_target: BlockID,
+    pub siafund_pool: Currency,
+    /// Nanoseconds, as Go serializes a `time.Duration`
+    pub oak_time: u64,
+    pub oak_target: BlockID,
+    pub foundation_primary_address: Address,
+    pub foundation_failsafe_address: Address,
+    pub total_work: String,
+    pub difficulty: String,
+    pub oak_work: String,
+    pub elements: ElementAccumulator,
+    pub attestations: u64,
+}
+
+/// The parameters of a Sia network, as returned by `ConsensusNetworkRequest`.
+///
+/// This corresponds to the `consensus.Network` type in Go. Durations are nanoseconds, as Go serializes a
+/// `time.Duration`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Network {
+    /// eg. `mainnet` or `zen`
+    pub name: String,
+    pub initial_coinbase: Currency,
+    pub minimum_coinbase: Currency,
+    pub initial_target: BlockID,
+    pub block_interval: u64,
+    /// Blocks until a miner payout, siafund claim or contract payout can be spent
+    pub maturity_delay: u64,
+    pub hardfork_dev_addr: HardforkDevAddr,
+    pub hardfork_tax: HardforkHeight,
+    pub hardfork_storage_proof: HardforkHeight,
+    pub hardfork_oak: HardforkOak,
+    #[serde(rename = "hardforkASIC")]
+    pub hardfork_asic: HardforkAsic,
+    pub hardfork_foundation: HardforkFoundation,
+    #[serde(rename = "hardforkV2")]
+    pub hardfork_v2: HardforkV2,
+}
+
+impl Network {
+    /// Height at which an output created at `height` by a payout, eg. a miner payout, can be spent
+    pub fn maturity_height(&self, height: u64) -> u64 { height.saturating_add(self.maturity_delay) }
+
+    /// Whether blocks at `height` may contain v2 transactions
+    pub fn v2_allowed(&self, height: u64) -> bool { height >= self.hardfork_v2.allow_height }
+
+    /// Whether blocks at `height` may only contain v2 transactions
+    pub fn v2_required(&self, height: u64) -> bool { height >= self.hardfork_v2.require_height }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HardforkHeight {
+    pub height: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardforkDevAddr {
+    pub height: u64,
+    pub old_address: Address,
+    pub new_address: Address,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardforkOak {
+    pub height: u64,
+    pub fix_height: u64,
+    pub genesis_timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardforkAsic {
+    pub height: u64,
+    /// Nanoseconds
+    pub oak_time: u64,
+    pub oak_target: BlockID,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardforkFoundation {
+    pub height: u64,
+    pub primary_address: Address,
+    pub failsafe_address: Address,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardforkV2 {
+    pub allow_height: u64,
+    pub require_height: u64,
+}
+
+/// A block with its transactions, as returned by `ConsensusBlockRequest`.
+///
+/// This corresponds to the `types.Block` type in Go. Blocks from the v2 hardfork onwards carry their v2
+/// transactions and the commitment to the state they apply to in `v2`.
+// Go encodes empty slices as `null`
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    #[serde(rename = "parentID")]
+    pub parent_id: BlockID,
+    pub nonce: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    pub miner_payouts: Vec<SiacoinOutput>,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    pub transactions: Vec<V1Transaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub v2: Option<V2BlockData>,
+}
+
+impl Block {
+    /// The v2 transactions of the block, empty before the v2 hardfork
+    pub fn v2_transactions(&self) -> &[V2Transaction] { self.v2.as_ref().map_or(&[], |v2| v2.transactions.as_slice()) }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct V2BlockData {
+    pub height: u64,
+    /// Commits to the parent state and the block's contents
+    #[serde_as(as = "FromInto<PrefixedH256>")]
+    pub commitment: H256,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    pub transactions: Vec<V2Transaction>,
+}
+
+/// The transactions in a node's txpool, not yet confirmed in a block.
+///
+/// Returned by walletd's `TxpoolTransactionsRequest`. Kept apart from the client so the spend checks of `utxo`
+/// are available without it.
+// Go encodes empty slices as `null`
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TxpoolTransactions {
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    pub transactions: Vec<V1Transaction>,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    pub v2transactions: Vec<V2Transaction>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventV1Transaction {
@@ -218,6 +572,7 @@ pub struct EventV1Transaction {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct EventV1ContractResolution {
     pub parent: FileContractElementV1,
     pub siacoin_element: SiacoinElement,
@@ -242,11 +597,9 @@ pub enum EventType {
     V2ContractResolution,
 }
 
-#[serde_as]
 #[derive(Clone, Debug, Serialize)]
 pub struct Event {
-    #[serde_as(as = "FromInto<PrefixedH256>")]
-    pub id: H256,
+    pub id: EventID,
     pub index: ChainIndex,
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "maturityHeight")]
@@ -265,7 +618,7 @@ impl<'de> Deserialize<'de> for Event {
     {
         #[derive(Deserialize, Debug)]
         struct EventHelper {
-            id: PrefixedH256,
+            id: EventID,
             index: ChainIndex,
             timestamp: DateTime<Utc>,
             #[serde(rename = "maturityHeight")]
@@ -293,16 +646,16 @@ impl<'de> Deserialize<'de> for Event {
             EventType::V2Transaction => serde_json::from_value::<V2Transaction>(helper.data)
                 .map(EventDataWrapper::V2Transaction)
                 .map_err(serde::de::Error::custom),
-            EventType::V1ContractResolution => {
-                return Err(serde::de::Error::custom("V1ContractResolution not supported"))
-            },
+            EventType::V1ContractResolution => serde_json::from_value::<EventV1ContractResolution>(helper.data)
+                .map(EventDataWrapper::EventV1ContractResolution)
+                .map_err(serde::de::Error::custom),
             EventType::V2ContractResolution => serde_json::from_value::<EventV2ContractResolution>(helper.data)
                 .map(|data| EventDataWrapper::V2FileContractResolution(Box::new(data)))
                 .map_err(serde::de::Error::custom),
         }?;
 
         Ok(Event {
-            id: helper.id.into(),
+            id: helper.id,
             index: helper.index,
             timestamp: helper.timestamp,
             maturity_height: helper.maturity_height,
@@ -0,0 +1,323 @@
+#[cfg(feature = "http")] use crate::http::client::ApiClientError;
+use crate::transaction::{Currency, SiacoinElement, SiacoinOutput, V2Transaction};
+use crate::types::{Event, EventDataWrapper, TransactionID, TxpoolTransactions, H256};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// Number of branches `SelectionStrategy::BranchAndBound` explores before giving up
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// Filters siacoin UTXOs down to those that can be spent by a new transaction.
+///
+/// An output is spendable if:
+/// - it is mature, ie. its `maturity_height` is at most the height of the next block
+/// - it has at least `min_confirmations` confirmations
+/// - it is not already spent by a transaction in the txpool
+///
+/// walletd only returns outputs of the confirmed UTXO set, so every output has at least one confirmation.
+/// The UTXO endpoints do not include the height an output was created at, so it is taken from the events of
+/// the address, see `confirmation_heights`. Outputs missing from them count as 1 confirmation.
+#[derive(Clone, Debug, Default)]
+pub struct SpendableFilter {
+    /// The current consensus tip height
+    pub height: u64,
+    pub min_confirmations: u64,
+    /// Known confirmation heights by output ID, see `confirmation_heights`
+    pub confirmation_heights: HashMap<H256, u64>,
+    /// IDs of outputs spent by unconfirmed transactions, see `txpool_spent_ids`
+    pub pending_spends: HashSet<H256>,
+}
+
+impl SpendableFilter {
+    pub fn new(height: u64) -> Self {
+        SpendableFilter {
+            height,
+            ..Default::default()
+        }
+    }
+
+    pub fn min_confirmations(mut self, min_confirmations: u64) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    pub fn confirmation_heights(mut self, confirmation_heights: HashMap<H256, u64>) -> Self {
+        self.confirmation_heights = confirmation_heights;
+        self
+    }
+
+    pub fn exclude_txpool(mut self, txpool: &TxpoolTransactions) -> Self {
+        self.pending_spends.extend(txpool_spent_ids(txpool));
+        self
+    }
+
+    pub fn is_mature(&self, utxo: &SiacoinElement) -> bool { utxo.maturity_height <= self.height.saturating_add(1) }
+
+    pub fn confirmations(&self, utxo: &SiacoinElement) -> u64 {
+        match self.confirmation_heights.get(&utxo.state_element.id) {
+            Some(confirmed_at) if *confirmed_at > self.height => 0,
+            Some(confirmed_at) => (self.height - confirmed_at).saturating_add(1),
+            None => 1,
+        }
+    }
+
+    pub fn is_spendable(&self, utxo: &SiacoinElement) -> bool {
+        self.is_mature(utxo)
+            && self.confirmations(utxo) >= self.min_confirmations
+            && !self.pending_spends.contains(&utxo.state_element.id)
+    }
+
+    pub fn filter(&self, utxos: Vec<SiacoinElement>) -> Vec<SiacoinElement> {
+        utxos.into_iter().filter(|utxo| self.is_spendable(utxo)).collect()
+    }
+}
+
+/// IDs of all siacoin outputs spent by the transactions currently in the txpool
+pub fn txpool_spent_ids(txpool: &TxpoolTransactions) -> HashSet<H256> {
+    let v1_spends = txpool
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.siacoin_inputs.iter().map(|input| input.parent_id));
+    let v2_spends = txpool
+        .v2transactions
+        .iter()
+        .flat_map(|tx| tx.siacoin_inputs.iter().map(|input| input.parent.state_element.id));
+    v1_spends.chain(v2_spends).collect()
+}
+
+/// IDs of all siafund outputs spent by the transactions currently in the txpool
+pub fn txpool_spent_siafund_ids(txpool: &TxpoolTransactions) -> HashSet<H256> {
+    let v1_spends = txpool
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.siafund_inputs.iter().map(|input| input.parent_id));
+    let v2_spends = txpool
+        .v2transactions
+        .iter()
+        .flat_map(|tx| tx.siafund_inputs.iter().map(|input| input.parent.state_element.id));
+    v1_spends.chain(v2_spends).collect()
+}
+
+/// Confirmation heights of the siacoin outputs created by `events`, by output ID.
+///
+/// Payouts and contract resolutions carry the created `SiacoinElement`. The outputs of v1 and v2 transactions are
+/// identified by the IDs computed from the transaction, so every output an address received is covered.
+pub fn confirmation_heights(events: &[Event]) -> HashMap<H256, u64> {
+    let mut heights = HashMap::new();
+    for event in events {
+        let height = event.index.height;
+        match &event.data {
+            EventDataWrapper::MinerPayout(payout)
+            | EventDataWrapper::FoundationPayout(payout)
+            | EventDataWrapper::ClaimPayout(payout) => {
+                heights.insert(payout.siacoin_element.state_element.id, height);
+            },
+            EventDataWrapper::V2FileContractResolution(resolution) => {
+                heights.insert(resolution.siacoin_element.state_element.id, height);
+            },
+            EventDataWrapper::EventV1ContractResolution(resolution) => {
+                heights.insert(resolution.siacoin_element.state_element.id, height);
+            },
+            EventDataWrapper::V1Transaction(event_tx) => {
+                let tx = &event_tx.transaction;
+                heights.extend((0..tx.siacoin_outputs.len() as u64).map(|i| (tx.siacoin_output_id(i), height)));
+            },
+            EventDataWrapper::V2Transaction(tx) => {
+                let txid = tx.txid();
+                heights.extend((0..tx.siacoin_outputs.len() as u64).map(|i| (SiacoinOutput::id(txid, i), height)));
+            },
+        }
+    }
+    heights
+}
+
+/// A txpool transaction spending some of the same outputs as the transaction being checked
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxpoolConflict {
+    /// ID of the conflicting transaction in the txpool
+    pub txid: TransactionID,
+    /// IDs of the outputs spent by both transactions
+    pub output_ids: Vec<H256>,
+}
+
+/// Find the txpool transactions that double spend any siacoin input of `tx`.
+///
+/// `tx` itself is ignored if it was already broadcast. A non-empty result means `tx` will be rejected
+/// or evicted, so it should be rebuilt with different inputs.
+pub fn find_txpool_conflicts(tx: &V2Transaction, txpool: &TxpoolTransactions) -> Vec<TxpoolConflict> {
+    let txid = tx.txid();
+    let inputs: HashSet<H256> = tx
+        .siacoin_inputs
+        .iter()
+        .map(|input| input.parent.state_element.id)
+        .collect();
+    let conflict = |pool_txid: TransactionID, spent: Vec<H256>| {
+        let output_ids: Vec<H256> = spent.into_iter().filter(|id| inputs.contains(id)).collect();
+        if pool_txid == txid || output_ids.is_empty() {
+            return None;
+        }
+        Some(TxpoolConflict {
+            txid: pool_txid,
+            output_ids,
+        })
+    };
+
+    let v1_conflicts = txpool.transactions.iter().filter_map(|pool_tx| {
+        let spent = pool_tx.siacoin_inputs.iter().map(|input| input.parent_id).collect();
+        conflict(pool_tx.txid(), spent)
+    });
+    let v2_conflicts = txpool.v2transactions.iter().filter_map(|pool_tx| {
+        let spent = pool_tx
+            .siacoin_inputs
+            .iter()
+            .map(|input| input.parent.state_element.id)
+            .collect();
+        conflict(pool_tx.txid(), spent)
+    });
+    v1_conflicts.chain(v2_conflicts).collect()
+}
+
+#[derive(Debug, Error)]
+pub enum CoinSelectionError {
+    #[cfg(feature = "http")]
+    #[error("CoinSelectionError error: {0}")]
+    Client(#[from] ApiClientError),
+    #[error("CoinSelectionError error: UTXOs total {} hastings, {} required", .available.0, .required.0)]
+    InsufficientFunds { available: Currency, required: Currency },
+}
+
+/// How `select_utxos` picks the outputs to spend
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SelectionStrategy {
+    /// Spend the largest outputs first, minimizing the number of inputs
+    #[default]
+    LargestFirst,
+    /// Search for a set of outputs exceeding the amount by at most `tolerance`, so no change output is needed.
+    /// `tolerance` is typically the fee of adding a change output. Falls back to `LargestFirst` if no such set
+    /// is found within a bounded number of tries.
+    BranchAndBound { tolerance: Currency },
+}
+
+/// The outputs chosen by `select_utxos`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoinSelection {
+    pub selected: Vec<SiacoinElement>,
+    /// The selected value exceeding the requested amount. With `SelectionStrategy::BranchAndBound` this is at
+    /// most `tolerance` if a match was found, and is usually left to the miner fee instead of a change output.
+    pub change: Currency,
+}
+
+impl CoinSelection {
+    pub fn total(&self) -> Currency { self.selected.iter().map(|utxo| utxo.siacoin_output.value).sum() }
+}
+
+/// Choose outputs from `utxos` covering `amount`, which should include the miner fee.
+///
+/// `utxos` are expected to be spendable already, see `SpendableFilter`.
+pub fn select_utxos(
+    mut utxos: Vec<SiacoinElement>,
+    amount: Currency,
+    strategy: SelectionStrategy,
+) -> Result<CoinSelection, CoinSelectionError> {
+    let value = |utxo: &SiacoinElement| utxo.siacoin_output.value.0;
+    let available = utxos.iter().fold(0u128, |sum, utxo| sum.saturating_add(value(utxo)));
+    if available < amount.0 {
+        return Err(CoinSelectionError::InsufficientFunds {
+            available: Currency(available),
+            required: amount,
+        });
+    }
+    utxos.sort_by_key(|utxo| Reverse(value(utxo)));
+
+    let indices = match strategy {
+        SelectionStrategy::BranchAndBound { tolerance } => {
+            let values: Vec<u128> = utxos.iter().map(value).collect();
+            BranchAndBound::new(&values, amount.0, tolerance.0).search()
+        },
+        SelectionStrategy::LargestFirst => None,
+    };
+    let selected: Vec<SiacoinElement> = match indices {
+        Some(indices) => indices.into_iter().map(|i| utxos[i].clone()).collect(),
+        None => {
+            let mut sum = 0u128;
+            utxos
+                .into_iter()
+                .take_while(|utxo| {
+                    let needed = sum < amount.0;
+                    sum = sum.saturating_add(value(utxo));
+                    needed
+                })
+                .collect()
+        },
+    };
+    let total = selected.iter().fold(0u128, |sum, utxo| sum + value(utxo));
+    Ok(CoinSelection {
+        selected,
+        change: Currency(total - amount.0),
+    })
+}
+
+/// Depth first search over including or excluding each output, largest first, for the set with the least
+/// excess over `target` within `tolerance`.
+///
+/// The search keeps its own stack instead of recursing, so its depth, up to the number of outputs, is not
+/// limited by the thread's stack.
+struct BranchAndBound<'a> {
+    values: &'a [u128],
+    target: u128,
+    tolerance: u128,
+    /// `remaining[i]` is the sum of `values[i..]`, used to prune branches that can not reach `target`
+    remaining: Vec<u128>,
+}
+
+impl<'a> BranchAndBound<'a> {
+    fn new(values: &'a [u128], target: u128, tolerance: u128) -> Self {
+        let mut remaining = vec![0u128; values.len() + 1];
+        for i in (0..values.len()).rev() {
+            remaining[i] = remaining[i + 1].saturating_add(values[i]);
+        }
+        BranchAndBound {
+            values,
+            target,
+            tolerance,
+            remaining,
+        }
+    }
+
+    fn search(self) -> Option<Vec<usize>> {
+        let mut best: Option<(u128, Vec<usize>)> = None;
+        let mut current = Vec::new();
+        // branches still to explore: the next output to decide on, the sum of the outputs included so far, the
+        // length `current` had at the parent and the output the branch includes, if any
+        let mut branches = vec![(0usize, 0u128, 0usize, None)];
+        let mut tries = 0;
+        while let Some((i, sum, depth, included)) = branches.pop() {
+            tries += 1;
+            if tries > BNB_MAX_TRIES || matches!(best, Some((0, _))) {
+                break;
+            }
+            current.truncate(depth);
+            current.extend(included);
+            if sum >= self.target {
+                let excess = sum - self.target;
+                let better = match &best {
+                    Some((best, indices)) => (excess, current.len()) < (*best, indices.len()),
+                    None => true,
+                };
+                if excess <= self.tolerance && better {
+                    best = Some((excess, current.clone()));
+                }
+                // adding outputs only increases the excess
+                continue;
+            }
+            if i == self.values.len() || sum.saturating_add(self.remaining[i]) < self.target {
+                continue;
+            }
+            // pushed last so including `values[i]` is explored first
+            branches.push((i + 1, sum, current.len(), None));
+            branches.push((i + 1, sum.saturating_add(self.values[i]), current.len(), Some(i)));
+        }
+        best.map(|(_, indices)| indices)
+    }
+}